@@ -0,0 +1,46 @@
+// Compares two texts line by line. Lines only in `expected` start with `- `, lines only in `actual`
+// with `+ ` and lines in both with two spaces.
+pub fn lines(expected: &str, actual: &str) -> Vec<String> {
+    let (expected, actual) = (split(expected), split(actual));
+
+    // The longest common subsequence of lines, from the end, so the lines that stayed the same
+    // can be found going forwards.
+    let mut common = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            lines.push(format!("  {}", expected[i]));
+            (i, j) = (i + 1, j + 1);
+        } else if j == actual.len() || i < expected.len() && common[i + 1][j] >= common[i][j + 1] {
+            lines.push(format!("- {}", expected[i]));
+            i += 1;
+        } else {
+            lines.push(format!("+ {}", actual[j]));
+            j += 1;
+        }
+    }
+
+    lines
+}
+
+// Empty text has no lines, rather than one empty line.
+fn split(text: &str) -> Vec<&str> {
+    if text.is_empty() {
+        vec![]
+    } else {
+        text.split('\n').collect()
+    }
+}
@@ -0,0 +1,303 @@
+use crate::lexer::{LexError, Lexer, Token, TokenType, Trivia};
+
+// How far each level of `{`, `(` and `[` is indented.
+const INDENT: &str = "    ";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    // A name, keyword or number, e.g. `print`, `rest...` or `-1.5`.
+    Word,
+    String,
+    // An operator, e.g. `+`, `==`, `=>` or `&&`.
+    Operator,
+    Dot,
+    // `..` or `..=`.
+    Range,
+    Comma,
+    Colon,
+    Open,
+    Close,
+}
+
+// A piece of code as it was written, e.g. a token or a comma.
+#[derive(Debug, Clone)]
+struct Piece {
+    kind: Kind,
+    text: String,
+}
+
+// Formats Milo code: one statement per line, `//` comments, consistent spacing and indentation.
+// Spacing that changes how code is lexed, e.g. between a name and the `(` that calls it, is kept.
+pub fn format(code: &str) -> Result<String, LexError> {
+    let mut lexer = Lexer::default();
+    lexer.keep_trivia = true;
+    let tokens = lexer.lex_code(code.to_string())?;
+
+    let mut output = String::new();
+    let mut depth: usize = 0;
+    let mut blank = false;
+
+    for line in lines(&lexer, tokens) {
+        let Some(line) = line else {
+            blank = !output.is_empty();
+            continue;
+        };
+
+        if blank {
+            output.push('\n');
+            blank = false;
+        }
+
+        let closers = line
+            .code
+            .iter()
+            .take_while(|(piece, _)| piece.kind == Kind::Close)
+            .count();
+
+        let indent = if line.code.is_empty() {
+            depth
+        } else {
+            depth.saturating_sub(closers)
+        };
+
+        let code = join(&line.code);
+
+        if !line.shebang {
+            output.push_str(&INDENT.repeat(indent));
+        }
+
+        output.push_str(&code);
+
+        // Comments after code stay in the same column where they can, so they stay aligned.
+        if let Some((column, comment)) = line.comment {
+            if !code.is_empty() {
+                let width = INDENT.len() * indent + code.chars().count();
+                output.push_str(&" ".repeat(column.saturating_sub(width).max(1)));
+            }
+
+            output.push_str(&comment);
+        }
+
+        output.push('\n');
+
+        for (piece, _) in &line.code {
+            match piece.kind {
+                Kind::Open => depth += 1,
+                Kind::Close => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+// A statement, or a line with only a comment.
+struct Line {
+    // The pieces of code, and whether there was space before each of them.
+    code: Vec<(Piece, bool)>,
+    // The column the comment started in, counted from the start of the statement, and the comment.
+    comment: Option<(usize, String)>,
+    // `#!/usr/bin/env milo`, which is kept as it is.
+    shebang: bool,
+}
+
+// The lines to print, with `None` for blank lines. The lexer starts a new line of tokens at each
+// `;`, so each statement gets its own line.
+fn lines(lexer: &Lexer, tokens: Vec<Vec<Token>>) -> Vec<Option<Line>> {
+    let mut lines: Vec<Option<Line>> = vec![];
+    // The column the last statement with code started in.
+    let mut previous_start = 0;
+
+    for (i, tokens) in tokens.into_iter().enumerate() {
+        let number = lexer.line_numbers[i];
+        let after_semicolon = i > 0 && lexer.line_numbers[i - 1] == number;
+
+        let mut items: Vec<(usize, Result<Token, Trivia>)> = lexer.columns[i]
+            .iter()
+            .copied()
+            .zip(tokens.into_iter().map(Ok))
+            .chain(
+                lexer.trivia[i]
+                    .iter()
+                    .map(|(column, trivia)| (*column, Err(trivia.clone()))),
+            )
+            .collect();
+        items.sort_by_key(|(column, _)| *column);
+
+        // The column the statement starts in, after the `;` before it and any space after that.
+        let start = items
+            .iter()
+            .find(|(_, item)| !matches!(item, Err(Trivia::Space(_))))
+            .map_or(0, |(column, _)| *column);
+
+        let mut line = Line {
+            code: vec![],
+            comment: None,
+            shebang: false,
+        };
+        let mut space = false;
+
+        for (column, item) in items {
+            match item {
+                Ok(token) => {
+                    for piece in pieces(token) {
+                        line.code.push((piece, space));
+                        space = false;
+                    }
+                }
+                Err(Trivia::Space(_)) => space = true,
+                Err(Trivia::Comma) => {
+                    line.code.push((
+                        Piece {
+                            kind: Kind::Comma,
+                            text: ",".to_string(),
+                        },
+                        space,
+                    ));
+                    space = false;
+                }
+                Err(Trivia::Comment(comment)) if number == 0 && comment.starts_with("#!") => {
+                    line.comment = Some((0, comment.trim_end().to_string()));
+                    line.shebang = true;
+                }
+                Err(Trivia::Comment(comment)) => {
+                    line.comment = Some((column, normalize_comment(&comment)));
+                }
+            }
+        }
+
+        if line.code.is_empty() && !line.shebang {
+            match (line.comment.take(), lines.last_mut()) {
+                // `a; // comment` keeps the comment on the same line as `a`.
+                (Some((column, comment)), Some(Some(previous)))
+                    if after_semicolon && previous.comment.is_none() =>
+                {
+                    previous.comment = Some((column - previous_start, comment));
+                    continue;
+                }
+                (Some((column, comment)), _) => line.comment = Some((column - start, comment)),
+                // Nothing after a `;` isn't a blank line.
+                (None, _) if after_semicolon => continue,
+                (None, _) => {
+                    lines.push(None);
+                    continue;
+                }
+            }
+        } else {
+            line.comment =
+                (line.comment).map(|(column, comment)| (column.saturating_sub(start), comment));
+            previous_start = start;
+        }
+
+        lines.push(Some(line));
+    }
+
+    // Blank lines at the end aren't kept.
+    while matches!(lines.last(), Some(None)) {
+        lines.pop();
+    }
+
+    lines
+}
+
+// The pieces a token was written as. A call is a single token, e.g. `print(`, but two pieces.
+fn pieces(token: Token) -> Vec<Piece> {
+    let piece = |kind, text: &str| Piece {
+        kind,
+        text: text.to_string(),
+    };
+
+    match token.token_type {
+        TokenType::String => vec![piece(Kind::String, &format!("\"{}\"", token.value))],
+        TokenType::Identifier => match token.value.strip_suffix('(') {
+            Some(name) => vec![piece(Kind::Word, name), piece(Kind::Open, "(")],
+            None => vec![piece(Kind::Word, &token.value)],
+        },
+        TokenType::Operator => {
+            let kind = match token.value.as_str() {
+                "(" | "[" | "{" => Kind::Open,
+                // An index is lexed as `[]`, but written as `[`.
+                "[]" => return vec![piece(Kind::Open, "[")],
+                ")" | "]" | "}" => Kind::Close,
+                "." => Kind::Dot,
+                ".." | "..=" => Kind::Range,
+                ":" => Kind::Colon,
+                _ => Kind::Operator,
+            };
+
+            vec![piece(kind, &token.value)]
+        }
+        _ => vec![piece(Kind::Word, &token.value)],
+    }
+}
+
+// `# text` becomes `// text`. The text itself is kept as it was written.
+fn normalize_comment(comment: &str) -> String {
+    let comment = comment.trim_end();
+
+    match comment.strip_prefix('#') {
+        Some(text) => format!("//{}", text),
+        None => comment.to_string(),
+    }
+}
+
+// The code of a line, with a space between pieces where one belongs.
+fn join(code: &[(Piece, bool)]) -> String {
+    let mut text = String::new();
+    // Whether each open `{` is a map, whose braces don't get spaces inside of them.
+    let mut braces = vec![];
+
+    for (i, (piece, spaced)) in code.iter().enumerate() {
+        if i > 0 {
+            let (previous, _) = &code[i - 1];
+
+            let space = match (previous.kind, piece.kind) {
+                // A name directly followed by `(` calls it, and a value directly followed by `[`
+                // is indexed, so these keep whether they had space between them.
+                (Kind::Word | Kind::String | Kind::Close, Kind::Open) if piece.text != "{" => {
+                    *spaced
+                }
+                (Kind::Dot | Kind::Range, _) | (_, Kind::Dot | Kind::Range) => *spaced,
+                (_, Kind::Comma | Kind::Colon) => false,
+                (Kind::Open, Kind::Close) if previous.text == "{" => false,
+                (Kind::Open, _) if previous.text == "{" => !braces.last().copied().unwrap_or(false),
+                (_, Kind::Close) if piece.text == "}" => !braces.last().copied().unwrap_or(false),
+                (Kind::Open, _) | (_, Kind::Close) => false,
+                (Kind::Comma | Kind::Colon, _) => true,
+                (Kind::Operator, _) if previous.text == "!" => false,
+                _ => true,
+            };
+
+            if space {
+                text.push(' ');
+            }
+        }
+
+        match piece.text.as_str() {
+            "{" => {
+                let previous = i.checked_sub(1).map(|i| &code[i].0);
+
+                // `{` after a value or keyword starts a block or struct, otherwise it is a map.
+                let is_map = match previous {
+                    Some(previous) => match previous.kind {
+                        Kind::Word | Kind::String | Kind::Close => false,
+                        Kind::Operator => previous.text != "=>",
+                        _ => true,
+                    },
+                    None => true,
+                };
+
+                braces.push(is_map);
+            }
+            "}" => {
+                braces.pop();
+            }
+            _ => {}
+        }
+
+        text.push_str(&piece.text);
+    }
+
+    text
+}
@@ -0,0 +1,360 @@
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::diff;
+
+// What a script should print, from annotations in its comments or from the file next to it.
+struct Expected {
+    stdout: String,
+    stderr: String,
+    // Whether it came from annotations, which `--update` doesn't change.
+    annotated: bool,
+}
+
+// How a script is run, from a comment like
+// `// Run with `printf "Ada\n" | GREETING=Hi milo --allow-env examples/arguments.milo Ada`.`
+// Without one, scripts run with no arguments, input or permissions.
+#[derive(Default)]
+struct Invocation {
+    stdin: Option<String>,
+    env: Vec<(String, String)>,
+    // The flags before the script, e.g. `--allow-env`.
+    flags: Vec<String>,
+    // The script's own arguments, after it.
+    arguments: Vec<String>,
+}
+
+// Runs each script and compares what it prints with what it should print: the lines annotated
+// with `// Should print "text"`, `// Should print a new line` and `// ERROR: message`, or else
+// the contents of `name.out` next to `name.milo`, or nothing if it doesn't exist. A script that
+// fails has to say so with `// ERROR:`, failures are never taken from what it printed.
+// With `update`, the `.out` files of scripts that ran fine are written from what they print.
+// Scripts with a `// Skip: reason` comment aren't run. Returns whether every script printed
+// what it should.
+pub fn run(files: Vec<PathBuf>, filter: Option<&str>, update: bool) -> io::Result<bool> {
+    let milo = env::current_exe()?;
+    let (mut passed, mut updated, mut skipped, mut filtered_out) = (0, 0, 0, 0);
+    let mut failures = vec![];
+
+    for file in files {
+        if filter.is_some_and(|filter| !file.to_string_lossy().contains(filter)) {
+            filtered_out += 1;
+            continue;
+        }
+
+        let code = fs::read_to_string(&file)?;
+
+        if let Some(reason) = skip(&code) {
+            println!("{} ... \x1b[33mskipped\x1b[0m ({})", file.display(), reason);
+            skipped += 1;
+            continue;
+        }
+
+        let invocation = match invocation(&code) {
+            Ok(invocation) => invocation,
+            Err(error) => {
+                println!("{} ... \x1b[31mFAILED\x1b[0m", file.display());
+                failures.push((file, error));
+                continue;
+            }
+        };
+
+        let expected = expected(&code, &file)?;
+
+        let mut child = Command::new(&milo)
+            .args(&invocation.flags)
+            .arg(&file)
+            .args(&invocation.arguments)
+            .envs(invocation.env)
+            .env_remove("MILO_DEBUG")
+            .stdin(match invocation.stdin {
+                Some(_) => Stdio::piped(),
+                None => Stdio::null(),
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        if let (Some(mut stdin), Some(input)) = (child.stdin.take(), invocation.stdin) {
+            stdin.write_all(input.as_bytes())?;
+        }
+
+        let output = child.wait_with_output()?;
+
+        let stdout = strip_colors(&String::from_utf8_lossy(&output.stdout));
+        let stderr = strip_colors(&String::from_utf8_lossy(&output.stderr));
+
+        // Only scripts that are annotated with an error may fail.
+        let failed_as_expected = output.status.success() == expected.stderr.is_empty();
+
+        if stdout == expected.stdout && stderr == expected.stderr && failed_as_expected {
+            println!("{} ... \x1b[32mok\x1b[0m", file.display());
+            passed += 1;
+            continue;
+        }
+
+        if update && !expected.annotated && output.status.success() {
+            write(&file.with_extension("out"), &stdout)?;
+
+            println!("{} ... \x1b[33mupdated\x1b[0m", file.display());
+            updated += 1;
+            continue;
+        }
+
+        println!("{} ... \x1b[31mFAILED\x1b[0m", file.display());
+
+        let mut differences = vec![];
+
+        if !failed_as_expected {
+            differences.push(match output.status.code() {
+                Some(code) => format!("exited with code {}", code),
+                None => "was stopped by a signal".to_string(),
+            });
+        }
+
+        for (name, expected, actual) in [
+            ("stdout", &expected.stdout, &stdout),
+            ("stderr", &expected.stderr, &stderr),
+        ] {
+            if expected != actual {
+                differences.push(format!(
+                    "{} (- expected, + actual):\n{}",
+                    name,
+                    diff::lines(expected.trim_end(), actual.trim_end()).join("\n")
+                ));
+            }
+        }
+
+        failures.push((file, differences.join("\n\n")));
+    }
+
+    if !failures.is_empty() {
+        println!("\nFailures:");
+    }
+
+    for (file, differences) in &failures {
+        println!("\n--- {} ---\n{}", file.display(), differences);
+    }
+
+    let result = if failures.is_empty() {
+        "\x1b[32mok\x1b[0m"
+    } else {
+        "\x1b[31mFAILED\x1b[0m"
+    };
+
+    let count = |count, name| match count {
+        0 => String::new(),
+        count => format!("{} {}; ", count, name),
+    };
+
+    println!(
+        "\ntest result: {}. {} passed; {} failed; {}{}{} filtered out",
+        result,
+        passed,
+        failures.len(),
+        count(updated, "updated"),
+        count(skipped, "skipped"),
+        filtered_out
+    );
+
+    if !failures.is_empty() && !update {
+        println!("Run `milo test --golden --update` to accept what unannotated scripts print");
+    }
+
+    Ok(failures.is_empty())
+}
+
+fn expected(code: &str, file: &Path) -> io::Result<Expected> {
+    if let Some(expected) = annotations(code) {
+        return Ok(expected);
+    }
+
+    let read = |path: PathBuf| match fs::read_to_string(path) {
+        Ok(text) => Ok(text),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(error) => Err(error),
+    };
+
+    Ok(Expected {
+        stdout: read(file.with_extension("out"))?,
+        stderr: String::new(),
+        annotated: false,
+    })
+}
+
+// The output annotated in the comments of a script, in order, if there are any annotations.
+fn annotations(code: &str) -> Option<Expected> {
+    let mut stdout = String::new();
+    let mut stderr = String::new();
+
+    for comment in code.lines().filter_map(comment) {
+        let comment = comment.trim();
+
+        if comment.starts_with("ERROR: ") {
+            stderr.push_str(comment);
+            stderr.push('\n');
+        } else if comment.starts_with("Should print a new line") {
+            stdout.push('\n');
+        } else if let Some(text) = comment.strip_prefix("Should print \"") {
+            // Anything after the closing quote explains the line, e.g. `"1" (Cuts off the 0)`.
+            let end = text.rfind('"').unwrap_or(text.len());
+            stdout.push_str(&text[..end]);
+            stdout.push('\n');
+        }
+    }
+
+    (!stdout.is_empty() || !stderr.is_empty()).then_some(Expected {
+        stdout,
+        stderr,
+        annotated: true,
+    })
+}
+
+// The comment on a line, if it has one. A `//` inside of a string doesn't start one.
+fn comment(line: &str) -> Option<&str> {
+    let mut in_string = false;
+
+    for (i, char) in line.char_indices() {
+        match char {
+            '"' => in_string = !in_string,
+            '/' if !in_string && line[i + 1..].starts_with('/') => return Some(&line[i + 2..]),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+// The reason given by a `// Skip: reason` comment, for scripts that can't be checked.
+fn skip(code: &str) -> Option<&str> {
+    code.lines()
+        .filter_map(comment)
+        .find_map(|comment| comment.trim().strip_prefix("Skip: "))
+}
+
+fn invocation(code: &str) -> Result<Invocation, String> {
+    let Some(command) = code
+        .lines()
+        .filter_map(comment)
+        .find_map(|comment| comment.trim().strip_prefix("Run with `"))
+        .and_then(|rest| rest.split_once('`'))
+        .map(|(command, _)| command)
+    else {
+        return Ok(Invocation::default());
+    };
+
+    let mut words = words(command).into_iter().peekable();
+    let mut invocation = Invocation::default();
+
+    if words.next_if(|word| word == "printf").is_some() {
+        match (words.next(), words.next()) {
+            (Some(text), Some(pipe)) if pipe == "|" => invocation.stdin = Some(unescape(&text)),
+            _ => return Err(format!("Expected `printf \"text\" |` in `{}`", command)),
+        }
+    }
+
+    while let Some(variable) = words.next_if(|word| !word.starts_with('-') && word.contains('=')) {
+        let (name, value) = variable.split_once('=').unwrap_or_default();
+        invocation.env.push((name.to_string(), value.to_string()));
+    }
+
+    if words.next().as_deref() != Some("milo") {
+        return Err(format!("Expected `milo` in `{}`", command));
+    }
+
+    // The script itself is replaced by the file being tested, wherever it is run from.
+    invocation.flags = words
+        .by_ref()
+        .take_while(|word| !word.ends_with(".milo"))
+        .collect();
+    invocation.arguments = words.collect();
+
+    Ok(invocation)
+}
+
+// The words of a command, split on spaces outside of `"quotes"`. Quotes are removed, but
+// escapes like `\n` are kept as they are.
+fn words(command: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut in_quotes = false;
+    let mut chars = command.chars();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '"' => {
+                in_quotes = !in_quotes;
+                word.get_or_insert_with(String::new);
+            }
+            '\\' => {
+                let word = word.get_or_insert_with(String::new);
+                word.push(char);
+                word.extend(chars.next());
+            }
+            ' ' if !in_quotes => words.extend(word.take()),
+            _ => word.get_or_insert_with(String::new).push(char),
+        }
+    }
+
+    words.extend(word);
+    words
+}
+
+// The text `printf` prints for its format, e.g. a new line for `\n`.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            unescaped.push(char);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+// Writes what a script prints, or removes the file if it prints nothing.
+fn write(path: &Path, text: &str) -> io::Result<()> {
+    if !text.is_empty() {
+        return fs::write(path, text);
+    }
+
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+// Removes the escape codes that color text in terminals, e.g. `\x1b[31m`.
+fn strip_colors(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(char) = chars.next() {
+        if char != '\x1b' {
+            stripped.push(char);
+            continue;
+        }
+
+        for char in chars.by_ref() {
+            if char.is_ascii_alphabetic() {
+                break;
+            }
+        }
+    }
+
+    stripped
+}
@@ -9,6 +9,13 @@ pub enum TokenType {
     Number,
     String,
     Boolean,
+    Nil,
+    List,
+    Map,
+    Tuple,
+    Struct,
+    Enum,
+    Module,
     Operator,
 }
 
@@ -18,45 +25,111 @@ impl From<TokenType> for VariableType {
             TokenType::String => VariableType::String,
             TokenType::Number => VariableType::Number,
             TokenType::Boolean => VariableType::Boolean,
+            TokenType::Nil => VariableType::Nil,
+            TokenType::List => VariableType::List,
+            TokenType::Map => VariableType::Map,
+            TokenType::Tuple => VariableType::Tuple,
+            TokenType::Struct => VariableType::Struct,
+            TokenType::Enum => VariableType::Enum,
+            TokenType::Module => VariableType::Module,
             TokenType::Identifier | TokenType::Operator => panic!("Invalid type conversion."),
         }
     }
 }
 
+impl TokenType {
+    /// Whether a token of this type can be stored in a variable.
+    pub fn is_value(&self) -> bool {
+        !matches!(self, TokenType::Identifier | TokenType::Operator)
+    }
+}
+
 #[derive(Debug, Clone, Default, PartialEq)]
 pub struct Token {
     pub token_type: TokenType,
+    /// The text of the token. For a struct, this is the name of the struct, for an enum the name of
+    /// the enum and variant, e.g. `Shape.Circle`, and for a module the name it was imported as.
     pub value: String,
+    /// The elements of a list or tuple, the values of a map or struct, or the payload of an enum variant.
+    /// Empty for every other type.
+    pub items: Vec<Token>,
+    /// The keys of a map, the field names of a struct, or the names a module exports, in order.
+    /// `keys[i]` belongs to `items[i]`.
+    pub keys: Vec<Token>,
+}
+
+// Code that can't be split into tokens, e.g. a string without its closing `"`.
+#[derive(Debug)]
+pub struct LexError {
+    pub message: String,
+    // The line it happened on, starting at 0.
+    pub line: usize,
+}
+
+// Something in the code that isn't part of a token, e.g. a comment. Only kept when the lexer is
+// asked to, so the code can be put back together, which the formatter does.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trivia {
+    // One or more spaces or tabs.
+    Space(String),
+    Comma,
+    // `// text` or `# text`, or the shebang on the first line.
+    Comment(String),
 }
 
 #[derive(Default)]
 pub struct Lexer {
+    // The line in the code each line of tokens from the last `lex_code` comes from, starting at 0.
+    pub line_numbers: Vec<usize>,
+    // The column each token from the last `lex_code` starts in, line by line, starting at 0.
+    pub columns: Vec<Vec<usize>>,
+    // Whether to keep the spaces, commas and comments in `trivia`.
+    pub keep_trivia: bool,
+    // The trivia of each line of tokens from the last `lex_code`, with the column it starts in.
+    pub trivia: Vec<Vec<(usize, Trivia)>>,
+    local_trivia: Vec<(usize, Trivia)>,
     token: Token,
-    token_index: usize,
     local_tokens: Vec<Token>,
+    local_columns: Vec<usize>,
+    // The column of the character being lexed, and of the token being built.
+    column: usize,
+    token_start: usize,
     is_in_string: bool,
     is_in_number: bool,
+    // Whether there was a `,` after the last token, which separates it from what comes next.
+    is_after_comma: bool,
 }
 
 impl Lexer {
-    pub fn lex_code(&mut self, code: String) -> LexedTokenLines {
-        #[cfg(debug_assertions)]
-        println!("--- Code ---\n{}\n------------\n", code);
-
+    pub fn lex_code(&mut self, code: String) -> Result<LexedTokenLines, LexError> {
         let mut global_tokens: LexedTokenLines = vec![];
-        let lines = code.split(|c| c == '\n' || c == ';').collect::<Vec<&str>>();
-
-        for (line_index, line) in lines.clone().into_iter().enumerate() {
-            if line.is_empty() {
+        self.line_numbers.clear();
+        self.columns.clear();
+        self.trivia.clear();
+
+        for (number, line) in code.split('\n').enumerate() {
+            // A shebang (`#!/usr/bin/env milo`) lets scripts be run directly, but isn't Milo.
+            // It still takes up a line, so errors are reported on the right one.
+            if number == 0 && line.starts_with("#!") {
+                self.add_trivia(0, Trivia::Comment(line.to_string()));
+                self.end_statement(number, &mut global_tokens);
                 continue;
             }
 
-            self.token_index = 0;
+            // Where the statement being lexed starts. A `;` outside of strings and comments
+            // ends it, and the rest of the line is lexed as a statement of its own.
+            let mut statement_start = 0;
 
             // Filter away characters that you cant even have in strings.
             let chars: Vec<char> = line.chars().filter(|c| *c != '\r').collect();
 
             for (i, char) in chars.clone().into_iter().enumerate() {
+                self.column = i;
+
+                if self.token.value.is_empty() && !self.is_in_string {
+                    self.token_start = self.column;
+                }
+
                 // Strings.
                 if char == '"' {
                     if !self.is_in_string {
@@ -76,11 +149,101 @@ impl Lexer {
 
                 // Comment logic.
                 if char == '#' || (char == '/' && (i < chars.len() - 1 && chars[i + 1] == '/')) {
+                    // The comment goes on to the end of the line, past any `;` in it.
+                    let comment = chars[i..].iter().collect();
+                    self.add_trivia(self.column, Trivia::Comment(comment));
                     break;
                 }
 
+                if char == ';' {
+                    // Nothing between two `;`s isn't a statement.
+                    if i > statement_start {
+                        self.end_statement(number, &mut global_tokens);
+                    }
+
+                    statement_start = i + 1;
+                    continue;
+                }
+
+                if char == ' ' || char == '\t' {
+                    self.add_trivia(self.column, Trivia::Space(char.to_string()));
+                }
+
                 // Opening brackets.
-                if char == '(' || char == ' ' {
+                if char == '(' || char == ' ' || char == '\t' {
+                    // Parentheses directly after a name call it. The name and the `(` become one token, e.g. `print(`.
+                    if char == '('
+                        && self.token.token_type == TokenType::Identifier
+                        && !self.token.value.is_empty()
+                    {
+                        self.token.value += "(";
+                        self.push_token();
+                        continue;
+                    }
+
+                    self.push_token();
+
+                    // Other parentheses group values.
+                    if char == '(' {
+                        self.token.token_type = TokenType::Operator;
+                        self.token.value = char.to_string();
+                        self.push_token();
+                    }
+
+                    continue;
+                }
+
+                if char == ')' {
+                    self.push_token();
+
+                    self.token.token_type = TokenType::Operator;
+                    self.token.value = char.to_string();
+                    self.push_token();
+                    continue;
+                }
+
+                // Square brackets. A `[` directly after a value indexes into it, otherwise it opens a list.
+                if char == '[' || char == ']' {
+                    let is_index = char == '['
+                        && i > 0
+                        && (chars[i - 1].is_alphanumeric()
+                            || chars[i - 1] == '_'
+                            || chars[i - 1] == '"'
+                            || chars[i - 1] == ']');
+
+                    self.push_token();
+
+                    self.token.token_type = TokenType::Operator;
+                    self.token.value = if is_index {
+                        "[]".to_string()
+                    } else {
+                        char.to_string()
+                    };
+                    self.push_token();
+                    continue;
+                }
+
+                // Curly brackets and colons, used by maps.
+                if char == '{' || char == '}' || char == ':' {
+                    self.push_token();
+
+                    self.token.token_type = TokenType::Operator;
+                    self.token.value = char.to_string();
+                    self.push_token();
+                    continue;
+                }
+
+                // Field access and method calls. Dots that aren't followed by a name (e.g. `rest...`) stay part of the token.
+                if char == '.'
+                    && i > 0
+                    && i < chars.len() - 1
+                    && (chars[i - 1].is_alphanumeric() || "_\")]}".contains(chars[i - 1]))
+                    && (chars[i + 1].is_alphabetic() || chars[i + 1] == '_')
+                {
+                    self.push_token();
+
+                    self.token.token_type = TokenType::Operator;
+                    self.token.value = char.to_string();
                     self.push_token();
                     continue;
                 }
@@ -96,17 +259,28 @@ impl Lexer {
                     self.token.token_type = TokenType::Number;
                     self.is_in_number = true
                 } else if self.is_in_number && !char.is_numeric() {
-                    self.push_token();
+                    // A decimal point, e.g. `1.5`. Not a range (`1..5`) or a second point.
+                    let is_decimal_point = char == '.'
+                        && chars.get(i + 1).is_some_and(|next| next.is_numeric())
+                        && !self.token.value.contains('.');
+
+                    if !is_decimal_point {
+                        self.push_token();
+                    }
                 }
 
+                // A `-` right before a digit starts a negative number, unless it comes after a
+                // value, e.g. `x-1` or `f(2)-1`, where it subtracts.
                 if char == '-' && i < chars.len() - 1 && chars[i + 1].is_numeric() {
                     self.push_token();
 
-                    self.token.token_type = TokenType::Number;
-                    self.is_in_number = true;
+                    if !self.follows_value() {
+                        self.token.token_type = TokenType::Number;
+                        self.is_in_number = true;
 
-                    self.token.value += char.to_string().as_str();
-                    continue;
+                        self.token.value += char.to_string().as_str();
+                        continue;
+                    }
                 }
 
                 if char == '='
@@ -119,62 +293,122 @@ impl Lexer {
                     || char == '>'
                     || char == '<'
                 {
+                    // An operator right after a name ends it, e.g. `x*y`. `..=` stays one range.
+                    if self.token.token_type != TokenType::Operator && self.token.value != ".." {
+                        self.push_token();
+                    }
+
                     self.token.token_type = TokenType::Operator;
 
-                    if i >= chars.len() || chars[i + 1] != '=' {
+                    // Two character operators, e.g. `==`, `>=` or `=>`.
+                    let next = chars.get(i + 1);
+                    let is_first_of_two = next == Some(&'=') || char == '=' && next == Some(&'>');
+
+                    if !is_first_of_two {
                         self.token.value += char.to_string().as_str();
                         self.push_token();
                         continue;
                     }
                 }
 
-                let current_token = self.current_token(&lines, line_index);
-
-                if current_token == "false" || current_token == "true" {
-                    self.token.token_type = TokenType::Boolean;
-                }
-
-                if current_token == "&&" || current_token == "||" {
-                    self.token.token_type = TokenType::Operator;
-                }
-
-                // Ignore outside of strings.
-                if char == ')' || char == ',' {
+                // Ignore outside of strings, but end the token before it, e.g. `[a,b]`.
+                if char == ',' {
+                    self.push_token();
+                    self.is_after_comma = true;
+                    self.add_trivia(self.column, Trivia::Comma);
                     continue;
                 }
 
                 self.token.value += char.to_string().as_str();
             }
 
-            self.push_token();
-            global_tokens.push(self.local_tokens.clone());
-            self.local_tokens.clear();
+            if self.is_in_string {
+                *self = Lexer::default();
+
+                return Err(LexError {
+                    message: "String is missing a closing `\"`".to_string(),
+                    line: number,
+                });
+            }
+
+            // Blank lines are kept, so the lines after them are numbered right when running. Nothing
+            // after the last `;` isn't.
+            if statement_start < chars.len() || chars.is_empty() {
+                self.end_statement(number, &mut global_tokens);
+            }
         }
 
-        global_tokens
+        Ok(global_tokens)
     }
 
-    fn current_token(&self, lines: &[&str], line_index: usize) -> String {
-        let tokens = lines[line_index]
-            .split(|c: char| c == ' ' || c == '(')
-            .collect::<Vec<&str>>();
+    // Adds the tokens of the statement being lexed as a line of their own.
+    fn end_statement(&mut self, number: usize, lines: &mut LexedTokenLines) {
+        self.push_token();
+        lines.push(std::mem::take(&mut self.local_tokens));
+        self.line_numbers.push(number);
+        self.columns.push(std::mem::take(&mut self.local_columns));
+        self.trivia.push(std::mem::take(&mut self.local_trivia));
+        self.is_after_comma = false;
+    }
+
+    fn add_trivia(&mut self, column: usize, trivia: Trivia) {
+        if !self.keep_trivia {
+            return;
+        }
 
-        if self.token_index >= tokens.len() || lines[line_index].is_empty() {
-            return String::new();
+        // Spaces next to each other are kept together.
+        if let (Some((start, Trivia::Space(spaces))), Trivia::Space(space)) =
+            (self.local_trivia.last_mut(), &trivia)
+        {
+            if *start + spaces.chars().count() == column {
+                spaces.push_str(space);
+                return;
+            }
         }
 
-        tokens[self.token_index].trim().to_string()
+        self.local_trivia.push((column, trivia));
+    }
+
+    // Whether the last token is a value or closes one, so an operator after it has a left side.
+    fn follows_value(&self) -> bool {
+        let Some(token) = self.local_tokens.last().filter(|_| !self.is_after_comma) else {
+            return false;
+        };
+
+        match token.token_type {
+            // Calls, e.g. `print(`, and keywords followed by a value, e.g. `return -1`.
+            TokenType::Identifier => {
+                !token.value.ends_with('(')
+                    && !["return", "throw", "in", "if", "match"].contains(&token.value.as_str())
+            }
+            TokenType::Operator => [")", "]", "}"].contains(&token.value.as_str()),
+            _ => true,
+        }
     }
 
     fn push_token(&mut self) {
-        if self.token.value.is_empty() {
+        // Empty strings (`""`) are still values.
+        if self.token.value.is_empty() && !self.is_in_string {
             return;
         }
 
+        // Keywords and word-like operators are lexed as identifiers until the whole word is known.
+        if self.token.token_type == TokenType::Identifier {
+            self.token.token_type = match self.token.value.as_str() {
+                "true" | "false" => TokenType::Boolean,
+                "nil" => TokenType::Nil,
+                "&&" | "||" | "??" | ".." => TokenType::Operator,
+                _ => TokenType::Identifier,
+            };
+        }
+
         self.local_tokens.push(self.token.clone());
+        self.is_after_comma = false;
+        self.local_columns.push(self.token_start);
         self.token = Token::default();
+        // The next token starts at the character being lexed, unless it is skipped.
+        self.token_start = self.column;
         self.is_in_number = false;
         self.is_in_string = false;
-        self.token_index += 1;
     }
 }
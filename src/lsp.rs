@@ -0,0 +1,638 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    manifest::Project,
+    parser::{
+        analysis::{Analysis, CompletionKind, Outline, Span, SymbolKind, TokenKind},
+        checker::Checker,
+        linter::Linter,
+    },
+};
+
+// The kinds of semantic tokens, in the order they are numbered in.
+const TOKEN_TYPES: &[&str] = &[
+    "keyword",
+    "variable",
+    "parameter",
+    "function",
+    "method",
+    "struct",
+    "enum",
+    "enumMember",
+    "property",
+    "namespace",
+    "string",
+    "number",
+    "operator",
+];
+
+// JSON-RPC errors.
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+// A language server for editors, speaking the Language Server Protocol over stdin and stdout.
+// Returns the exit code, which is 0 when the editor asked it to shut down before exiting.
+pub fn serve() -> i32 {
+    let mut server = Server::default();
+    let mut input = io::stdin().lock();
+
+    while let Some(message) = receive(&mut input) {
+        let method = message["method"].as_str().unwrap_or_default().to_string();
+
+        if method == "exit" {
+            return if server.shutdown { 0 } else { 1 };
+        }
+
+        let params = &message["params"];
+
+        // Requests have an id and get a response, notifications don't.
+        let Some(id) = message.get("id").cloned() else {
+            server.notification(&method, params);
+            continue;
+        };
+
+        let response = match server.request(&method, params) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, error)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": error },
+            }),
+        };
+
+        send(&response);
+    }
+
+    // The editor closed stdin without asking it to exit.
+    1
+}
+
+// Reads a message, which is a `Content-Length` header, a blank line and that many bytes of JSON.
+fn receive(input: &mut impl BufRead) -> Option<Value> {
+    loop {
+        let mut length = None;
+
+        loop {
+            let mut header = String::new();
+
+            if input.read_line(&mut header).ok()? == 0 {
+                return None;
+            }
+
+            let header = header.trim_end();
+
+            if header.is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("Content-Length") {
+                    length = value.trim().parse::<usize>().ok();
+                }
+            }
+        }
+
+        let Some(length) = length else {
+            continue;
+        };
+
+        let mut body = vec![0; length];
+        input.read_exact(&mut body).ok()?;
+
+        // A message that isn't JSON can't be answered, as its id is unknown.
+        if let Ok(message) = serde_json::from_slice(&body) {
+            return Some(message);
+        }
+    }
+}
+
+fn send(message: &Value) {
+    let body = message.to_string();
+    let mut output = io::stdout().lock();
+
+    let _ = write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+    let _ = output.flush();
+}
+
+fn notify(method: &str, params: Value) {
+    send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+}
+
+#[derive(Default)]
+struct Server {
+    // The text of every open document, by URI.
+    documents: HashMap<String, String>,
+    shutdown: bool,
+}
+
+type Response = Result<Value, (i64, String)>;
+
+impl Server {
+    fn notification(&mut self, method: &str, params: &Value) {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.clone(), text.to_string());
+                self.publish_diagnostics(&uri);
+            }
+            "textDocument/didChange" => {
+                // The whole document is sent on every change, so the last change is all of it.
+                let changes = params["contentChanges"].as_array();
+                let text = changes
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+
+                if let Some(text) = text {
+                    self.documents.insert(uri.clone(), text.to_string());
+                    self.publish_diagnostics(&uri);
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                notify(
+                    "textDocument/publishDiagnostics",
+                    json!({ "uri": uri, "diagnostics": [] }),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> Response {
+        match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "referencesProvider": true,
+                    "renameProvider": true,
+                    "documentSymbolProvider": true,
+                    "completionProvider": { "triggerCharacters": ["."] },
+                    "semanticTokensProvider": {
+                        "legend": { "tokenTypes": TOKEN_TYPES, "tokenModifiers": [] },
+                        "full": true,
+                    },
+                },
+                "serverInfo": { "name": "milo", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => self.hover(params),
+            "textDocument/definition" => self.definition(params),
+            "textDocument/references" => self.references(params),
+            "textDocument/rename" => self.rename(params),
+            "textDocument/documentSymbol" => self.document_symbols(params),
+            "textDocument/completion" => self.completion(params),
+            "textDocument/semanticTokens/full" => self.semantic_tokens(params),
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method `{}`", method))),
+        }
+    }
+
+    // The document a request is about and the position in it, as a line and a char column.
+    fn document<'a>(&'a self, params: &'a Value) -> Option<(&'a str, &'a str, usize, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let text = self.documents.get(uri)?;
+
+        let line = params["position"]["line"].as_u64().unwrap_or_default() as usize;
+        let character = params["position"]["character"].as_u64().unwrap_or_default() as usize;
+
+        Some((uri, text, line, from_utf16(text, line, character)))
+    }
+
+    fn publish_diagnostics(&self, uri: &str) {
+        let Some(text) = self.documents.get(uri) else {
+            return;
+        };
+
+        let path = path(uri);
+        let mut checker = Checker::default();
+
+        let project = path
+            .parent()
+            .and_then(|directory| Project::find(directory).ok().flatten());
+
+        if let Some(project) = &project {
+            checker.module_paths = project.module_paths();
+            checker.dependencies = project.dependencies().unwrap_or_default();
+        }
+
+        checker.check_code(text.clone(), Some(path.clone()));
+
+        let mut linter = Linter::default();
+        linter.lint(text.clone(), Some(path.clone()));
+
+        // Mistakes in imported files are reported in those files.
+        let in_this_file = |file: &Option<PathBuf>| file.as_ref().is_none_or(|file| *file == path);
+
+        let errors = checker
+            .diagnostics
+            .iter()
+            .chain(&linter.errors)
+            .filter(|diagnostic| in_this_file(&diagnostic.file))
+            .map(|diagnostic| {
+                json!({
+                    "range": line_range(text, diagnostic.line),
+                    "severity": 1,
+                    "source": "milo",
+                    "message": diagnostic.message,
+                })
+            });
+
+        let warnings = linter
+            .warnings
+            .iter()
+            .filter(|warning| in_this_file(&warning.file))
+            .map(|warning| {
+                json!({
+                    "range": line_range(text, warning.line),
+                    "severity": if warning.denied { 1 } else { 2 },
+                    "source": "milo",
+                    "code": warning.rule.id(),
+                    "message": warning.message,
+                })
+            });
+
+        notify(
+            "textDocument/publishDiagnostics",
+            json!({ "uri": uri, "diagnostics": errors.chain(warnings).collect::<Vec<_>>() }),
+        );
+    }
+
+    fn hover(&self, params: &Value) -> Response {
+        let Some((_, text, line, column)) = self.document(params) else {
+            return Ok(Value::Null);
+        };
+
+        let hover = analyze(text).and_then(|analysis| analysis.hover(line, column));
+
+        Ok(match hover {
+            Some((markdown, span)) => json!({
+                "contents": { "kind": "markdown", "value": markdown },
+                "range": range(text, span),
+            }),
+            None => Value::Null,
+        })
+    }
+
+    fn definition(&self, params: &Value) -> Response {
+        let Some((uri, text, line, column)) = self.document(params) else {
+            return Ok(Value::Null);
+        };
+
+        let Some(analysis) = analyze(text) else {
+            return Ok(Value::Null);
+        };
+
+        let Some(symbol) = analysis.symbol_at(line, column) else {
+            return Ok(Value::Null);
+        };
+
+        Ok(locations(uri, text, analysis.definitions(symbol)))
+    }
+
+    fn references(&self, params: &Value) -> Response {
+        let Some((uri, text, line, column)) = self.document(params) else {
+            return Ok(Value::Null);
+        };
+
+        let Some(analysis) = analyze(text) else {
+            return Ok(Value::Null);
+        };
+
+        let Some(symbol) = analysis.symbol_at(line, column) else {
+            return Ok(json!([]));
+        };
+
+        let include_declaration = params["context"]["includeDeclaration"]
+            .as_bool()
+            .unwrap_or(true);
+
+        let definitions = analysis.definitions(symbol);
+        let spans = analysis
+            .occurrences(symbol)
+            .into_iter()
+            .filter(|span| include_declaration || !definitions.contains(span))
+            .collect();
+
+        Ok(locations(uri, text, spans))
+    }
+
+    fn rename(&self, params: &Value) -> Response {
+        let Some((uri, text, line, column)) = self.document(params) else {
+            return Ok(Value::Null);
+        };
+
+        let new_name = params["newName"].as_str().unwrap_or_default();
+
+        if !is_name(new_name) {
+            return Err((INVALID_PARAMS, format!("`{}` isn't a valid name", new_name)));
+        }
+
+        let Some(analysis) = analyze(text) else {
+            return Ok(Value::Null);
+        };
+
+        let Some(symbol) = analysis.symbol_at(line, column) else {
+            return Ok(Value::Null);
+        };
+
+        let edits: Vec<Value> = analysis
+            .occurrences(symbol)
+            .into_iter()
+            .map(|span| json!({ "range": range(text, span), "newText": new_name }))
+            .collect();
+
+        Ok(json!({ "changes": { uri: edits } }))
+    }
+
+    fn document_symbols(&self, params: &Value) -> Response {
+        let Some(text) = self.text(params) else {
+            return Ok(Value::Null);
+        };
+
+        let Some(analysis) = analyze(text) else {
+            return Ok(json!([]));
+        };
+
+        fn symbol(text: &str, outline: &Outline) -> Value {
+            // The numbers editors use for kinds of symbols.
+            let kind = match outline.kind {
+                SymbolKind::Module => 2,
+                SymbolKind::Method => 6,
+                SymbolKind::Field => 8,
+                SymbolKind::Enum => 10,
+                SymbolKind::Variable | SymbolKind::Parameter => 13,
+                SymbolKind::Variant => 22,
+                SymbolKind::Struct => 23,
+            };
+
+            let children: Vec<Value> = outline
+                .children
+                .iter()
+                .map(|child| symbol(text, child))
+                .collect();
+
+            json!({
+                "name": outline.name,
+                "detail": outline.detail,
+                "kind": kind,
+                "range": range(text, outline.span),
+                "selectionRange": range(text, outline.span),
+                "children": children,
+            })
+        }
+
+        Ok(analysis
+            .outline()
+            .iter()
+            .map(|outline| symbol(text, outline))
+            .collect())
+    }
+
+    fn completion(&self, params: &Value) -> Response {
+        let Some((_, text, line, column)) = self.document(params) else {
+            return Ok(Value::Null);
+        };
+
+        let before: String = text
+            .split('\n')
+            .nth(line)
+            .unwrap_or_default()
+            .chars()
+            .take(column)
+            .collect();
+
+        // Code that is being written often doesn't lex, e.g. an unclosed string, so the code
+        // before it is used instead.
+        let analysis = analyze(text).or_else(|| {
+            let previous: Vec<&str> = text.split('\n').take(line).collect();
+            analyze(&previous.join("\n"))
+        });
+
+        let Some(analysis) = analysis else {
+            return Ok(json!([]));
+        };
+
+        let items: Vec<Value> = analysis
+            .completions(line, &before)
+            .into_iter()
+            .map(|completion| {
+                // The numbers editors use for kinds of completions.
+                let kind = match completion.kind {
+                    CompletionKind::Method => 2,
+                    CompletionKind::Function => 3,
+                    CompletionKind::Field => 5,
+                    CompletionKind::Variable => 6,
+                    CompletionKind::Module => 9,
+                    CompletionKind::Keyword => 14,
+                    CompletionKind::Enum => 13,
+                    CompletionKind::Variant => 20,
+                    CompletionKind::Constant => 21,
+                    CompletionKind::Struct => 22,
+                };
+
+                json!({ "label": completion.label, "kind": kind, "detail": completion.detail })
+            })
+            .collect();
+
+        Ok(json!(items))
+    }
+
+    fn semantic_tokens(&self, params: &Value) -> Response {
+        let Some(text) = self.text(params) else {
+            return Ok(Value::Null);
+        };
+
+        let Some(analysis) = analyze(text) else {
+            return Ok(json!({ "data": [] }));
+        };
+
+        // Each token is 5 numbers: its line and start relative to the token before it, its
+        // length, its kind and its modifiers.
+        let mut data = vec![];
+        let (mut line, mut start) = (0, 0);
+
+        for (span, kind) in analysis.semantic_tokens() {
+            let token_start = to_utf16(text, span.line, span.column);
+            let token_end = to_utf16(text, span.line, span.column + span.length);
+
+            if span.line != line {
+                start = 0;
+            }
+
+            data.extend([
+                span.line - line,
+                token_start - start,
+                token_end - token_start,
+                token_type(kind),
+                0,
+            ]);
+
+            (line, start) = (span.line, token_start);
+        }
+
+        Ok(json!({ "data": data }))
+    }
+
+    fn text(&self, params: &Value) -> Option<&str> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        self.documents.get(uri).map(String::as_str)
+    }
+}
+
+fn analyze(text: &str) -> Option<Analysis> {
+    Analysis::new(text).ok()
+}
+
+fn token_type(kind: TokenKind) -> usize {
+    match kind {
+        TokenKind::Keyword => 0,
+        TokenKind::Variable => 1,
+        TokenKind::Parameter => 2,
+        TokenKind::Function => 3,
+        TokenKind::Method => 4,
+        TokenKind::Struct => 5,
+        TokenKind::Enum => 6,
+        TokenKind::Variant => 7,
+        TokenKind::Field => 8,
+        TokenKind::Module => 9,
+        TokenKind::String => 10,
+        TokenKind::Number => 11,
+        TokenKind::Operator => 12,
+    }
+}
+
+// Whether a new name for something is a name Milo would lex as one.
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|first| first.is_alphabetic() || first == '_')
+        && chars.all(|char| char.is_alphanumeric() || char == '_')
+        && !matches!(
+            name,
+            "struct"
+                | "enum"
+                | "impl"
+                | "fn"
+                | "match"
+                | "return"
+                | "throw"
+                | "try"
+                | "catch"
+                | "finally"
+                | "import"
+                | "export"
+                | "as"
+                | "for"
+                | "in"
+                | "true"
+                | "false"
+                | "nil"
+                | "self"
+        )
+}
+
+// `file:///path/to/file.milo` to `/path/to/file.milo`.
+fn path(uri: &str) -> PathBuf {
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+    let bytes = path.as_bytes();
+    let mut decoded = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    PathBuf::from(String::from_utf8_lossy(&decoded).to_string())
+}
+
+// Editors count columns in UTF-16 code units, while the lexer counts chars.
+fn to_utf16(text: &str, line: usize, column: usize) -> usize {
+    let line = text.split('\n').nth(line).unwrap_or_default();
+    let before = line.chars().take(column);
+
+    before.map(char::len_utf16).sum::<usize>() + column.saturating_sub(line.chars().count())
+}
+
+fn from_utf16(text: &str, line: usize, character: usize) -> usize {
+    let line = text.split('\n').nth(line).unwrap_or_default();
+    let mut units = 0;
+
+    for (column, char) in line.chars().enumerate() {
+        if units >= character {
+            return column;
+        }
+
+        units += char.len_utf16();
+    }
+
+    line.chars().count()
+}
+
+fn range(text: &str, span: Span) -> Value {
+    json!({
+        "start": { "line": span.line, "character": to_utf16(text, span.line, span.column) },
+        "end": {
+            "line": span.line,
+            "character": to_utf16(text, span.line, span.column + span.length),
+        },
+    })
+}
+
+// All of a line, for diagnostics that are only known by their line.
+fn line_range(text: &str, line: usize) -> Value {
+    let length = text
+        .split('\n')
+        .nth(line)
+        .unwrap_or_default()
+        .trim_end()
+        .chars()
+        .count();
+
+    range(
+        text,
+        Span {
+            line,
+            column: 0,
+            length,
+        },
+    )
+}
+
+fn locations(uri: &str, text: &str, spans: Vec<Span>) -> Value {
+    spans
+        .into_iter()
+        .map(|span| json!({ "uri": uri, "range": range(text, span) }))
+        .collect()
+}
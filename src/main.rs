@@ -1,58 +1,684 @@
 use std::{
-    fs,
-    io::{self, Write},
+    env, fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process,
 };
 
 use clap::Parser as _;
 use lexer::Lexer;
-use parser::Parser;
+use manifest::Project;
+use parser::{
+    checker::{self, Checker, Diagnostic},
+    linter::{Linter, Rule},
+    Parser, TestFailure,
+};
+use permissions::{Permission, Permissions};
 
+mod diff;
+mod formatter;
+mod golden;
 mod lexer;
+mod lsp;
+mod manifest;
 mod parser;
+mod patterns;
+mod permissions;
 
 #[derive(clap::Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, args_conflicts_with_subcommands(true))]
 struct Args {
-    #[arg(required_unless_present("repl"), trailing_var_arg(true))]
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    // `milo file.milo` is short for `milo run file.milo`.
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Run a file, or the entry point of the project in the current directory
+    Run(RunArgs),
+
+    /// Look for mistakes in a file, or the entry point of the project, without running it
+    Check {
+        /// The files to check
+        files: Vec<PathBuf>,
+    },
+
+    /// Warn about code that is probably a mistake, e.g. variables that are never used.
+    ///
+    /// Rules: unused-variable, unused-assignment, unreachable-code, self-comparison,
+    /// constant-condition and shadowing. A `# milo: allow(rule, ...)` comment allows them on its
+    /// own line, or on the next one when it is on a line by itself.
+    Lint(LintArgs),
+
+    /// Format files, or every `.milo` file in the project
+    Fmt {
+        /// The files to format, or directories to format every `.milo` file in
+        files: Vec<PathBuf>,
+
+        /// Don't change any files, but fail if one of them isn't formatted
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Start a language server for editors, speaking the Language Server Protocol over stdio
+    Lsp,
+
+    /// Run the `test "name" { ... }` blocks in files, or in every `.milo` file in the project.
+    ///
+    /// A file with tests is run first, then each of its tests on their own. Tests fail when they
+    /// throw an error, e.g. from `assert`, `assert_eq` or `assert_throws`.
+    ///
+    /// With --golden, every file is run as a script instead, and what it prints is compared with
+    /// its `// Should print "text"` and `// ERROR: message` comments, or else with the `.out` file
+    /// next to it. A "// Run with `milo --allow-env file.milo arguments`" comment gives a script
+    /// its flags, arguments, environment variables and stdin, and `// Skip: reason` skips it.
+    Test(TestArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct TestArgs {
+    /// The files to test, or directories to test every `.milo` file in. The project by default
+    files: Vec<PathBuf>,
+
+    /// Only run the tests whose name contains TEXT, or the scripts whose path does with --golden
+    #[arg(short, long, value_name = "TEXT")]
+    filter: Option<String>,
+
+    /// Compare what scripts print with what they should print
+    #[arg(long)]
+    golden: bool,
+
+    /// Write the `.out` files of scripts without annotations from what they print
+    #[arg(long, requires("golden"))]
+    update: bool,
+
+    #[command(flatten)]
+    permissions: PermissionArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct LintArgs {
+    /// The files to lint, or directories to lint every `.milo` file in. The project by default
+    files: Vec<PathBuf>,
+
+    /// Don't warn about a rule
+    #[arg(long, value_name = "RULE", value_delimiter = ',')]
+    allow: Vec<Rule>,
+
+    /// Make a rule an error, so linting fails when it is broken
+    #[arg(long, value_name = "RULE", value_delimiter = ',')]
+    deny: Vec<Rule>,
+}
+
+#[derive(clap::Args, Debug)]
+struct RunArgs {
+    /// The file to run, or `-` to read it from stdin, followed by the script's arguments
+    #[arg(trailing_var_arg(true))]
     file_path: Vec<String>,
 
+    /// Run CODE instead of a file. Every value after it is an argument to the script
+    #[arg(short, long, value_name = "CODE", conflicts_with("repl"))]
+    eval: Option<String>,
+
     #[arg(short, long)]
     repl: bool,
+
+    #[command(flatten)]
+    permissions: PermissionArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct PermissionArgs {
+    /// Allow reading files in a directory, or anywhere if no directory is given
+    #[arg(
+        long,
+        value_name = "DIR",
+        num_args(0..=1),
+        require_equals(true),
+        default_missing_value("/")
+    )]
+    allow_read: Vec<PathBuf>,
+
+    /// Allow writing files in a directory, or anywhere if no directory is given
+    #[arg(
+        long,
+        value_name = "DIR",
+        num_args(0..=1),
+        require_equals(true),
+        default_missing_value("/")
+    )]
+    allow_write: Vec<PathBuf>,
+
+    /// Allow reading environment variables
+    #[arg(long)]
+    allow_env: bool,
+
+    /// Allow running other programs
+    #[arg(long)]
+    allow_run: bool,
+
+    /// Allow reading the clock
+    #[arg(long)]
+    allow_clock: bool,
+
+    /// Allow everything
+    #[arg(short = 'A', long)]
+    allow_all: bool,
+}
+
+impl PermissionArgs {
+    fn permissions(self) -> Permissions {
+        let mut permissions = if self.allow_all {
+            Permissions::all()
+        } else {
+            Permissions::default()
+        };
+
+        for directory in self.allow_read {
+            permissions.allow_read(directory);
+        }
+
+        for directory in self.allow_write {
+            permissions.allow_write(directory);
+        }
+
+        for (allowed, permission) in [
+            (self.allow_env, Permission::Env),
+            (self.allow_run, Permission::Run),
+            (self.allow_clock, Permission::Clock),
+        ] {
+            if allowed {
+                permissions.allow(permission);
+            }
+        }
+
+        permissions
+    }
 }
 
 fn read_code_from_file(file_path: String) -> String {
-    fs::read_to_string(file_path).expect("Please pass a valid file")
+    fs::read_to_string(&file_path)
+        .unwrap_or_else(|error| fail(&format!("Cannot read `{}`, {}", file_path, error)))
+}
+
+fn read_code_from_stdin() -> String {
+    let mut code = String::new();
+    io::stdin()
+        .read_to_string(&mut code)
+        .unwrap_or_else(|error| fail(&format!("Cannot read the code from stdin, {}", error)));
+    code
+}
+
+// Reports an error that happened before any Milo code could run.
+fn fail(message: &str) -> ! {
+    eprintln!("\x1b[31mERROR: {}.\x1b[0m", message);
+    process::exit(1);
 }
 
 fn main() {
     let args = Args::parse();
 
+    match args.command {
+        Some(Command::Run(run_args)) => run(run_args),
+        Some(Command::Check { files }) => check(files),
+        Some(Command::Lint(lint_args)) => lint(lint_args),
+        Some(Command::Fmt { files, check }) => fmt(files, check),
+        Some(Command::Lsp) => process::exit(lsp::serve()),
+        Some(Command::Test(test_args)) => test(test_args),
+        None => run(args.run),
+    }
+}
+
+fn run(args: RunArgs) {
+    let permissions = args.permissions.permissions();
+
     if args.repl {
-        let mut lexer = Lexer::default();
+        repl(permissions);
+    }
+
+    let mut lexer = Lexer::default();
+    let mut parser = Parser::default();
+    parser.permissions = permissions;
+
+    let directory = match args.file_path.first() {
+        Some(file_path) if args.eval.is_none() && file_path != "-" => Path::new(file_path)
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default(),
+        _ => PathBuf::new(),
+    };
+
+    // The project the file is in, if any, for its module paths and dependencies.
+    let project = env::current_dir()
+        .map(|current| current.join(directory))
+        .map_err(|error| error.to_string())
+        .and_then(|directory| Project::find(&directory))
+        .unwrap_or_else(|error| fail(&error));
+
+    if let Some(project) = &project {
+        parser.module_paths = project.module_paths();
+        parser.dependencies = project.dependencies().unwrap_or_else(|error| fail(&error));
+    }
+
+    let code = match args.eval {
+        Some(code) => {
+            parser.script_args = args.file_path;
+            code
+        }
+        None if args
+            .file_path
+            .first()
+            .is_some_and(|file_path| file_path == "-") =>
+        {
+            parser.script_args = args.file_path[1..].to_vec();
+            read_code_from_stdin()
+        }
+        None if !args.file_path.is_empty() => {
+            // Imports are resolved relative to the file that was run.
+            parser.file_path = Some(PathBuf::from(&args.file_path[0]));
+            parser.script_args = args.file_path[1..].to_vec();
+            read_code_from_file(args.file_path[0].clone())
+        }
+        // Without a file, the entry point of the project is run.
+        None => {
+            let Some(project) = project else {
+                fail(&format!(
+                    "No file to run, and no `{}` in this directory or above it",
+                    manifest::FILE_NAME
+                ));
+            };
+
+            let entry = project.entry();
+
+            if !entry.is_file() {
+                fail(&format!(
+                    "The entry point of `{}`, `{}`, doesn't exist",
+                    project.manifest.package.name,
+                    entry.display()
+                ));
+            }
+
+            parser.file_path = Some(entry.clone());
+            read_code_from_file(entry.to_string_lossy().to_string())
+        }
+    };
+
+    if parser::is_debugging() {
+        println!("--- Code ---\n{}\n------------\n", code);
+    }
+
+    match lexer.lex_code(code) {
+        Ok(tokens) => {
+            parser.parse(tokens);
+            parser.finish();
+        }
+        Err(error) => parser.lex_error(error),
+    }
+
+    if let Some(code) = parser.exit_code {
+        process::exit(code);
+    }
+
+    if let Some(kind) = parser.failure {
+        process::exit(kind.exit_code());
+    }
+}
+
+fn check(files: Vec<PathBuf>) {
+    let directory = files
+        .first()
+        .and_then(|file| file.parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    let project = env::current_dir()
+        .map(|current| current.join(directory))
+        .map_err(|error| error.to_string())
+        .and_then(|directory| Project::find(&directory))
+        .unwrap_or_else(|error| fail(&error));
+
+    let mut checker = Checker::default();
+
+    if let Some(project) = &project {
+        checker.module_paths = project.module_paths();
+        checker.dependencies = project.dependencies().unwrap_or_else(|error| fail(&error));
+    }
+
+    let files = match (files.is_empty(), project) {
+        (false, _) => files,
+        (true, Some(project)) => vec![project.entry()],
+        (true, None) => fail(&format!(
+            "No file to check, and no `{}` in this directory or above it",
+            manifest::FILE_NAME
+        )),
+    };
+
+    for file in &files {
+        checker.check_file(file);
+    }
+
+    for diagnostic in &checker.diagnostics {
+        report(diagnostic);
+    }
+
+    if !checker.diagnostics.is_empty() {
+        process::exit(1);
+    }
+}
+
+fn report(diagnostic: &Diagnostic) {
+    eprintln!(
+        "\x1b[31mERROR: {}. Error occurred on line {}{}.\x1b[0m",
+        diagnostic.message,
+        diagnostic.line + 1,
+        in_file(&diagnostic.file)
+    );
+}
+
+fn in_file(file: &Option<PathBuf>) -> String {
+    file.as_ref()
+        .map(|file| format!(" in {}", file.display()))
+        .unwrap_or_default()
+}
+
+fn lint(args: LintArgs) {
+    let mut linter = Linter {
+        allowed: args.allow.into_iter().collect(),
+        denied: args.deny.into_iter().collect(),
+        ..Default::default()
+    };
+
+    for file in project_files(args.files, "lint") {
+        linter.lint_file(&file);
+    }
+
+    for diagnostic in &linter.errors {
+        report(diagnostic);
+    }
+
+    for warning in &linter.warnings {
+        let (color, kind) = if warning.denied {
+            (31, "Error")
+        } else {
+            (33, "Warning")
+        };
+
+        eprintln!(
+            "\x1b[{}m{}: {} ({}). {} occurred on line {}{}.\x1b[0m",
+            color,
+            kind.to_uppercase(),
+            warning.message,
+            warning.rule,
+            kind,
+            warning.line + 1,
+            in_file(&warning.file)
+        );
+    }
+
+    if !linter.errors.is_empty() || linter.warnings.iter().any(|warning| warning.denied) {
+        process::exit(1);
+    }
+}
+
+fn fmt(files: Vec<PathBuf>, check: bool) {
+    let mut failed = false;
+
+    for file in project_files(files, "format") {
+        let code = fs::read_to_string(&file)
+            .unwrap_or_else(|error| fail(&format!("Cannot read `{}`, {}", file.display(), error)));
+
+        let formatted = match formatter::format(&code) {
+            Ok(formatted) => formatted,
+            Err(error) => {
+                report(&Diagnostic {
+                    file: Some(file),
+                    line: error.line,
+                    message: error.message,
+                });
+                failed = true;
+                continue;
+            }
+        };
+
+        if formatted == code {
+            continue;
+        }
+
+        if check {
+            // The first line that changes, to point at.
+            let line = code
+                .lines()
+                .zip(formatted.lines())
+                .position(|(old, new)| old != new)
+                .unwrap_or_else(|| code.lines().count().min(formatted.lines().count()));
+
+            report(&Diagnostic {
+                file: Some(file),
+                line,
+                message: "The code isn't formatted, run `milo fmt`".to_string(),
+            });
+            failed = true;
+        } else if let Err(error) = fs::write(&file, formatted) {
+            fail(&format!("Cannot write `{}`, {}", file.display(), error));
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+}
+
+fn test(args: TestArgs) {
+    if args.golden {
+        let files = project_files(args.files, "test");
+
+        match golden::run(files, args.filter.as_deref(), args.update) {
+            Ok(true) => return,
+            Ok(false) => process::exit(1),
+            Err(error) => fail(&format!("Cannot run the scripts, {}", error)),
+        }
+    }
+
+    let permissions = args.permissions.permissions();
+    let (mut passed, mut filtered_out, mut broken_files) = (0, 0, 0);
+    let mut failures: Vec<(String, PathBuf, TestFailure)> = vec![];
+
+    for file in project_files(args.files, "test") {
+        let code = fs::read_to_string(&file)
+            .unwrap_or_else(|error| fail(&format!("Cannot read `{}`, {}", file.display(), error)));
+
+        // Files without tests aren't run, since running them could do anything.
+        let has_tests = Lexer::default()
+            .lex_code(code.clone())
+            .map_or(true, |lines| {
+                lines.iter().any(|tokens| checker::is_test(tokens))
+            });
+
+        if !has_tests {
+            continue;
+        }
+
         let mut parser = Parser::default();
+        parser.permissions = permissions.clone();
+        parser.file_path = Some(file.clone());
 
-        loop {
-            let mut input = String::new();
+        let project = file
+            .parent()
+            .map(|directory| env::current_dir().unwrap_or_default().join(directory))
+            .and_then(|directory| Project::find(&directory).unwrap_or_else(|error| fail(&error)));
 
-            print!("> ");
-            io::stdout().flush().unwrap();
+        if let Some(project) = &project {
+            parser.module_paths = project.module_paths();
+            parser.dependencies = project.dependencies().unwrap_or_else(|error| fail(&error));
+        }
 
-            match io::stdin().read_line(&mut input) {
-                Ok(_) => {
-                    let tokens = lexer.lex_code(input);
-                    parser.parse(tokens);
-                    parser.should_abort = false;
-                }
-                Err(err) => panic!("{}", err),
+        println!("\nRunning tests in {}", file.display());
+
+        match Lexer::default().lex_code(code) {
+            Ok(tokens) => {
+                parser.parse(tokens);
+                parser.finish();
+            }
+            Err(error) => parser.lex_error(error),
+        }
+
+        // The error was already printed. None of the tests can run without the rest of the file.
+        if parser.failure.is_some() || parser.exit_code.is_some() {
+            let message = match parser.exit_code {
+                Some(code) => format!("exit({}) was called before the tests", code),
+                None => "The file failed before the tests".to_string(),
             };
+
+            println!("{} ... \x1b[31mFAILED\x1b[0m", message);
+            broken_files += 1;
+            continue;
         }
+
+        for test in parser.tests.clone() {
+            if args
+                .filter
+                .as_ref()
+                .is_some_and(|filter| !test.name.contains(filter.as_str()))
+            {
+                filtered_out += 1;
+                continue;
+            }
+
+            match parser.run_test(&test) {
+                Ok(()) => {
+                    println!("test {} ... \x1b[32mok\x1b[0m", test.name);
+                    passed += 1;
+                }
+                Err(failure) => {
+                    println!("test {} ... \x1b[31mFAILED\x1b[0m", test.name);
+                    failures.push((test.name, file.clone(), failure));
+                }
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        println!("\nFailures:");
+    }
+
+    for (name, file, failure) in &failures {
+        // Messages with more lines, e.g. a diff, go on after where the test failed.
+        let (message, details) = match failure.message.split_once('\n') {
+            Some((message, details)) => (message, format!("\n{}", details)),
+            None => (failure.message.as_str(), String::new()),
+        };
+
+        println!(
+            "\n--- {} ---\n\x1b[31mERROR: {}. Error occurred on line {} in {}.{}\x1b[0m",
+            name,
+            message,
+            failure.line + 1,
+            file.display(),
+            details
+        );
     }
 
+    let failed = failures.len() + broken_files;
+
+    let result = if failed == 0 {
+        "\x1b[32mok\x1b[0m"
+    } else {
+        "\x1b[31mFAILED\x1b[0m"
+    };
+
+    println!(
+        "\ntest result: {}. {} passed; {} failed; {} filtered out",
+        result, passed, failed, filtered_out
+    );
+
+    if failed > 0 {
+        process::exit(1);
+    }
+}
+
+// The `.milo` files in `paths`, or in the project in the current directory if there are none.
+fn project_files(paths: Vec<PathBuf>, action: &str) -> Vec<PathBuf> {
+    let paths = if paths.is_empty() {
+        let project = env::current_dir()
+            .map_err(|error| error.to_string())
+            .and_then(|directory| Project::find(&directory))
+            .unwrap_or_else(|error| fail(&error));
+
+        match project {
+            Some(project) => vec![project.root],
+            None => fail(&format!(
+                "No file to {}, and no `{}` in this directory or above it",
+                action,
+                manifest::FILE_NAME
+            )),
+        }
+    } else {
+        paths
+    };
+
+    paths.iter().flat_map(|path| milo_files(path)).collect()
+}
+
+// `path` if it is a file, or every `.milo` file in it if it is a directory.
+fn milo_files(path: &Path) -> Vec<PathBuf> {
+    if !path.is_dir() {
+        return vec![path.to_path_buf()];
+    }
+
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)
+        .unwrap_or_else(|error| fail(&format!("Cannot read `{}`, {}", path.display(), error)))
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    entries.sort();
+
+    entries
+        .into_iter()
+        .filter(|entry| entry.is_dir() || entry.extension().is_some_and(|ext| ext == "milo"))
+        .flat_map(|entry| milo_files(&entry))
+        .collect()
+}
+
+fn repl(permissions: Permissions) -> ! {
     let mut lexer = Lexer::default();
     let mut parser = Parser::default();
+    parser.permissions = permissions;
+
+    loop {
+        let mut input = String::new();
+
+        print!("> ");
+        io::stdout().flush().unwrap();
+
+        match io::stdin().read_line(&mut input) {
+            // Ctrl-D
+            Ok(0) => process::exit(0),
+            Ok(_) if matches!(input.trim(), ":quit" | ":exit") => process::exit(0),
+            Ok(_) => {
+                if parser::is_debugging() {
+                    println!("--- Code ---\n{}\n------------\n", input);
+                }
 
-    let code = read_code_from_file(args.file_path[0].clone());
+                match lexer.lex_code(input) {
+                    Ok(tokens) => parser.parse(tokens),
+                    Err(error) => parser.lex_error(error),
+                }
 
-    let tokens = lexer.lex_code(code);
-    parser.parse(tokens);
+                // `exit` stops what was typed, but not the REPL itself.
+                if let Some(code) = parser.exit_code.take() {
+                    println!(
+                        "exit({}) ignored, use :quit or Ctrl-D to leave the REPL",
+                        code
+                    );
+                }
+
+                parser.should_abort = false;
+                parser.failure = None;
+            }
+            Err(err) => panic!("{}", err),
+        };
+    }
 }
@@ -0,0 +1,134 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+pub const FILE_NAME: &str = "milo.toml";
+
+// A `milo.toml`, e.g.
+//
+// [package]
+// name = "app"
+// entry = "src/main.milo"
+//
+// [modules]
+// paths = ["src", "lib"]
+//
+// [dependencies]
+// utils = { path = "../utils" }
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub package: Package,
+    #[serde(default)]
+    pub modules: Modules,
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, Dependency>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Package {
+    pub name: String,
+    // The file `milo run` runs, relative to the manifest.
+    #[serde(default = "default_entry")]
+    pub entry: PathBuf,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Modules {
+    // Directories that `import name` looks in when there is no `name.milo` next to the importer.
+    #[serde(default)]
+    pub paths: Vec<PathBuf>,
+}
+
+// A project on disk, imported by its name. `path` is either a `.milo` file or a directory with a
+// `milo.toml`, in which case its entry is imported.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Dependency {
+    pub path: PathBuf,
+}
+
+fn default_entry() -> PathBuf {
+    PathBuf::from("main.milo")
+}
+
+// A manifest and the directory it is in.
+#[derive(Debug)]
+pub struct Project {
+    pub root: PathBuf,
+    pub manifest: Manifest,
+}
+
+impl Project {
+    // Finds the `milo.toml` in `directory` or the closest directory above it.
+    pub fn find(directory: &Path) -> Result<Option<Project>, String> {
+        for directory in directory.ancestors() {
+            if directory.join(FILE_NAME).is_file() {
+                return Project::load(directory).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn load(root: &Path) -> Result<Project, String> {
+        let path = root.join(FILE_NAME);
+
+        let text = fs::read_to_string(&path)
+            .map_err(|error| format!("Cannot read `{}`, {}", path.display(), error))?;
+
+        let manifest = toml::from_str(&text)
+            .map_err(|error| format!("Invalid `{}`: {}", path.display(), error.message()))?;
+
+        Ok(Project {
+            root: root.to_path_buf(),
+            manifest,
+        })
+    }
+
+    pub fn entry(&self) -> PathBuf {
+        self.root.join(&self.manifest.package.entry)
+    }
+
+    pub fn module_paths(&self) -> Vec<PathBuf> {
+        self.manifest
+            .modules
+            .paths
+            .iter()
+            .map(|path| self.root.join(path))
+            .collect()
+    }
+
+    // The file each dependency is imported from, by name.
+    pub fn dependencies(&self) -> Result<HashMap<String, PathBuf>, String> {
+        let mut dependencies = HashMap::new();
+
+        for (name, dependency) in &self.manifest.dependencies {
+            let path = self.root.join(&dependency.path);
+
+            let file = if path.is_dir() {
+                Project::load(&path)?.entry()
+            } else {
+                path
+            };
+
+            if !file.is_file() {
+                return Err(format!(
+                    "Dependency `{}` points to `{}`, which doesn't exist",
+                    name,
+                    file.display()
+                ));
+            }
+
+            dependencies.insert(name.clone(), file);
+        }
+
+        Ok(dependencies)
+    }
+}
@@ -1,9 +1,25 @@
 use std::{
+    cmp::Ordering,
     collections::HashMap,
+    fs,
     io::{self, Write},
+    path::{Path, PathBuf},
 };
 
-use crate::lexer::{LexedTokenLines, Token, TokenType};
+use crate::{
+    lexer::{LexError, LexedTokenLines, Lexer, Token, TokenType},
+    patterns::Pattern,
+    permissions::Permissions,
+};
+
+pub mod analysis;
+mod builtins;
+pub mod checker;
+pub mod linter;
+mod number;
+mod walk;
+
+use number::Number;
 
 #[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub enum VariableType {
@@ -11,6 +27,13 @@ pub enum VariableType {
     String,
     Number,
     Boolean,
+    Nil,
+    List,
+    Map,
+    Tuple,
+    Struct,
+    Enum,
+    Module,
 }
 
 impl From<VariableType> for TokenType {
@@ -19,520 +42,3319 @@ impl From<VariableType> for TokenType {
             VariableType::String => TokenType::String,
             VariableType::Number => TokenType::Number,
             VariableType::Boolean => TokenType::Boolean,
+            VariableType::Nil => TokenType::Nil,
+            VariableType::List => TokenType::List,
+            VariableType::Map => TokenType::Map,
+            VariableType::Tuple => TokenType::Tuple,
+            VariableType::Struct => TokenType::Struct,
+            VariableType::Enum => TokenType::Enum,
+            VariableType::Module => TokenType::Module,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Variable {
     variable_type: VariableType,
     value: String,
+    items: Vec<Token>,
+    keys: Vec<Token>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Function {
+    parameters: Vec<String>,
+    body: LexedTokenLines,
+    // The line the body starts on, for error messages.
+    line: usize,
+}
+
+// An `impl` block that hasn't been closed yet, and the method currently being declared inside it.
+struct ImplBlock {
+    type_name: String,
+    method: Option<(String, Function)>,
+    depth: usize,
+}
+
+// A `match` whose arms haven't all been read yet.
+struct MatchBlock {
+    value: Token,
+    result: MatchResult,
+    // The lines inside the braces, with the line each of them is on.
+    lines: Vec<(usize, Vec<Token>)>,
+    depth: usize,
+    // The line `match` is on, for error messages.
+    line: usize,
+}
+
+// What happens to the value of the arm that matched.
+enum MatchResult {
+    Discard,
+    Assign(String),
+    Return,
+}
+
+struct MatchArm {
+    pattern: Pattern,
+    guard: Option<Vec<Token>>,
+    body: LexedTokenLines,
+    line: usize,
+}
+
+// What importing a module gives back. Modules are only run once, after that this is reused.
+#[derive(Clone)]
+struct Module {
+    // The exported variables, as a `Module` token.
+    exports: Token,
+    // The exported types, and their methods.
+    structs: HashMap<String, Vec<String>>,
+    enums: HashMap<String, Vec<(String, usize)>>,
+    methods: HashMap<String, HashMap<String, Function>>,
+}
+
+// A `try` block whose `catch` and `finally` blocks haven't all been read yet.
+struct TryBlock {
+    sections: Vec<TrySection>,
+    depth: usize,
+}
+
+struct TrySection {
+    kind: TrySectionKind,
+    // The first line of the body is whatever follows the `{` on the line it is on.
+    body: LexedTokenLines,
+    line: usize,
+}
+
+#[derive(PartialEq)]
+enum TrySectionKind {
+    Try,
+    // `catch name {`, where the name holds the caught error.
+    Catch(Option<String>),
+    Finally,
+}
+
+// A `for` loop whose body hasn't been closed yet.
+struct LoopBlock {
+    // The names each item is bound to, e.g. `item` or `key, value`.
+    names: Vec<String>,
+    iterable: Token,
+    // The first line of the body is whatever follows the `{` on the `for` line.
+    body: LexedTokenLines,
+    depth: usize,
+    line: usize,
+}
+
+// A `test "name" { ... }` block. Tests don't run with the rest of the file, only `milo test` runs
+// them, each on its own.
+#[derive(Debug, Clone)]
+pub struct Test {
+    pub name: String,
+    body: LexedTokenLines,
+    // The line `test` is on.
+    pub line: usize,
+}
+
+// Why a test failed, and the line it failed on.
+#[derive(Debug, Clone)]
+pub struct TestFailure {
+    pub message: String,
+    pub line: usize,
 }
 
+// A variable, or a part of one, that a value can be written into, e.g. `xs`, `p.items` or `xs[0]`.
+#[derive(Debug, Clone)]
+struct Place {
+    name: String,
+    path: Vec<Step>,
+}
+
+#[derive(Debug, Clone)]
+enum Step {
+    Field(String),
+    // The index has already been evaluated.
+    Index(Token),
+}
+
+// How deep methods can call each other before giving up.
+const MAX_CALL_DEPTH: usize = 128;
+
 #[derive(Default)]
 pub struct Parser {
     stack: Vec<Token>,
     variables: HashMap<String, Variable>,
+    // The field names of every declared struct, in declaration order.
+    structs: HashMap<String, Vec<String>>,
+    // A struct whose declaration spans multiple lines and hasn't been closed yet.
+    declaring_struct: Option<(String, Vec<String>)>,
+    // The variants of every declared enum and how many values each of them holds, in declaration order.
+    enums: HashMap<String, Vec<(String, usize)>>,
+    declaring_enum: Option<(String, Vec<(String, usize)>)>,
+    // The methods of every type with an `impl` block, by type name and then method name.
+    methods: HashMap<String, HashMap<String, Function>>,
+    declaring_impl: Option<ImplBlock>,
+    // The line the struct, enum or `impl` block being declared starts on.
+    declaring_line: usize,
+    matching: Option<MatchBlock>,
+    trying: Option<TryBlock>,
+    looping: Option<LoopBlock>,
+    // A test that hasn't been closed yet, and how many braces are open in it.
+    testing: Option<(Test, usize)>,
+    // Every test in the file, in order.
+    pub tests: Vec<Test>,
+    // How many `try` blocks are currently running. Errors are only printed outside of them.
+    try_depth: usize,
+    // An error that was thrown inside of a `try` block, and the line it was thrown on.
+    exception: Option<(Token, usize)>,
+    // The file being run, if any. Imports are resolved relative to it.
+    pub file_path: Option<PathBuf>,
+    pub permissions: Permissions,
+    // The values given after the file on the command line, see `args()`.
+    pub script_args: Vec<String>,
+    // Where `import name` looks after the importing file's directory, from `milo.toml`.
+    pub module_paths: Vec<PathBuf>,
+    // The file each dependency in `milo.toml` is imported from, by name.
+    pub dependencies: HashMap<String, PathBuf>,
+    // Every module that has been imported so far, by its canonical path.
+    modules: HashMap<PathBuf, Module>,
+    // The files that are importing this one, starting with the file that was run.
+    import_chain: Vec<PathBuf>,
+    // The names declared with `export`.
+    exports: Vec<String>,
+    call_depth: usize,
+    return_value: Option<Token>,
+    // Set while a built-in is called as a method: what the method was called on, if it can be
+    // written to.
+    method_receiver: Option<Option<Place>>,
     tokens_on_line: Vec<Token>,
     line: usize,
     current_token: Token,
     index: usize,
-    skip_next: bool,
-    resolve_variables: bool,
+    skip_tokens: usize,
+    skip_line: bool,
+    line_stack_start: usize,
+    // Set while `=` is evaluated, so the name on its left isn't looked up as a variable.
+    assigning: bool,
     pub should_abort: bool,
+    // The kind of the last error that was raised.
+    error_kind: ErrorKind,
+    // Set when an error wasn't caught, and the script failed because of it.
+    pub failure: Option<ErrorKind>,
+    // Set by `exit(code)`, which stops the script without running any `catch` or `finally` blocks.
+    pub exit_code: Option<i32>,
+}
+
+// What went wrong when a script failed. Each kind exits the process with its own code.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ErrorKind {
+    // Code that can't be split into tokens.
+    Lex,
+    // Code that is shaped wrong, e.g. a missing `}`.
+    Parse,
+    #[default]
+    Runtime,
+}
+
+impl ErrorKind {
+    // 2 is left out, since it is used for wrong command line arguments.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Runtime => 1,
+            ErrorKind::Parse => 3,
+            ErrorKind::Lex => 4,
+        }
+    }
 }
 
 impl Parser {
     pub fn parse(&mut self, tokens: LexedTokenLines) {
-        #[cfg(debug_assertions)]
-        println!(
-            "--- Tokens ---\n{:?}\n--------------\n\n--- Output ---",
-            tokens
-        );
+        if is_debugging() {
+            println!(
+                "--- Tokens ---\n{:?}\n--------------\n\n--- Output ---",
+                tokens
+            );
+        }
 
-        for (line, mut tokens) in tokens.into_iter().enumerate() {
-            self.tokens_on_line.clone_from(&tokens);
-            tokens.reverse();
+        self.run(tokens, 0);
 
+        if is_debugging() {
+            println!("--------------");
+
+            println!("--- Variables ---");
+            println!("{:?}", self.variables);
+            println!("-----------------");
+        }
+    }
+
+    // Reports a block that is still open once all of the code has run, e.g. `try {` without its
+    // `}`. Separate from `parse`, since the REPL runs a block over several inputs.
+    pub fn finish(&mut self) {
+        if self.should_abort {
+            return;
+        }
+
+        let unclosed = if self.declaring_struct.is_some() {
+            Some(("struct", self.declaring_line))
+        } else if self.declaring_enum.is_some() {
+            Some(("enum", self.declaring_line))
+        } else if let Some(block) = &self.declaring_impl {
+            match &block.method {
+                // The body of a method starts on the line after `fn`.
+                Some((_, method)) => Some(("fn", method.line - 1)),
+                None => Some(("impl", self.declaring_line)),
+            }
+        } else if let Some(block) = &self.matching {
+            Some(("match", block.line))
+        } else if let Some(block) = &self.looping {
+            Some(("for", block.line))
+        } else if let Some(block) = &self.trying {
+            block.sections.last().map(|section| {
+                let keyword = match section.kind {
+                    TrySectionKind::Try => "try",
+                    TrySectionKind::Catch(_) => "catch",
+                    TrySectionKind::Finally => "finally",
+                };
+
+                (keyword, section.line)
+            })
+        } else {
+            self.testing.as_ref().map(|(test, _)| ("test", test.line))
+        };
+
+        if let Some((keyword, line)) = unclosed {
             self.line = line;
+            self.syntax_error(&missing_closing(keyword, "}"));
+        }
+    }
 
-            for (i, token) in tokens.clone().into_iter().enumerate() {
-                if self.should_abort {
-                    #[cfg(debug_assertions)]
-                    {
-                        println!("--------------");
+    fn run(&mut self, lines: LexedTokenLines, first_line: usize) {
+        for (line, tokens) in lines.into_iter().enumerate() {
+            self.tokens_on_line = tokens;
 
-                        println!("--- Variables ---");
-                        println!("{:?}", self.variables);
-                        println!("-----------------");
-                    }
-                    return;
-                }
+            self.line = first_line + line;
+            self.line_stack_start = self.stack.len();
+            self.skip_line = false;
+            self.skip_tokens = 0;
 
-                if self.skip_next {
-                    self.skip_next = false;
-                    continue;
-                }
+            if self.should_abort || self.return_value.is_some() {
+                break;
+            }
 
-                self.current_token = token.clone();
-                self.index = (tokens.len() - i) - 1;
+            checker::qualify_types(&mut self.tokens_on_line, |name| {
+                self.structs.contains_key(name) || self.enums.contains_key(name)
+            });
 
-                match token.token_type {
-                    TokenType::Identifier => self.parse_identifier(token.value),
-                    TokenType::Operator => self.parse_operator(token.value),
-                    TokenType::Number | TokenType::String | TokenType::Boolean => {
-                        self.stack.push(token)
-                    }
-                }
+            if self.parse_statement() {
+                continue;
             }
+
+            // Checked up front, so nothing on the line runs when it can't all run.
+            if let Some(message) = unclosed_bracket(&self.tokens_on_line) {
+                self.syntax_error(&message);
+                continue;
+            }
+
+            self.evaluate();
         }
+    }
 
-        #[cfg(debug_assertions)]
-        {
-            println!("--------------");
+    // Evaluates `tokens_on_line` from right to left.
+    fn evaluate(&mut self) {
+        for (index, token) in self.tokens_on_line.clone().into_iter().enumerate().rev() {
+            if self.should_abort || self.skip_line {
+                return;
+            }
 
-            println!("--- Variables ---");
-            println!("{:?}", self.variables);
-            println!("-----------------");
+            if self.skip_tokens > 0 {
+                self.skip_tokens -= 1;
+                continue;
+            }
+
+            self.current_token = token.clone();
+            self.index = index;
+
+            match token.token_type {
+                TokenType::Identifier => self.parse_identifier(token.value),
+                TokenType::Operator => self.parse_operator(token.value),
+                TokenType::Number
+                | TokenType::String
+                | TokenType::Boolean
+                | TokenType::Nil
+                | TokenType::List
+                | TokenType::Map
+                | TokenType::Tuple
+                | TokenType::Struct
+                | TokenType::Enum
+                | TokenType::Module => self.stack.push(token),
+            }
         }
     }
 
-    fn parse_identifier(&mut self, identifier: String) {
-        if identifier == "print" {
-            let token = self.pop_stack(
-                "print",
-                1,
-                Some(Token {
-                    token_type: TokenType::String,
-                    value: "".to_string(),
-                }),
-            );
+    // Handles lines that are declarations rather than expressions. Returns whether the line was handled.
+    fn parse_statement(&mut self) -> bool {
+        if self.declaring_struct.is_some() {
+            self.declare_struct_fields();
+            return true;
+        }
 
-            let mut to_print = &token.value;
+        if self.declaring_enum.is_some() {
+            self.declare_enum_variants();
+            return true;
+        }
 
-            let to_print_if_number = if to_print == "0" {
-                &format!("\x1b[33m{}\x1b[0m", to_print)
-            } else {
-                &format!("\x1b[33m{}\x1b[0m", to_print.trim_start_matches('0'))
-            };
+        if self.declaring_impl.is_some() {
+            self.declare_impl_line();
+            return true;
+        }
+
+        if self.matching.is_some() {
+            self.collect_match_line();
+            return true;
+        }
+
+        if self.trying.is_some() {
+            self.collect_try_line();
+            return true;
+        }
+
+        if self.looping.is_some() {
+            self.collect_loop_line();
+            return true;
+        }
 
-            let to_print_yellow = &format!("\x1b[33m{}\x1b[0m", to_print);
+        if self.testing.is_some() {
+            self.collect_test_line();
+            return true;
+        }
 
-            to_print = match token.token_type {
-                // Remove leading zeroes if the value is a number.
-                TokenType::Number => to_print_if_number,
-                TokenType::Boolean => to_print_yellow,
-                TokenType::Identifier | TokenType::Operator | TokenType::String => to_print,
+        if checker::is_test(&self.tokens_on_line) {
+            let name = self.tokens_on_line[1].value.clone();
+            let test = Test {
+                name,
+                body: vec![],
+                line: self.line,
             };
 
-            println!("{}", to_print);
-        } else {
-            let var = self.try_parse_variable(&identifier);
-            if let Some(variable) = var {
-                self.stack.push(variable);
-            } else {
-                self.error(&format!("Unknown identifier: `{}`", identifier));
+            self.tokens_on_line.drain(..3);
+            self.testing = Some((test, 1));
+            self.collect_test_line();
+            return true;
+        }
+
+        // assert_throws(value) catches the error `value` throws, so it can't be evaluated first.
+        if self.tokens_on_line.first().is_some_and(|token| {
+            token.token_type == TokenType::Identifier && token.value == "assert_throws("
+        }) {
+            self.assert_throws();
+            return true;
+        }
+
+        // name = match value {
+        if self.tokens_on_line.len() >= 3
+            && self.tokens_on_line[0].token_type == TokenType::Identifier
+            && self.tokens_on_line[1].token_type == TokenType::Operator
+            && self.tokens_on_line[1].value == "="
+            && self.tokens_on_line[2].token_type == TokenType::Identifier
+            && self.tokens_on_line[2].value == "match"
+        {
+            let name = self.tokens_on_line[0].value.clone();
+            self.start_match(2, MatchResult::Assign(name));
+            return true;
+        }
+
+        let Some(keyword) = self.tokens_on_line.first() else {
+            return false;
+        };
+
+        if keyword.token_type != TokenType::Identifier {
+            return false;
+        }
+
+        match keyword.value.as_str() {
+            "struct" => self.declare_struct(),
+            "enum" => self.declare_enum(),
+            "impl" => self.declare_impl(),
+            "match" => self.start_match(0, MatchResult::Discard),
+            "return"
+                if self.tokens_on_line.get(1).is_some_and(|token| {
+                    token.token_type == TokenType::Identifier && token.value == "match"
+                }) =>
+            {
+                if self.call_depth == 0 {
+                    self.syntax_error("`return` can only be used inside of a method");
+                } else {
+                    self.start_match(1, MatchResult::Return);
+                }
+            }
+            "return" => self.parse_return(),
+            "throw" => self.parse_throw(),
+            "for" => self.start_loop(),
+            "import" => self.parse_import(),
+            "export" => self.parse_export(),
+            "try" => {
+                self.trying = Some(TryBlock {
+                    sections: vec![],
+                    depth: 0,
+                });
+                self.collect_try_line();
             }
+            _ => return false,
         }
+
+        true
     }
 
-    fn parse_operator(&mut self, operator: String) {
-        if operator == "+" {
-            if let Some((a, b)) = self.get_surrounding_operator("+") {
-                if a.token_type != TokenType::String && a.token_type != TokenType::Number {
-                    self.error(&format!(
-                        "`{}` expected a string or number, got identifier: `{}`",
-                        operator, a.value
-                    ));
-                    return;
-                } else if b.token_type != TokenType::String && b.token_type != TokenType::Number {
-                    self.error(&format!(
-                        "`{}` expected a string or number, got identifier: `{}`",
-                        operator, b.value
-                    ));
-                    return;
-                }
+    // struct Name { field, field, ... }
+    fn declare_struct(&mut self) {
+        let tokens = self.tokens_on_line.clone();
 
-                let is_string =
-                    a.token_type == TokenType::String || b.token_type == TokenType::String;
+        if !is_block_start(&tokens, 3) {
+            self.syntax_error("`struct` expected a name followed by `{`");
+            return;
+        }
 
-                if is_string {
-                    self.stack.push(Token {
-                        token_type: TokenType::String,
-                        value: format!("{}{}", a.value, b.value),
-                    });
-                    return;
-                }
+        // The fields may be on this line, or on the following ones.
+        self.declaring_struct = Some((tokens[1].value.clone(), vec![]));
+        self.declaring_line = self.line;
+        self.tokens_on_line = tokens[3..].to_vec();
+        self.declare_struct_fields();
+    }
 
-                let a_number = a.value.parse::<i64>().unwrap_or_else(|_| {
-                    self.error(&format!(
-                        "Operator `+` expected a number to its left. Got `{}`",
-                        a.value
-                    ));
+    fn declare_struct_fields(&mut self) {
+        let Some((name, mut fields)) = self.declaring_struct.take() else {
+            return;
+        };
+
+        for token in &self.tokens_on_line {
+            if token.token_type == TokenType::Identifier {
+                fields.push(token.value.clone());
+            } else if token.token_type == TokenType::Operator && token.value == "}" {
+                self.structs.insert(name, fields);
+                return;
+            }
+        }
 
-                    0
-                });
+        self.declaring_struct = Some((name, fields));
+    }
 
-                let b_number = b.value.parse::<i64>().unwrap_or_else(|_| {
-                    self.error(&format!(
-                        "Operator `+` expected a number to its right. Got `{}`",
-                        b.value
-                    ));
+    // enum Name { Variant, Variant(value, ...), ... }
+    fn declare_enum(&mut self) {
+        let tokens = self.tokens_on_line.clone();
 
-                    0
-                });
+        if !is_block_start(&tokens, 3) {
+            self.syntax_error("`enum` expected a name followed by `{`");
+            return;
+        }
 
-                self.stack.push(Token {
-                    token_type: TokenType::Number,
-                    value: (a_number + b_number).to_string(),
-                })
+        // The variants may be on this line, or on the following ones.
+        self.declaring_enum = Some((tokens[1].value.clone(), vec![]));
+        self.declaring_line = self.line;
+        self.tokens_on_line = tokens[3..].to_vec();
+        self.declare_enum_variants();
+    }
+
+    fn declare_enum_variants(&mut self) {
+        let Some((name, mut variants)) = self.declaring_enum.take() else {
+            return;
+        };
+
+        let tokens = self.tokens_on_line.clone();
+        let mut i = 0;
+
+        while i < tokens.len() {
+            let token = &tokens[i];
+            i += 1;
+
+            if token.token_type == TokenType::Operator && token.value == "}" {
+                self.enums.insert(name, variants);
+                return;
             }
-        } else if operator == "=" {
-            self.resolve_variables = false;
 
-            if let Some((name, value)) = self.get_surrounding_operator("=") {
-                if name.token_type != TokenType::Identifier {
-                    self.error(&format!(
-                        "`{}` expected an identifier on its left, got: `{:?}`",
-                        operator, name.token_type
-                    ));
-                    return;
-                } else if value.token_type != TokenType::String
-                    && value.token_type != TokenType::Number
-                    && value.token_type != TokenType::Boolean
-                {
-                    self.error(&format!(
-                        "`{}` expected a string, number, or boolean, got identifier: `{}`",
-                        operator, value.value
-                    ));
-                    return;
-                }
+            if token.token_type != TokenType::Identifier {
+                self.syntax_error(&format!(
+                    "Expected a variant of `{}`, got: `{}`",
+                    name, token.value
+                ));
+                return;
+            }
 
-                self.variables.insert(
-                    name.value,
-                    Variable {
-                        variable_type: VariableType::from(value.token_type),
-                        value: value.value,
-                    },
-                );
+            // A variant that holds values, e.g. `Rect(w, h)`. The names only document what the values are.
+            let Some(variant) = token.value.strip_suffix('(') else {
+                variants.push((token.value.clone(), 0));
+                continue;
+            };
+
+            let Some(length) = tokens[i..]
+                .iter()
+                .position(|token| token.token_type == TokenType::Operator && token.value == ")")
+            else {
+                self.syntax_error(&format!("Variant `{}` is missing a closing `)`", variant));
+                return;
+            };
+
+            variants.push((variant.to_string(), length));
+            i += length + 1;
+        }
+
+        self.declaring_enum = Some((name, variants));
+    }
+
+    // Gives back an enum value, e.g. `Shape.Circle(5)`.
+    fn construct_variant(&mut self, enum_name: &str, variant: &str, payload: Vec<Token>) {
+        let variants = &self.enums[enum_name];
+
+        let Some(&(_, length)) = variants.iter().find(|(name, _)| name == variant) else {
+            self.error(&format!("`{}` has no variant `{}`", enum_name, variant));
+            return;
+        };
+
+        if payload.len() != length {
+            self.error(&format!(
+                "`{}.{}` expected {} argument(s), got {}",
+                enum_name,
+                variant,
+                length,
+                payload.len()
+            ));
+            return;
+        }
+
+        if let Some(value) = payload.iter().find(|value| !value.token_type.is_value()) {
+            self.error(&format!("Unknown identifier: `{}`", value.value));
+            return;
+        }
+
+        self.stack.push(Token {
+            token_type: TokenType::Enum,
+            value: format!("{}.{}", enum_name, variant),
+            items: payload,
+            ..Default::default()
+        });
+    }
+
+    // match value {, where `match` is at `keyword`.
+    fn start_match(&mut self, keyword: usize, result: MatchResult) {
+        let tokens = self.tokens_on_line[keyword + 1..].to_vec();
+
+        if tokens.len() < 2
+            || tokens[tokens.len() - 1].token_type != TokenType::Operator
+            || tokens[tokens.len() - 1].value != "{"
+        {
+            self.syntax_error("`match` expected a value followed by `{`");
+            return;
+        }
+
+        let Some(value) = self.evaluate_tokens(tokens[..tokens.len() - 1].to_vec()) else {
+            if !self.should_abort {
+                self.syntax_error("`match` expected a single value");
             }
+            return;
+        };
 
-            self.resolve_variables = true;
-        } else if operator == "==" {
-            if let Some((a, b)) = self.get_surrounding_operator("==") {
-                if a.token_type != TokenType::String
-                    && a.token_type != TokenType::Number
-                    && a.token_type != TokenType::Boolean
-                {
-                    self.error(&format!(
-                        "`{}` expected a string, number, or boolean, got identifier: `{}`",
-                        operator, a.value
-                    ));
-                    return;
-                } else if b.token_type != TokenType::String
-                    && b.token_type != TokenType::Number
-                    && b.token_type != TokenType::Boolean
-                {
-                    self.error(&format!(
-                        "`{}` expected a string, number, or boolean, got identifier: `{}`",
-                        operator, b.value
-                    ));
-                    return;
-                }
+        if self.should_abort {
+            return;
+        }
 
-                self.stack.push(Token {
-                    token_type: TokenType::Boolean,
-                    value: (a == b).to_string(),
-                })
+        if !value.token_type.is_value() {
+            self.error(&format!("Unknown identifier: `{}`", value.value));
+            return;
+        }
+
+        self.matching = Some(MatchBlock {
+            value,
+            result,
+            lines: vec![],
+            depth: 1,
+            line: self.line,
+        });
+    }
+
+    // Collects the arms of a `match` until its braces balance out, then runs the arm that matches.
+    fn collect_match_line(&mut self) {
+        let Some(mut block) = self.matching.take() else {
+            return;
+        };
+
+        let mut tokens = self.tokens_on_line.clone();
+
+        for token in &tokens {
+            if token.token_type == TokenType::Operator && token.value == "{" {
+                block.depth += 1;
+            } else if token.token_type == TokenType::Operator && token.value == "}" {
+                block.depth -= 1;
             }
-        } else if operator == "!=" {
-            if let Some((a, b)) = self.get_surrounding_operator("!=") {
-                if a.token_type != TokenType::String
-                    && a.token_type != TokenType::Number
-                    && a.token_type != TokenType::Boolean
-                {
-                    self.error(&format!(
-                        "`{}` expected a string, number, or boolean, got identifier: `{}`",
-                        operator, a.value
-                    ));
-                    return;
-                } else if b.token_type != TokenType::String
-                    && b.token_type != TokenType::Number
-                    && b.token_type != TokenType::Boolean
-                {
-                    self.error(&format!(
-                        "`{}` expected a string, number, or boolean, got identifier: `{}`",
-                        operator, b.value
-                    ));
-                    return;
-                }
+        }
 
-                self.stack.push(Token {
-                    token_type: TokenType::Boolean,
-                    value: (a != b).to_string(),
-                })
+        if block.depth > 0 {
+            block.lines.push((self.line, tokens));
+            self.matching = Some(block);
+            return;
+        }
+
+        // The closing `}` may come after the last arm, e.g. `_ => 0 }`.
+        tokens.pop();
+        if !tokens.is_empty() {
+            block.lines.push((self.line, tokens));
+        }
+
+        let Some(arms) = self.match_arms(block.lines) else {
+            return;
+        };
+
+        self.line = block.line;
+
+        if !self.check_exhaustive(&arms, &block.value) {
+            return;
+        }
+
+        let Some(value) = self.run_match(arms, &block.value) else {
+            self.line = block.line;
+            if !self.should_abort {
+                self.error(&format!(
+                    "No `match` arm matched the value `{}`",
+                    format_item(&block.value)
+                ));
             }
-        } else if operator == ">" {
-            if let Some((a, b)) = self.get_surrounding_operator(">") {
-                if a.token_type != TokenType::Number {
-                    self.error(&format!(
-                        "`{}` expected a number, got identifier: `{}`",
-                        operator, a.value
-                    ));
-                    return;
-                } else if b.token_type != TokenType::Number {
+            return;
+        };
+
+        self.line = block.line;
+
+        match block.result {
+            MatchResult::Discard => {}
+            MatchResult::Assign(name) => {
+                if !value.token_type.is_value() {
                     self.error(&format!(
-                        "`{}` expected a number, got identifier: `{}`",
-                        operator, b.value
+                        "`=` expected a value, got identifier: `{}`",
+                        value.value
                     ));
                     return;
                 }
 
-                self.stack.push(Token {
-                    token_type: TokenType::Boolean,
-                    value: (a.value.parse::<i128>().unwrap() > b.value.parse::<i128>().unwrap())
-                        .to_string(),
-                })
+                self.set_variable(name, value);
             }
-        } else if operator == "<" {
-            if let Some((a, b)) = self.get_surrounding_operator("<") {
-                if a.token_type != TokenType::Number {
-                    self.error(&format!(
-                        "`{}` expected a number, got identifier: `{}`",
-                        operator, a.value
-                    ));
-                    return;
-                } else if b.token_type != TokenType::Number {
-                    self.error(&format!(
-                        "`{}` expected a number, got identifier: `{}`",
-                        operator, b.value
-                    ));
-                    return;
+            MatchResult::Return => self.return_value = Some(value),
+        }
+    }
+
+    // Splits the lines of a `match` into arms: `pattern [if guard] => body`, where the body is either the
+    // rest of the line or a block that starts with `{` at the end of the line.
+    fn match_arms(&mut self, lines: Vec<(usize, Vec<Token>)>) -> Option<Vec<MatchArm>> {
+        let mut arms = vec![];
+        let mut lines = lines.into_iter();
+
+        while let Some((line, tokens)) = lines.next() {
+            if tokens.is_empty() {
+                continue;
+            }
+
+            self.line = line;
+
+            let Some(arrow) = tokens
+                .iter()
+                .position(|token| token.token_type == TokenType::Operator && token.value == "=>")
+            else {
+                self.syntax_error(
+                    "Every `match` arm needs a `=>` between its pattern and its body",
+                );
+                return None;
+            };
+
+            let guard = tokens[..arrow]
+                .iter()
+                .position(|token| token.token_type == TokenType::Identifier && token.value == "if");
+
+            let pattern = match Pattern::parse(&tokens[..guard.unwrap_or(arrow)]) {
+                Ok(pattern) => pattern,
+                Err(message) => {
+                    self.syntax_error(&message);
+                    return None;
                 }
+            };
 
-                self.stack.push(Token {
-                    token_type: TokenType::Boolean,
-                    value: (a.value.parse::<i128>().unwrap() < b.value.parse::<i128>().unwrap())
-                        .to_string(),
-                })
+            if !self.check_pattern(&pattern) {
+                return None;
             }
-        } else if operator == ">=" {
-            if let Some((a, b)) = self.get_surrounding_operator(">=") {
-                if a.token_type != TokenType::Number {
-                    self.error(&format!(
-                        "`{}` expected a number, got identifier: `{}`",
-                        operator, a.value
-                    ));
-                    return;
-                } else if b.token_type != TokenType::Number {
-                    self.error(&format!(
-                        "`{}` expected a number, got identifier: `{}`",
-                        operator, b.value
-                    ));
-                    return;
+
+            let guard = guard.map(|guard| tokens[guard + 1..arrow].to_vec());
+            let body = tokens[arrow + 1..].to_vec();
+
+            let is_block = body.len() == 1
+                && body[0].token_type == TokenType::Operator
+                && body[0].value == "{";
+
+            if !is_block {
+                arms.push(MatchArm {
+                    pattern,
+                    guard,
+                    body: vec![body],
+                    line,
+                });
+                continue;
+            }
+
+            // Collect the block until its braces balance out again.
+            let mut body = vec![];
+            let mut depth = 1;
+
+            for (_, mut tokens) in lines.by_ref() {
+                for token in &tokens {
+                    if token.token_type == TokenType::Operator && token.value == "{" {
+                        depth += 1;
+                    } else if token.token_type == TokenType::Operator && token.value == "}" {
+                        depth -= 1;
+                    }
                 }
 
-                self.stack.push(Token {
-                    token_type: TokenType::Boolean,
-                    value: (a.value.parse::<i128>().unwrap() >= b.value.parse::<i128>().unwrap())
-                        .to_string(),
-                })
+                if depth == 0 {
+                    tokens.pop();
+                    body.push(tokens);
+                    break;
+                }
+
+                body.push(tokens);
             }
-        } else if operator == "<=" {
-            if let Some((a, b)) = self.get_surrounding_operator("<=") {
-                if a.token_type != TokenType::Number {
-                    self.error(&format!(
-                        "`{}` expected a number, got identifier: `{}`",
-                        operator, a.value
-                    ));
-                    return;
-                } else if b.token_type != TokenType::Number {
+
+            if depth > 0 {
+                self.syntax_error("`=>` is missing a closing `}`");
+                return None;
+            }
+
+            arms.push(MatchArm {
+                pattern,
+                guard,
+                body,
+                line: line + 1,
+            });
+        }
+
+        Some(arms)
+    }
+
+    // Checks the structs and enums a pattern refers to.
+    fn check_pattern(&mut self, pattern: &Pattern) -> bool {
+        let mut error = None;
+
+        pattern.walk(&mut |pattern| {
+            if error.is_some() {
+                return;
+            }
+
+            match pattern {
+                Pattern::Struct { name, fields } => match self.structs.get(name) {
+                    None => error = Some(format!("Unknown struct: `{}`", name)),
+                    Some(declared) => {
+                        let unknown = fields.iter().find(|(field, _)| !declared.contains(field));
+
+                        if let Some((field, _)) = unknown {
+                            error = Some(format!("`{}` has no field `{}`", name, field));
+                        }
+                    }
+                },
+                Pattern::Variant {
+                    enum_name,
+                    variant,
+                    payload,
+                } => match self.enums.get(enum_name) {
+                    None => error = Some(format!("Unknown enum: `{}`", enum_name)),
+                    Some(variants) => match variants.iter().find(|(name, _)| name == variant) {
+                        None => {
+                            error = Some(format!("`{}` has no variant `{}`", enum_name, variant))
+                        }
+                        Some((_, length)) if *length != payload.len() => {
+                            error = Some(format!(
+                                "`{}.{}` holds {} value(s), but the pattern has {}",
+                                enum_name,
+                                variant,
+                                length,
+                                payload.len()
+                            ))
+                        }
+                        Some(_) => {}
+                    },
+                },
+                _ => {}
+            }
+        });
+
+        if let Some(message) = error {
+            self.error(&message);
+            return false;
+        }
+
+        true
+    }
+
+    // A `match` on an enum needs an arm for every variant, unless it has an arm that matches anything.
+    fn check_exhaustive(&mut self, arms: &[MatchArm], value: &Token) -> bool {
+        let unguarded = arms
+            .iter()
+            .filter(|arm| arm.guard.is_none())
+            .map(|arm| &arm.pattern)
+            .collect::<Vec<&Pattern>>();
+
+        if unguarded.iter().any(|pattern| pattern.is_irrefutable()) {
+            return true;
+        }
+
+        let mut enum_names = vec![];
+
+        if value.token_type == TokenType::Enum {
+            enum_names.push(type_name(value));
+        }
+
+        for arm in arms {
+            if let Pattern::Variant { enum_name, .. } = &arm.pattern {
+                if !enum_names.contains(enum_name) {
+                    enum_names.push(enum_name.clone());
+                }
+            }
+        }
+
+        for enum_name in enum_names {
+            let Some(variants) = self.enums.get(&enum_name).cloned() else {
+                continue;
+            };
+
+            for (variant, _) in variants {
+                let is_covered = unguarded.iter().any(|pattern| match pattern {
+                    Pattern::Variant {
+                        enum_name: name,
+                        variant: covered,
+                        payload,
+                    } => {
+                        *name == enum_name
+                            && *covered == variant
+                            && payload.iter().all(Pattern::is_irrefutable)
+                    }
+                    _ => false,
+                });
+
+                if !is_covered {
                     self.error(&format!(
-                        "`{}` expected a number, got identifier: `{}`",
-                        operator, b.value
+                        "Non-exhaustive `match`: `{}.{}` is not covered",
+                        enum_name, variant
                     ));
-                    return;
+                    return false;
                 }
+            }
+        }
 
-                self.stack.push(Token {
-                    token_type: TokenType::Boolean,
-                    value: (a.value.parse::<i128>().unwrap() <= b.value.parse::<i128>().unwrap())
-                        .to_string(),
-                })
+        true
+    }
+
+    // Runs the first arm that matches `value`, giving back the value of its body.
+    fn run_match(&mut self, arms: Vec<MatchArm>, value: &Token) -> Option<Token> {
+        for arm in arms {
+            let mut bindings = vec![];
+
+            if !arm.pattern.matches(value, &mut bindings) {
+                continue;
             }
-        } else if operator == "&&" {
-            if let Some((a, b)) = self.get_surrounding_operator("&&") {
-                if a.token_type != TokenType::Boolean {
-                    self.error(&format!(
-                        "`{}` expected a boolean, got identifier: `{}`",
-                        operator, a.value
-                    ));
-                    return;
-                } else if b.token_type != TokenType::Boolean {
-                    self.error(&format!(
-                        "`{}` expected a boolean, got identifier: `{}`",
-                        operator, b.value
-                    ));
-                    return;
+
+            // The names bound by the pattern only exist inside of the arm.
+            let shadowed = bindings
+                .iter()
+                .map(|(name, _)| (name.clone(), self.variables.get(name).cloned()))
+                .collect::<Vec<(String, Option<Variable>)>>();
+
+            for (name, value) in bindings {
+                self.set_variable(name, value);
+            }
+
+            let is_match = match arm.guard {
+                None => true,
+                Some(guard) => {
+                    self.line = arm.line;
+
+                    match self.evaluate_tokens(guard) {
+                        Some(token) if token.token_type == TokenType::Boolean => {
+                            token.value == "true"
+                        }
+                        _ if self.should_abort => false,
+                        Some(token) => {
+                            self.error(&format!(
+                                "`if` expected a boolean, got: `{}`",
+                                format_item(&token)
+                            ));
+                            false
+                        }
+                        None => {
+                            self.error("`if` expected a single value");
+                            false
+                        }
+                    }
                 }
+            };
 
-                self.stack.push(Token {
-                    token_type: TokenType::Boolean,
-                    value: (a.value.parse().unwrap() && b.value.parse().unwrap()).to_string(),
-                })
+            let result = if is_match {
+                let stack_start = self.stack.len();
+                self.run(arm.body, arm.line);
+
+                let result = if self.stack.len() > stack_start {
+                    self.stack.pop().unwrap()
+                } else {
+                    nil()
+                };
+
+                self.stack.truncate(stack_start);
+                Some(result)
+            } else {
+                None
+            };
+
+            for (name, variable) in shadowed {
+                match variable {
+                    Some(variable) => self.variables.insert(name, variable),
+                    None => self.variables.remove(&name),
+                };
             }
-        } else if operator == "||" {
-            if let Some((a, b)) = self.get_surrounding_operator("||") {
-                if a.token_type != TokenType::Boolean {
-                    self.error(&format!(
-                        "`{}` expected a boolean, got identifier: `{}`",
-                        operator, a.value
-                    ));
-                    return;
-                } else if b.token_type != TokenType::Boolean {
-                    self.error(&format!(
-                        "`{}` expected a boolean, got identifier: `{}`",
-                        operator, b.value
-                    ));
+
+            if self.should_abort {
+                return None;
+            }
+
+            if result.is_some() {
+                return result;
+            }
+        }
+
+        None
+    }
+
+    // impl Name {
+    fn declare_impl(&mut self) {
+        if !is_block_start(&self.tokens_on_line, 3) || self.tokens_on_line.len() != 3 {
+            self.syntax_error("`impl` expected a name followed by `{`");
+            return;
+        }
+
+        let type_name = self.tokens_on_line[1].value.clone();
+
+        if !self.structs.contains_key(&type_name) && !self.enums.contains_key(&type_name) {
+            self.error(&format!(
+                "Cannot implement methods for `{}`, which is not a struct or enum",
+                type_name
+            ));
+            return;
+        }
+
+        self.declaring_impl = Some(ImplBlock {
+            type_name,
+            method: None,
+            depth: 0,
+        });
+        self.declaring_line = self.line;
+    }
+
+    fn declare_impl_line(&mut self) {
+        let Some(mut block) = self.declaring_impl.take() else {
+            return;
+        };
+
+        let tokens = self.tokens_on_line.clone();
+
+        // Inside a method body, collect lines until the braces balance out again.
+        if let Some((name, mut method)) = block.method.take() {
+            for token in &tokens {
+                if token.token_type == TokenType::Operator && token.value == "{" {
+                    block.depth += 1;
+                } else if token.token_type == TokenType::Operator && token.value == "}" {
+                    block.depth -= 1;
+                }
+            }
+
+            if block.depth == 0 {
+                self.methods
+                    .entry(block.type_name.clone())
+                    .or_default()
+                    .insert(name, method);
+            } else {
+                method.body.push(tokens);
+                block.method = Some((name, method));
+            }
+
+            self.declaring_impl = Some(block);
+            return;
+        }
+
+        let Some(first) = tokens.first() else {
+            self.declaring_impl = Some(block);
+            return;
+        };
+
+        if first.token_type == TokenType::Operator && first.value == "}" {
+            return;
+        }
+
+        if first.token_type != TokenType::Identifier || first.value != "fn" {
+            self.error("Expected `fn` or `}` inside of an `impl` block");
+            return;
+        }
+
+        // fn name(self, parameter, ...) {
+        let Some(opening) = tokens
+            .iter()
+            .position(|token| token.token_type == TokenType::Operator && token.value == "{")
+        else {
+            self.syntax_error("`fn` expected a name followed by its parameters and `{`");
+            return;
+        };
+
+        let signature = &tokens[1..opening];
+
+        let is_valid = signature.len() >= 2
+            && signature[0].value.ends_with('(')
+            && signature[signature.len() - 1].value == ")"
+            && signature[1..signature.len() - 1]
+                .iter()
+                .all(|token| token.token_type == TokenType::Identifier);
+
+        if !is_valid {
+            self.syntax_error("`fn` expected a name followed by its parameters and `{`");
+            return;
+        }
+
+        let name = signature[0].value.trim_end_matches('(').to_string();
+        let parameters = signature[1..signature.len() - 1]
+            .iter()
+            .map(|token| token.value.clone())
+            .collect::<Vec<String>>();
+
+        if parameters.first().map(String::as_str) != Some("self") {
+            self.error(&format!(
+                "Method `{}` must take `self` as its first parameter",
+                name
+            ));
+            return;
+        }
+
+        let mut method = Function {
+            parameters,
+            body: vec![],
+            line: self.line + 1,
+        };
+
+        // A method written on a single line, e.g. `fn x(self) { return self.x }`.
+        let rest = &tokens[opening + 1..];
+        if rest
+            .last()
+            .is_some_and(|token| token.token_type == TokenType::Operator && token.value == "}")
+        {
+            method.body.push(rest[..rest.len() - 1].to_vec());
+            method.line = self.line;
+
+            self.methods
+                .entry(block.type_name.clone())
+                .or_default()
+                .insert(name, method);
+        } else {
+            block.method = Some((name, method));
+            block.depth = 1;
+        }
+
+        self.declaring_impl = Some(block);
+    }
+
+    // return value
+    fn parse_return(&mut self) {
+        if self.call_depth == 0 {
+            self.syntax_error("`return` can only be used inside of a method");
+            return;
+        }
+
+        self.tokens_on_line.remove(0);
+        let stack_start = self.stack.len();
+
+        self.evaluate();
+
+        if self.should_abort {
+            return;
+        }
+
+        // Values are pushed right to left, so the leftmost one is on top of the stack.
+        let mut values = self.stack.split_off(stack_start);
+        values.reverse();
+
+        if let Some(value) = values.iter().find(|value| !value.token_type.is_value()) {
+            self.error(&format!("Unknown identifier: `{}`", value.value));
+            return;
+        }
+
+        // `return a, b` gives back both of them as a tuple.
+        let value = match values.len() {
+            0 => nil(),
+            1 => values.remove(0),
+            _ => Token {
+                token_type: TokenType::Tuple,
+                items: values,
+                ..Default::default()
+            },
+        };
+
+        self.return_value = Some(value);
+    }
+
+    // import name, import "path/to/file.milo", or either of them followed by `as name`
+    fn parse_import(&mut self) {
+        let tokens = self.tokens_on_line[1..].to_vec();
+
+        let (target, alias) = match tokens.as_slice() {
+            [target] => (target, None),
+            [target, keyword, alias]
+                if keyword.value == "as" && alias.token_type == TokenType::Identifier =>
+            {
+                (target, Some(alias.value.clone()))
+            }
+            _ => {
+                self.syntax_error(
+                    "`import` expected a module name or path, and optionally `as name`",
+                );
+                return;
+            }
+        };
+
+        // Built-in modules, e.g. `import math`, win over files with the same name.
+        if let Some(module) =
+            builtins::module(&target.value).filter(|_| target.token_type == TokenType::Identifier)
+        {
+            self.set_variable(alias.unwrap_or(target.value.clone()), module);
+            return;
+        }
+
+        let Some((path, default_name)) = import_path(
+            target,
+            self.file_path.as_deref(),
+            &self.dependencies,
+            &self.module_paths,
+        ) else {
+            self.syntax_error(&format!(
+                "`import` expected a module name or path, got: `{}`",
+                target.value
+            ));
+            return;
+        };
+
+        let Ok(canonical) = fs::canonicalize(&path) else {
+            self.error(&format!("Cannot import `{}`, no such file", path.display()));
+            return;
+        };
+
+        let name = alias.unwrap_or(default_name);
+
+        let mut chain = self.import_chain.clone();
+        chain.extend(self.file_path.clone());
+
+        if chain
+            .iter()
+            .any(|importer| fs::canonicalize(importer).is_ok_and(|importer| importer == canonical))
+        {
+            chain.push(path);
+
+            self.error(&format!(
+                "Circular import: {}",
+                chain
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<String>>()
+                    .join(" -> ")
+            ));
+            return;
+        }
+
+        let module = match self.modules.get(&canonical) {
+            Some(module) => module.clone(),
+            None => {
+                let Some(module) = self.run_module(path, chain) else {
                     return;
+                };
+
+                self.modules.insert(canonical, module.clone());
+                module
+            }
+        };
+
+        // The types of a module are only known by their name in it, e.g. `geometry.Point`, so
+        // they don't clash with the types of the importing file or of other modules.
+        let types = module
+            .structs
+            .keys()
+            .chain(module.enums.keys())
+            .cloned()
+            .collect::<Vec<String>>();
+        let qualify = |type_name: &str| {
+            types
+                .iter()
+                .any(|other| other == type_name)
+                .then(|| format!("{}.{}", name, type_name))
+        };
+
+        for (type_name, fields) in module.structs {
+            self.structs.insert(qualify(&type_name).unwrap(), fields);
+        }
+
+        for (type_name, variants) in module.enums {
+            self.enums.insert(qualify(&type_name).unwrap(), variants);
+        }
+
+        for (type_name, mut methods) in module.methods {
+            for method in methods.values_mut() {
+                for line in &mut method.body {
+                    for i in 0..line.len() {
+                        // `p.Point` is a field, not the type.
+                        let is_field = i > 0
+                            && line[i - 1].token_type == TokenType::Operator
+                            && line[i - 1].value == ".";
+
+                        if line[i].token_type == TokenType::Identifier && !is_field {
+                            if let Some(qualified) = qualify(&line[i].value) {
+                                line[i].value = qualified;
+                            }
+                        }
+                    }
+                }
+            }
+
+            self.methods
+                .insert(qualify(&type_name).unwrap_or(type_name), methods);
+        }
+
+        let mut exports = module.exports;
+        exports.items = exports
+            .items
+            .into_iter()
+            .map(|item| rename_types(item, &qualify))
+            .collect();
+        exports.value.clone_from(&name);
+        self.set_variable(name, exports);
+    }
+
+    // Runs a module with its own variables and types, giving back what it exports.
+    fn run_module(&mut self, path: PathBuf, import_chain: Vec<PathBuf>) -> Option<Module> {
+        let code = match fs::read_to_string(&path) {
+            Ok(code) => code,
+            Err(error) => {
+                self.error(&format!("Cannot import `{}`, {}", path.display(), error));
+                return None;
+            }
+        };
+
+        let tokens = Lexer::default().lex_code(code);
+
+        let mut parser = Parser {
+            file_path: Some(path),
+            permissions: self.permissions.clone(),
+            script_args: self.script_args.clone(),
+            module_paths: self.module_paths.clone(),
+            dependencies: self.dependencies.clone(),
+            modules: std::mem::take(&mut self.modules),
+            import_chain,
+            // Errors in the module can be caught by a `try` block around the `import`.
+            try_depth: self.try_depth,
+            ..Default::default()
+        };
+
+        match tokens {
+            Ok(tokens) => {
+                parser.run(tokens, 0);
+                parser.finish();
+            }
+            Err(error) => parser.lex_error(error),
+        }
+
+        self.modules = std::mem::take(&mut parser.modules);
+
+        if parser.should_abort {
+            self.exit_code = parser.exit_code;
+
+            match parser.exception.take() {
+                Some((value, _)) => {
+                    self.error_kind = parser.error_kind;
+                    self.throw(value);
+                }
+                // The module already printed the error.
+                None => {
+                    self.failure = parser.failure;
+                    self.should_abort = true;
                 }
+            }
+            return None;
+        }
+
+        let mut module = Module {
+            exports: Token {
+                token_type: TokenType::Module,
+                ..Default::default()
+            },
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            methods: HashMap::new(),
+        };
+
+        for name in parser.exports.clone() {
+            if let Some(value) = parser.try_parse_variable(&name) {
+                module.exports.keys.push(Token {
+                    token_type: TokenType::String,
+                    value: name.clone(),
+                    ..Default::default()
+                });
+                module.exports.items.push(value);
+            }
+
+            if let Some(fields) = parser.structs.remove(&name) {
+                module.structs.insert(name.clone(), fields);
+            }
+
+            if let Some(variants) = parser.enums.remove(&name) {
+                module.enums.insert(name.clone(), variants);
+            }
+
+            if let Some(methods) = parser.methods.remove(&name) {
+                module.methods.insert(name, methods);
+            }
+        }
+
+        Some(module)
+    }
+
+    // export name, export name = value, export struct ..., or export enum ...
+    fn parse_export(&mut self) {
+        if self.call_depth > 0 {
+            self.syntax_error("`export` can only be used at the top level of a file");
+            return;
+        }
+
+        self.tokens_on_line.remove(0);
+        let tokens = self.tokens_on_line.clone();
+
+        let is_declaration = tokens.len() >= 2
+            && tokens[0].token_type == TokenType::Identifier
+            && (tokens[0].value == "struct" || tokens[0].value == "enum");
+
+        let is_assignment = tokens.len() >= 3
+            && tokens[0].token_type == TokenType::Identifier
+            && tokens[1].token_type == TokenType::Operator
+            && tokens[1].value == "=";
+
+        if is_declaration {
+            self.exports.push(tokens[1].value.clone());
+        } else if is_assignment {
+            self.exports.push(tokens[0].value.clone());
+        } else if let [name] = tokens.as_slice() {
+            let exists = self.variables.contains_key(&name.value)
+                || self.structs.contains_key(&name.value)
+                || self.enums.contains_key(&name.value);
+
+            if name.token_type != TokenType::Identifier || !exists {
+                self.error(&format!(
+                    "Cannot export `{}`, which doesn't exist",
+                    name.value
+                ));
+                return;
+            }
+
+            self.exports.push(name.value.clone());
+            return;
+        } else {
+            self.syntax_error("`export` expected a name, an assignment, or a `struct` or `enum`");
+            return;
+        }
+
+        if !self.parse_statement() {
+            self.evaluate();
+        }
+    }
+
+    // throw value
+    fn parse_throw(&mut self) {
+        self.tokens_on_line.remove(0);
+
+        let Some(value) = self.evaluate_tokens(self.tokens_on_line.clone()) else {
+            if !self.should_abort {
+                self.syntax_error("`throw` expected a single value");
+            }
+            return;
+        };
+
+        if self.should_abort {
+            return;
+        }
+
+        if !value.token_type.is_value() {
+            self.error(&format!("Unknown identifier: `{}`", value.value));
+            return;
+        }
+
+        self.error_kind = ErrorKind::Runtime;
+        self.throw(value);
+    }
+
+    // Collects the `try`, `catch` and `finally` blocks until the last of them is closed, then runs them.
+    fn collect_try_line(&mut self) {
+        let Some(mut block) = self.trying.take() else {
+            return;
+        };
+
+        let mut tokens = self.tokens_on_line.clone().into_iter();
+        let mut line = vec![];
+
+        while let Some(token) = tokens.next() {
+            let is_operator = token.token_type == TokenType::Operator;
+
+            // Between blocks: `try {`, `} catch name {` or `} finally {`.
+            if block.depth == 0 {
+                let has_catch = block
+                    .sections
+                    .iter()
+                    .any(|section| matches!(section.kind, TrySectionKind::Catch(_)));
+                let has_finally = block
+                    .sections
+                    .iter()
+                    .any(|section| section.kind == TrySectionKind::Finally);
+
+                let mut opening = tokens.next();
+
+                let kind = match token.value.as_str() {
+                    "try" if block.sections.is_empty() => TrySectionKind::Try,
+                    "catch" if !block.sections.is_empty() && !has_catch && !has_finally => {
+                        // The name is optional, e.g. `catch {`.
+                        let name = opening
+                            .take_if(|name| name.token_type == TokenType::Identifier)
+                            .map(|name| name.value);
+
+                        if name.is_some() {
+                            opening = tokens.next();
+                        }
+
+                        TrySectionKind::Catch(name)
+                    }
+                    "finally" if !block.sections.is_empty() && !has_finally => {
+                        TrySectionKind::Finally
+                    }
+                    _ => {
+                        self.error(&format!(
+                            "Expected `catch` or `finally` after a `try` block, got: `{}`",
+                            token.value
+                        ));
+                        return;
+                    }
+                };
+
+                if !opening.is_some_and(|opening| {
+                    opening.token_type == TokenType::Operator && opening.value == "{"
+                }) {
+                    self.syntax_error(&format!("`{}` expected a `{{`", token.value));
+                    return;
+                }
+
+                block.sections.push(TrySection {
+                    kind,
+                    body: vec![],
+                    line: self.line,
+                });
+                block.depth = 1;
+                continue;
+            }
+
+            if is_operator && token.value == "{" {
+                block.depth += 1;
+            } else if is_operator && token.value == "}" {
+                block.depth -= 1;
+
+                if block.depth == 0 {
+                    let section = block.sections.last_mut().unwrap();
+                    if !line.is_empty() || section.body.is_empty() {
+                        section.body.push(std::mem::take(&mut line));
+                    }
+                    continue;
+                }
+            }
+
+            line.push(token);
+        }
+
+        if block.depth > 0 {
+            block.sections.last_mut().unwrap().body.push(line);
+            self.trying = Some(block);
+            return;
+        }
+
+        if block.sections.len() < 2 {
+            self.syntax_error("`try` expected a `catch` or `finally` block after it");
+            return;
+        }
+
+        self.run_try(block);
+    }
+
+    // Collects the body of a test until its `}`. The test is kept for `milo test` to run.
+    fn collect_test_line(&mut self) {
+        let Some((mut test, mut depth)) = self.testing.take() else {
+            return;
+        };
+
+        let mut tokens = self.tokens_on_line.clone().into_iter();
+        let mut line = vec![];
+
+        for token in tokens.by_ref() {
+            if token.token_type == TokenType::Operator && token.value == "{" {
+                depth += 1;
+            } else if token.token_type == TokenType::Operator && token.value == "}" {
+                depth -= 1;
+
+                if depth == 0 {
+                    break;
+                }
+            }
+
+            line.push(token);
+        }
+
+        if depth > 0 {
+            test.body.push(line);
+            self.testing = Some((test, depth));
+            return;
+        }
+
+        if !line.is_empty() || test.body.is_empty() {
+            test.body.push(line);
+        }
+
+        if let Some(token) = tokens.next() {
+            self.syntax_error(&format!(
+                "Expected nothing after the `}}` of a test, got: `{}`",
+                token.value
+            ));
+            return;
+        }
+
+        if self.tests.iter().any(|other| other.name == test.name) {
+            self.syntax_error(&format!("There is already a test called \"{}\"", test.name));
+            return;
+        }
+
+        self.tests.push(test);
+    }
+
+    // Runs a test on its own. Whatever it assigns is thrown away afterwards, so tests can't affect
+    // each other.
+    pub fn run_test(&mut self, test: &Test) -> Result<(), TestFailure> {
+        let variables = self.variables.clone();
+        let stack_start = self.stack.len();
+
+        self.try_depth += 1;
+        self.run(test.body.clone(), test.line);
+        self.try_depth -= 1;
+
+        let exception = self.exception.take();
+        let exit_code = self.exit_code.take();
+
+        self.variables = variables;
+        self.stack.truncate(stack_start);
+        self.should_abort = false;
+        self.return_value = None;
+        (self.matching, self.trying, self.looping) = (None, None, None);
+
+        if let Some(code) = exit_code {
+            return Err(TestFailure {
+                message: format!("The test called `exit({})`", code),
+                line: self.line,
+            });
+        }
+
+        match exception {
+            Some((value, line)) => Err(TestFailure {
+                message: match value.token_type {
+                    TokenType::String => value.value,
+                    _ => format_item(&value),
+                },
+                line,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    // assert_throws(value) or assert_throws(value, expected), where `expected` is the error, or
+    // the message of an error raised by Milo itself.
+    fn assert_throws(&mut self) {
+        let end = checker::closing(&self.tokens_on_line, 0);
+
+        if end + 1 < self.tokens_on_line.len() {
+            self.syntax_error("`assert_throws` can't be part of a bigger expression");
+            return;
+        }
+
+        let arguments = self.tokens_on_line[1..end.min(self.tokens_on_line.len())].to_vec();
+
+        // Commas aren't kept, so the value ends where another value starts: outside of brackets,
+        // and not next to an operator.
+        let mut depth = 0;
+        let mut split = arguments.len();
+
+        for (i, token) in arguments.iter().enumerate() {
+            let is_operator = |token: &Token, except: &[&str]| {
+                token.token_type == TokenType::Operator && !except.contains(&token.value.as_str())
+            };
+
+            if i > 0
+                && depth == 0
+                && !is_operator(&arguments[i - 1], &[")", "]", "}"])
+                && !is_operator(token, &["(", "["])
+            {
+                split = i;
+                break;
+            }
+
+            if checker::is_opening(token) {
+                depth += 1;
+            } else if checker::is_closing(token) {
+                depth -= 1;
+            }
+        }
+
+        let (value, expected) = arguments.split_at(split);
+
+        if value.is_empty() {
+            self.error("`assert_throws` expected 1 or 2 argument(s), got 0");
+            return;
+        }
+
+        let expected = if expected.is_empty() {
+            None
+        } else {
+            match self.evaluate_tokens(expected.to_vec()) {
+                Some(expected) => Some(expected),
+                None if self.should_abort => return,
+                None => {
+                    self.error("`assert_throws` expected 1 or 2 argument(s)");
+                    return;
+                }
+            }
+        };
+
+        self.try_depth += 1;
+        let result = self.evaluate_tokens(value.to_vec());
+        self.try_depth -= 1;
+
+        let exception = self.exception.take();
+        self.should_abort = false;
+
+        let Some((thrown, _)) = exception else {
+            let result = result.map_or("nothing".to_string(), |result| format_item(&result));
+            self.error(&format!("Expected an error, but got `{}`", result));
+            return;
+        };
+
+        if let Some(expected) = expected.filter(|expected| !is_equal(expected, &thrown)) {
+            self.error(&format!(
+                "Expected a different error\n{}",
+                builtins::difference(&expected, &thrown)
+            ));
+        }
+    }
+
+    fn run_try(&mut self, block: TryBlock) {
+        let mut sections = block.sections.into_iter();
+        let body = sections.next().unwrap();
+
+        let mut catch = None;
+        let mut finally = None;
+
+        for section in sections {
+            match section.kind {
+                TrySectionKind::Catch(ref name) => catch = Some((name.clone(), section)),
+                _ => finally = Some(section),
+            }
+        }
+
+        let stack_start = self.stack.len();
+
+        // Errors inside of the `catch` block are only thrown further after the `finally` block has run.
+        self.try_depth += 1;
+        self.run(body.body, body.line);
+
+        if self.should_abort && self.exit_code.is_none() {
+            if let Some((name, catch)) = catch {
+                let exception = self.exception.take();
+                self.should_abort = false;
+
+                // The caught error only exists inside of the `catch` block.
+                let shadowed = name
+                    .as_ref()
+                    .map(|name| (name.clone(), self.variables.get(name).cloned()));
+
+                if let (Some(name), Some((value, _))) = (&name, exception) {
+                    self.set_variable(name.clone(), value);
+                }
+
+                if finally.is_none() {
+                    self.try_depth -= 1;
+                }
+
+                self.run(catch.body, catch.line);
+
+                if finally.is_none() {
+                    self.try_depth += 1;
+                }
+
+                if let Some((name, variable)) = shadowed {
+                    match variable {
+                        Some(variable) => self.variables.insert(name, variable),
+                        None => self.variables.remove(&name),
+                    };
+                }
+            }
+        }
+
+        self.try_depth -= 1;
+        self.stack.truncate(stack_start);
+
+        let exception = if self.should_abort {
+            self.exception.take()
+        } else {
+            None
+        };
+
+        if let Some(finally) = finally.filter(|_| self.exit_code.is_none()) {
+            // A `return` in the `try` or `catch` block still runs the `finally` block first.
+            let returned = self.return_value.take();
+            self.should_abort = false;
+
+            self.run(finally.body, finally.line);
+            self.stack.truncate(stack_start);
+
+            // Errors and returns in the `finally` block replace the ones from before it.
+            if self.should_abort {
+                return;
+            }
+
+            if self.return_value.is_none() {
+                self.return_value = returned;
+            }
+        }
+
+        if let Some((value, line)) = exception {
+            self.line = line;
+            self.throw(value);
+        }
+    }
+
+    // for item in value {, or for key, value in map {
+    fn start_loop(&mut self) {
+        let tokens = self.tokens_on_line.clone();
+
+        let Some((in_index, opening)) = checker::loop_bounds(&tokens) else {
+            self.syntax_error("`for` expected a name, `in` and a value followed by `{`");
+            return;
+        };
+
+        let names = &tokens[1..in_index];
+
+        if names.is_empty()
+            || names.len() > 2
+            || names
+                .iter()
+                .any(|name| name.token_type != TokenType::Identifier || name.value.ends_with('('))
+        {
+            self.syntax_error("`for` expected one or two names before `in`");
+            return;
+        }
+
+        let Some(iterable) = self.evaluate_tokens(tokens[in_index + 1..opening].to_vec()) else {
+            if !self.should_abort {
+                self.syntax_error("`for` expected a single value");
+            }
+            return;
+        };
+
+        if self.should_abort {
+            return;
+        }
+
+        if !iterable.token_type.is_value() {
+            self.error(&format!("Unknown identifier: `{}`", iterable.value));
+            return;
+        }
+
+        if !matches!(
+            iterable.token_type,
+            TokenType::List | TokenType::Tuple | TokenType::Map | TokenType::String
+        ) {
+            self.error(&format!("`for` can't loop over a {}", type_name(&iterable)));
+            return;
+        }
+
+        self.looping = Some(LoopBlock {
+            names: names.iter().map(|name| name.value.clone()).collect(),
+            iterable,
+            body: vec![],
+            depth: 1,
+            line: self.line,
+        });
+
+        self.tokens_on_line.drain(..=opening);
+        self.collect_loop_line();
+    }
+
+    // Collects the body of a `for` loop until its `}`, then runs it.
+    fn collect_loop_line(&mut self) {
+        let Some(mut block) = self.looping.take() else {
+            return;
+        };
+
+        let mut tokens = self.tokens_on_line.clone().into_iter();
+        let mut line = vec![];
+
+        for token in tokens.by_ref() {
+            if token.token_type == TokenType::Operator && token.value == "{" {
+                block.depth += 1;
+            } else if token.token_type == TokenType::Operator && token.value == "}" {
+                block.depth -= 1;
+
+                if block.depth == 0 {
+                    break;
+                }
+            }
+
+            line.push(token);
+        }
+
+        if block.depth > 0 {
+            block.body.push(line);
+            self.looping = Some(block);
+            return;
+        }
+
+        if !line.is_empty() || block.body.is_empty() {
+            block.body.push(line);
+        }
+
+        if let Some(token) = tokens.next() {
+            self.syntax_error(&format!(
+                "Expected nothing after the `}}` of a `for` loop, got: `{}`",
+                token.value
+            ));
+            return;
+        }
+
+        self.run_loop(block);
+    }
+
+    // Runs the body once for every item. With two names, the first is the index of the item, or
+    // the key for a map.
+    fn run_loop(&mut self, block: LoopBlock) {
+        let iterable = block.iterable;
+        let two_names = block.names.len() == 2;
+
+        let items: Vec<Vec<Token>> = match iterable.token_type {
+            TokenType::String => iterable
+                .value
+                .chars()
+                .enumerate()
+                .map(|(i, char)| vec![builtins::number(i), builtins::string(char.to_string())])
+                .collect(),
+            TokenType::Map => iterable
+                .keys
+                .into_iter()
+                .zip(iterable.items)
+                .map(|(key, value)| vec![key, value])
+                .collect(),
+            _ => iterable
+                .items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| vec![builtins::number(i), item])
+                .collect(),
+        };
+
+        // The names only exist inside of the loop.
+        let shadowed = block
+            .names
+            .iter()
+            .map(|name| (name.clone(), self.variables.get(name).cloned()))
+            .collect::<Vec<(String, Option<Variable>)>>();
+
+        let stack_start = self.stack.len();
+
+        for mut item in items {
+            // A single name gets the item of a list or string, but the key of a map.
+            if !two_names && iterable.token_type != TokenType::Map {
+                item.remove(0);
+            }
+
+            for (name, value) in block.names.iter().zip(item) {
+                self.set_variable(name.clone(), value);
+            }
+
+            self.run(block.body.clone(), block.line);
+            self.stack.truncate(stack_start);
+
+            if self.should_abort || self.return_value.is_some() {
+                break;
+            }
+        }
+
+        for (name, variable) in shadowed {
+            match variable {
+                Some(variable) => self.variables.insert(name, variable),
+                None => self.variables.remove(&name),
+            };
+        }
+    }
+
+    fn call_method(
+        &mut self,
+        name: &str,
+        method: Function,
+        receiver: Token,
+        place: Option<Place>,
+        arguments: Vec<Token>,
+    ) {
+        if self.call_depth >= MAX_CALL_DEPTH {
+            self.error(&format!(
+                "Method `{}` called itself too many times (more than {})",
+                name, MAX_CALL_DEPTH
+            ));
+            return;
+        }
+
+        if arguments.len() != method.parameters.len() - 1 {
+            self.error(&format!(
+                "`{}` expected {} argument(s), got {}",
+                name,
+                method.parameters.len() - 1,
+                arguments.len()
+            ));
+            return;
+        }
+
+        // Methods can read the variables around them, but anything they assign (except to `self`) is thrown away.
+        let outer_variables = self.variables.clone();
+
+        self.set_variable("self".to_string(), receiver);
+        for (parameter, argument) in method.parameters[1..].iter().zip(arguments) {
+            self.set_variable(parameter.clone(), argument);
+        }
+
+        let tokens_on_line = std::mem::take(&mut self.tokens_on_line);
+        let (line, index, current_token) = (self.line, self.index, self.current_token.clone());
+        let (skip_tokens, line_stack_start) = (self.skip_tokens, self.line_stack_start);
+        let stack_start = self.stack.len();
+
+        self.call_depth += 1;
+        self.run(method.body, method.line);
+        self.call_depth -= 1;
+
+        let returned = self.return_value.take().unwrap_or(nil());
+        let receiver = self.try_parse_variable(&"self".to_string());
+
+        self.stack.truncate(stack_start);
+        self.tokens_on_line = tokens_on_line;
+        (self.line, self.index, self.current_token) = (line, index, current_token);
+        (self.skip_tokens, self.line_stack_start) = (skip_tokens, line_stack_start);
+        self.skip_line = false;
+        self.variables = outer_variables;
+
+        if self.should_abort {
+            return;
+        }
+
+        if let (Some(place), Some(receiver)) = (place, receiver) {
+            self.write_back(&place, receiver);
+
+            if self.should_abort {
+                return;
+            }
+        }
+
+        self.stack.push(returned);
+    }
+
+    fn call_builtin_method(
+        &mut self,
+        name: &str,
+        receiver: Token,
+        place: Option<Place>,
+        mut arguments: Vec<Token>,
+    ) {
+        // The receiver becomes the first argument.
+        arguments.insert(0, receiver);
+
+        self.method_receiver = Some(place);
+        self.call_function(name, arguments);
+        self.method_receiver = None;
+    }
+
+    fn parse_identifier(&mut self, identifier: String) {
+        // Calls are lexed as the name followed by `(`, e.g. `print(`.
+        if let Some(name) = identifier.strip_suffix('(') {
+            // Method calls (`p.len()`) are handled by the `.` to their left, which collects the arguments.
+            if self.is_field_name() {
+                return;
+            }
+
+            if let Some(arguments) = self.pop_until_closing_bracket(name, ")") {
+                self.call_function(name, arguments);
+            }
+
+            return;
+        }
+
+        // Field names (`p.x` or `Point { x: 1 }`) are names, not variables.
+        if self.is_field_name() {
+            self.stack.push(Token {
+                token_type: TokenType::String,
+                value: identifier,
+                ..Default::default()
+            });
+            return;
+        }
+
+        let var = self.try_parse_variable(&identifier);
+        if let Some(variable) = var {
+            self.stack.push(variable);
+        } else {
+            self.error(&format!("Unknown identifier: `{}`", identifier));
+        }
+    }
+
+    fn call_function(&mut self, name: &str, arguments: Vec<Token>) {
+        match builtins::get(name) {
+            Some(builtin) => (builtin.function)(self, arguments),
+            None => self.error(&format!("Unknown function: `{}`", name)),
+        }
+    }
+
+    // Reports the first operand that isn't what `operator` expected, e.g. a list given to `<`.
+    fn operands_are(
+        &mut self,
+        operator: &str,
+        operands: [&Token; 2],
+        expected: &str,
+        is_expected: impl Fn(&Token) -> bool,
+    ) -> bool {
+        match operands.into_iter().find(|token| !is_expected(token)) {
+            Some(token) => {
+                self.error(&format!(
+                    "`{}` expected {}, got: `{}`",
+                    operator,
+                    expected,
+                    format_item(token)
+                ));
+                false
+            }
+            None => true,
+        }
+    }
+
+    // Applies `+`, `-`, `*` or `/` to two numbers.
+    fn arithmetic(&mut self, operator: &str, a: Token, b: Token) {
+        if !self.operands_are(operator, [&a, &b], "a number", |token| {
+            token.token_type == TokenType::Number
+        }) {
+            return;
+        }
+
+        let (Some(a), Some(b)) = (Number::parse(&a), Number::parse(&b)) else {
+            self.error(&format!("`{}` expected valid numbers", operator));
+            return;
+        };
+
+        match a.apply(operator, b) {
+            Ok(result) => self.stack.push(result.to_token()),
+            Err(message) => self.error(&message),
+        }
+    }
+
+    // Checks that a call got exactly `N` arguments.
+    fn arguments<const N: usize>(
+        &mut self,
+        name: &str,
+        arguments: Vec<Token>,
+    ) -> Option<[Token; N]> {
+        let amount = arguments.len();
+
+        let Ok(arguments) = <[Token; N]>::try_from(arguments) else {
+            self.error(&format!(
+                "`{}` expected {} argument(s), got {}",
+                name, N, amount
+            ));
+            return None;
+        };
+
+        Some(arguments)
+    }
+
+    fn parse_operator(&mut self, operator: String) {
+        if operator == "+" {
+            if let Some((a, b)) = self.get_surrounding_operator("+") {
+                if !self.operands_are(&operator, [&a, &b], "a string or number", |token| {
+                    matches!(token.token_type, TokenType::String | TokenType::Number)
+                }) {
+                    return;
+                }
+
+                let is_string =
+                    a.token_type == TokenType::String || b.token_type == TokenType::String;
+
+                if is_string {
+                    self.stack.push(Token {
+                        token_type: TokenType::String,
+                        value: format!("{}{}", a.value, b.value),
+                        ..Default::default()
+                    });
+                    return;
+                }
+
+                self.arithmetic("+", a, b);
+            }
+        } else if operator == "-" || operator == "*" || operator == "/" {
+            if let Some((a, b)) = self.get_surrounding_operator(&operator) {
+                self.arithmetic(&operator, a, b);
+            }
+        } else if operator == "=" {
+            if let Some(targets) = destructuring_targets(&self.tokens_on_line[..self.index]) {
+                self.destructure(targets);
+                return;
+            }
+
+            // Assigning to a field or an index, e.g. `p.x = 1` or `xs[1][0] = 7`.
+            let is_part = |token: &Token, operator: &str| {
+                token.token_type == TokenType::Operator && token.value == operator
+            };
+
+            if self.index >= 3 && is_part(&self.tokens_on_line[self.index - 2], ".")
+                || self.index >= 2 && is_part(&self.tokens_on_line[self.index - 1], "]")
+            {
+                self.assign_place();
+                return;
+            }
+
+            self.assigning = true;
+
+            if let Some((name, value)) = self.get_surrounding_operator("=") {
+                if !value.token_type.is_value() {
+                    self.error(&format!(
+                        "`{}` expected a value, got identifier: `{}`",
+                        operator, value.value
+                    ));
+                    return;
+                }
+
+                if name.token_type != TokenType::Identifier {
+                    self.error(&format!(
+                        "`{}` expected an identifier on its left, got: `{:?}`",
+                        operator, name.token_type
+                    ));
+                    return;
+                }
+
+                self.set_variable(name.value, value);
+            }
+
+            self.assigning = false;
+        } else if operator == "==" {
+            if let Some((a, b)) = self.get_surrounding_operator("==") {
+                if !a.token_type.is_value() {
+                    self.error(&format!(
+                        "`{}` expected a value, got identifier: `{}`",
+                        operator, a.value
+                    ));
+                    return;
+                } else if !b.token_type.is_value() {
+                    self.error(&format!(
+                        "`{}` expected a value, got identifier: `{}`",
+                        operator, b.value
+                    ));
+                    return;
+                }
+
+                self.stack.push(Token {
+                    token_type: TokenType::Boolean,
+                    value: is_equal(&a, &b).to_string(),
+                    ..Default::default()
+                })
+            }
+        } else if operator == "!=" {
+            if let Some((a, b)) = self.get_surrounding_operator("!=") {
+                if !a.token_type.is_value() {
+                    self.error(&format!(
+                        "`{}` expected a value, got identifier: `{}`",
+                        operator, a.value
+                    ));
+                    return;
+                } else if !b.token_type.is_value() {
+                    self.error(&format!(
+                        "`{}` expected a value, got identifier: `{}`",
+                        operator, b.value
+                    ));
+                    return;
+                }
+
+                self.stack.push(Token {
+                    token_type: TokenType::Boolean,
+                    value: (!is_equal(&a, &b)).to_string(),
+                    ..Default::default()
+                })
+            }
+        } else if operator == ">" {
+            if let Some((a, b)) = self.get_surrounding_operator(">") {
+                if !self.operands_are(&operator, [&a, &b], "a number", |token| {
+                    token.token_type == TokenType::Number
+                }) {
+                    return;
+                }
+
+                self.stack.push(Token {
+                    token_type: TokenType::Boolean,
+                    value: matches!(compare_numbers(&a, &b), Some(Ordering::Greater)).to_string(),
+                    ..Default::default()
+                })
+            }
+        } else if operator == "<" {
+            if let Some((a, b)) = self.get_surrounding_operator("<") {
+                if !self.operands_are(&operator, [&a, &b], "a number", |token| {
+                    token.token_type == TokenType::Number
+                }) {
+                    return;
+                }
+
+                self.stack.push(Token {
+                    token_type: TokenType::Boolean,
+                    value: matches!(compare_numbers(&a, &b), Some(Ordering::Less)).to_string(),
+                    ..Default::default()
+                })
+            }
+        } else if operator == ">=" {
+            if let Some((a, b)) = self.get_surrounding_operator(">=") {
+                if !self.operands_are(&operator, [&a, &b], "a number", |token| {
+                    token.token_type == TokenType::Number
+                }) {
+                    return;
+                }
+
+                self.stack.push(Token {
+                    token_type: TokenType::Boolean,
+                    value: matches!(
+                        compare_numbers(&a, &b),
+                        Some(Ordering::Greater | Ordering::Equal)
+                    )
+                    .to_string(),
+                    ..Default::default()
+                })
+            }
+        } else if operator == "<=" {
+            if let Some((a, b)) = self.get_surrounding_operator("<=") {
+                if !self.operands_are(&operator, [&a, &b], "a number", |token| {
+                    token.token_type == TokenType::Number
+                }) {
+                    return;
+                }
+
+                self.stack.push(Token {
+                    token_type: TokenType::Boolean,
+                    value: matches!(
+                        compare_numbers(&a, &b),
+                        Some(Ordering::Less | Ordering::Equal)
+                    )
+                    .to_string(),
+                    ..Default::default()
+                })
+            }
+        } else if operator == "&&" {
+            if let Some((a, b)) = self.get_surrounding_operator("&&") {
+                if !self.operands_are(&operator, [&a, &b], "a boolean", |token| {
+                    token.token_type == TokenType::Boolean
+                }) {
+                    return;
+                }
+
+                self.stack.push(Token {
+                    token_type: TokenType::Boolean,
+                    value: (a.value.parse().unwrap() && b.value.parse().unwrap()).to_string(),
+                    ..Default::default()
+                })
+            }
+        } else if operator == "||" {
+            if let Some((a, b)) = self.get_surrounding_operator("||") {
+                if !self.operands_are(&operator, [&a, &b], "a boolean", |token| {
+                    token.token_type == TokenType::Boolean
+                }) {
+                    return;
+                }
+
+                self.stack.push(Token {
+                    token_type: TokenType::Boolean,
+                    value: (a.value.parse().unwrap() || b.value.parse().unwrap()).to_string(),
+                    ..Default::default()
+                })
+            }
+        } else if operator == "??" {
+            if let Some((a, b)) = self.get_surrounding_operator("??") {
+                if a.token_type == TokenType::Identifier {
+                    self.error(&format!("Unknown identifier: `{}`", a.value));
+                    return;
+                }
+
+                // Fall back to the right side only if the left side has no value.
+                if a.token_type == TokenType::Nil {
+                    self.stack.push(b);
+                } else {
+                    self.stack.push(a);
+                }
+            }
+        } else if operator == "]" || operator == "}" || operator == ")" || operator == ":" {
+            // Marks where the contents of the brackets end, or where a map key ends, on the stack.
+            self.stack.push(self.current_token.clone());
+        } else if operator == "." {
+            self.access_field();
+        } else if operator == "{" {
+            let Some(contents) = self.pop_until_closing_bracket("{", "}") else {
+                return;
+            };
+
+            let struct_name = self.previous_struct_name();
+
+            let mut map = Token {
+                token_type: TokenType::Map,
+                ..Default::default()
+            };
+
+            // The contents come off the stack as `key`, `:`, `value`, `key`, `:`, `value`...
+            for entry in contents.chunks(3) {
+                let [key, colon, value] = entry else {
+                    self.error("Every key in a map needs a value");
+                    return;
+                };
+
+                if colon.token_type != TokenType::Operator || colon.value != ":" {
+                    self.syntax_error(&format!("Expected a `:` after the map key `{}`", key.value));
+                    return;
+                }
+
+                if struct_name.is_none() && !self.is_valid_key(key) {
+                    return;
+                }
+
+                match map.keys.iter().position(|k| k == key) {
+                    Some(position) => map.items[position] = value.clone(),
+                    None => {
+                        map.keys.push(key.clone());
+                        map.items.push(value.clone());
+                    }
+                }
+            }
+
+            if let Some(name) = struct_name {
+                self.construct_struct(name, map);
+                return;
+            }
+
+            self.stack.push(map);
+        } else if operator == "(" {
+            let Some(mut items) = self.pop_until_closing_bracket("(", ")") else {
+                return;
+            };
+
+            // A single value in parentheses is just that value, anything else is a tuple.
+            if items.len() == 1 {
+                self.stack.push(items.remove(0));
+            } else {
+                self.stack.push(Token {
+                    token_type: TokenType::Tuple,
+                    items,
+                    ..Default::default()
+                });
+            }
+        } else if operator == "[" {
+            if let Some(items) = self.pop_until_closing_bracket("[", "]") {
+                self.stack.push(Token {
+                    token_type: TokenType::List,
+                    items,
+                    ..Default::default()
+                });
+            }
+        } else if operator == "[]" {
+            let Some(mut indices) = self.pop_until_closing_bracket("[]", "]") else {
+                return;
+            };
+
+            if indices.len() != 1 {
+                self.error(&format!(
+                    "`{}` expected exactly one index, got {}",
+                    operator,
+                    indices.len()
+                ));
+                return;
+            }
+
+            let index = indices.remove(0);
+
+            let Some(collection) = self.try_previous_operand() else {
+                self.error(&format!(
+                    "Operator `{}` expected a list, tuple, or map on its left, got nothing",
+                    operator
+                ));
+                return;
+            };
+
+            if self.should_abort {
+                return;
+            }
+
+            match collection.token_type {
+                TokenType::List | TokenType::Tuple => {
+                    let kind = match collection.token_type {
+                        TokenType::Tuple => "tuple",
+                        _ => "list",
+                    };
+
+                    if let Some(index) = self.resolve_index(&index, kind, collection.items.len()) {
+                        self.stack.push(collection.items[index].clone());
+                    }
+                }
+                TokenType::Map => match collection.keys.iter().position(|k| *k == index) {
+                    Some(position) => self.stack.push(collection.items[position].clone()),
+                    None => self.error(&format!("Key `{}` not found in map", index.value)),
+                },
+                // Strings are indexed by character, not by byte.
+                TokenType::String => {
+                    let chars = collection.value.chars().collect::<Vec<char>>();
+
+                    if let Some(index) = self.resolve_index(&index, "string", chars.len()) {
+                        self.stack.push(Token {
+                            token_type: TokenType::String,
+                            value: chars[index].to_string(),
+                            ..Default::default()
+                        });
+                    }
+                }
+                _ => self.error(&format!(
+                    "`{}` expected a list, tuple, map, or string, got: `{}`",
+                    operator, collection.value
+                )),
+            }
+        } else {
+            self.error(&format!("Unknown operator: `{}`", operator));
+        }
+    }
+
+    #[allow(dead_code)]
+    fn next_token(
+        &mut self,
+        identifier: &str,
+        expected_argument_amount: u8,
+        default: Option<Token>,
+    ) -> Token {
+        if self.tokens_on_line.len() <= self.index + 1 {
+            return default.unwrap_or_else(|| {
+                self.error(&format!(
+                    "`{}` needs {} argument(s).",
+                    identifier, expected_argument_amount
+                ));
+
+                Token::default()
+            });
+        }
+
+        let to_return = self.tokens_on_line[self.index + 1].clone();
+
+        let var = self.try_parse_variable(&to_return.value);
+        if let Some(variable) = var {
+            return variable;
+        }
+
+        to_return
+    }
+
+    #[allow(dead_code)]
+    fn previous_token(
+        &mut self,
+        identifier: &str,
+        expected_argument_amount: u8,
+        default: Option<Token>,
+    ) -> Token {
+        if self.index == 0 {
+            return default.unwrap_or_else(|| {
+                self.error(&format!(
+                    "`{}` needs {} argument(s).",
+                    identifier, expected_argument_amount
+                ));
+
+                Token::default()
+            });
+        }
+
+        let to_return = self.tokens_on_line[self.index - 1].clone();
+
+        let var = self.try_parse_variable(&to_return.value);
+        if let Some(variable) = var {
+            return variable;
+        }
+
+        to_return
+    }
+
+    #[allow(dead_code)]
+    fn try_next_token(&self) -> Option<Token> {
+        if self.tokens_on_line.len() <= self.index + 1 {
+            return None;
+        }
+
+        let to_return = self.tokens_on_line[self.index + 1].clone();
+
+        let var = self.try_parse_variable(&to_return.value);
+        if let Some(variable) = var {
+            return Some(variable);
+        }
+
+        Some(to_return)
+    }
+
+    fn try_previous_token(&self) -> Option<Token> {
+        if self.index == 0 {
+            return None;
+        }
+
+        let to_return = self.tokens_on_line[self.index - 1].clone();
+
+        let var = self.try_parse_variable(&to_return.value);
+        if let Some(variable) = var {
+            return Some(variable);
+        }
+
+        Some(to_return)
+    }
+
+    // Finds where the operand ending at `end` starts, e.g. `a` for `a.b[0]`.
+    fn operand_start(&self, end: usize) -> usize {
+        let tokens = &self.tokens_on_line;
+        let mut start = end;
+
+        loop {
+            let token = &tokens[start];
+
+            if token.token_type == TokenType::Operator
+                && (token.value == "]" || token.value == ")" || token.value == "}")
+            {
+                let Some(opening) = self.opening_bracket(start) else {
+                    return start;
+                };
+
+                start = opening;
+
+                // `xs[0]` and `Point { ... }` include what comes before the bracket.
+                if start > 0
+                    && (tokens[start].value == "[]"
+                        || tokens[start].value == "{"
+                            && tokens[start - 1].token_type == TokenType::Identifier
+                            && self.structs.contains_key(&tokens[start - 1].value))
+                {
+                    start -= 1;
+                    continue;
+                }
+            }
+
+            if start >= 2
+                && tokens[start - 1].token_type == TokenType::Operator
+                && tokens[start - 1].value == "."
+            {
+                start -= 2;
+                continue;
+            }
+
+            return start;
+        }
+    }
+
+    fn opening_bracket(&self, closing: usize) -> Option<usize> {
+        let mut depth = 0;
+
+        for i in (0..closing).rev() {
+            let token = &self.tokens_on_line[i];
+
+            let is_operator = token.token_type == TokenType::Operator;
+            let is_call = token.token_type == TokenType::Identifier && token.value.ends_with('(');
+
+            if is_operator && matches!(token.value.as_str(), "]" | ")" | "}") {
+                depth += 1;
+            } else if is_call
+                || is_operator && matches!(token.value.as_str(), "[" | "[]" | "(" | "{")
+            {
+                if depth == 0 {
+                    return Some(i);
+                }
+
+                depth -= 1;
+            }
+        }
+
+        None
+    }
+
+    // Evaluates the tokens from `start` up to (but not including) `end` on their own, giving back their value.
+    fn evaluate_operand(&mut self, start: usize, end: usize) -> Option<Token> {
+        self.evaluate_tokens(self.tokens_on_line[start..end].to_vec())
+    }
+
+    // Evaluates `tokens` as if they were a line of their own, giving back their value.
+    fn evaluate_tokens(&mut self, tokens: Vec<Token>) -> Option<Token> {
+        let tokens_on_line = std::mem::replace(&mut self.tokens_on_line, tokens);
+        let (index, current_token) = (self.index, self.current_token.clone());
+        let (skip_line, line_stack_start) = (self.skip_line, self.line_stack_start);
+        let stack_start = self.stack.len();
+
+        self.skip_tokens = 0;
+        self.skip_line = false;
+        self.line_stack_start = stack_start;
+        self.evaluate();
+
+        let mut values = self.stack.split_off(stack_start);
+
+        self.tokens_on_line = tokens_on_line;
+        self.index = index;
+        self.current_token = current_token;
+        (self.skip_line, self.line_stack_start) = (skip_line, line_stack_start);
+
+        if values.len() == 1 {
+            values.pop()
+        } else {
+            None
+        }
+    }
+
+    // Like `try_previous_token`, but evaluates the whole operand if it is more than one token (e.g. `p.x`).
+    // Skips every token in the operand.
+    fn try_previous_operand(&mut self) -> Option<Token> {
+        if self.index == 0 {
+            return None;
+        }
+
+        let mut start = self.operand_start(self.index - 1);
+
+        // Operators that bind at least as tightly as this one belong to its left side, so
+        // `10 - 2 - 3` is `(10 - 2) - 3` and `2 * 3 + 1` is `(2 * 3) + 1`.
+        if let Some(binding) = precedence(&self.current_token) {
+            while start >= 2
+                && precedence(&self.tokens_on_line[start - 1]).is_some_and(|other| other >= binding)
+            {
+                start = self.operand_start(start - 2);
+            }
+        }
+
+        // `=` assigns to its left side, so it mustn't be evaluated.
+        if start == self.index - 1 || self.assigning {
+            self.skip_tokens = 1;
+            return self.try_previous_token();
+        }
+
+        let operand = self.evaluate_operand(start, self.index);
+        self.skip_tokens = self.index - start;
+        operand
+    }
+
+    fn get_surrounding_operator(&mut self, operator: &str) -> Option<(Token, Token)> {
+        let a = self.try_previous_operand();
+        let mut b = self.stack.pop();
+
+        // Brackets mark where their contents end on the stack, they aren't values themselves.
+        if let Some(token) = b.take_if(|token| token.token_type == TokenType::Operator) {
+            self.stack.push(token);
+        }
+
+        if self.should_abort {
+            return None;
+        }
+
+        // What the operator works on, e.g. `+` in `print(+)` expected a number.
+        let expected = match operator {
+            "+" | "-" | "*" | "/" | "<" | ">" | "<=" | ">=" => "a number",
+            "&&" | "||" => "a boolean",
+            _ => "a value",
+        };
+
+        if a.is_none() {
+            self.syntax_error(&format!(
+                "Operator `{}` expected {} to its left, got nothing",
+                operator, expected
+            ));
+            return None;
+        } else if b.is_none() {
+            self.syntax_error(&format!(
+                "Operator `{}` expected {} to its right, got nothing",
+                operator, expected
+            ));
+            return None;
+        }
+
+        let a = a.unwrap();
+        let b = b.unwrap();
+
+        Some((a, b))
+    }
+
+    fn try_parse_variable(&self, identifier: &String) -> Option<Token> {
+        if self.assigning {
+            return None;
+        }
+
+        let var: Option<&Variable> = self.variables.get(identifier);
+        if let Some(variable) = var {
+            return Some(Token {
+                token_type: TokenType::from(variable.variable_type),
+                value: variable.value.clone(),
+                items: variable.items.clone(),
+                keys: variable.keys.clone(),
+            });
+        }
+
+        None
+    }
+
+    fn pop_until_closing_bracket(&mut self, operator: &str, closing: &str) -> Option<Vec<Token>> {
+        let mut items = vec![];
+
+        loop {
+            match self.stack.pop() {
+                Some(token)
+                    if token.token_type == TokenType::Operator && token.value == closing =>
+                {
+                    return Some(items)
+                }
+                Some(token) => items.push(token),
+                None => {
+                    self.syntax_error(&missing_closing(operator, closing));
+                    return None;
+                }
+            }
+        }
+    }
+
+    // The position `index` points to in a list, tuple or string (the `kind`) of `length` items.
+    fn resolve_index(&mut self, index: &Token, kind: &str, length: usize) -> Option<usize> {
+        let Ok(number) = index.value.parse::<i64>() else {
+            self.error(&format!(
+                "Expected a number as the index, got: `{}`",
+                format_item(index)
+            ));
+            return None;
+        };
+
+        // Negative indices count from the end of the list.
+        let resolved = if number < 0 {
+            number + length as i64
+        } else {
+            number
+        };
+
+        if resolved < 0 || resolved >= length as i64 {
+            self.error(&format!(
+                "Index `{}` is out of bounds for a {} of length {}",
+                number, kind, length
+            ));
+            return None;
+        }
+
+        Some(resolved as usize)
+    }
+
+    // Assigns the values on the stack to `targets`, where each target starts on the line.
+    fn destructure(&mut self, targets: Result<Vec<usize>, String>) {
+        self.skip_line = true;
+
+        let targets = match targets {
+            Ok(targets) => targets,
+            Err(message) => {
+                self.syntax_error(&message);
+                return;
+            }
+        };
+
+        // Values are pushed right to left, so the leftmost one is on top of the stack.
+        let mut values = self.stack.split_off(self.line_stack_start);
+        values.reverse();
+
+        if values.len() == 1
+            && (values[0].token_type == TokenType::List || values[0].token_type == TokenType::Tuple)
+        {
+            values = values.remove(0).items;
+        }
+
+        // Only a name on its own can collect the rest, e.g. `rest...`.
+        let collects_rest = |i: usize, tokens: &[Token]| {
+            tokens[i].value.ends_with("...") && target_end(tokens, i) == Some(i + 1)
+        };
+
+        let rest = targets
+            .last()
+            .filter(|i| collects_rest(**i, &self.tokens_on_line))
+            .map(|i| {
+                self.tokens_on_line[*i]
+                    .value
+                    .trim_end_matches("...")
+                    .to_string()
+            });
+
+        if targets[..targets.len() - 1]
+            .iter()
+            .any(|i| collects_rest(*i, &self.tokens_on_line))
+        {
+            self.error("Only the last name can collect the rest of the values");
+            return;
+        }
+
+        let required = if rest.is_some() {
+            targets.len() - 1
+        } else {
+            targets.len()
+        };
+
+        if values.len() < required || (rest.is_none() && values.len() > required) {
+            self.error(&format!(
+                "Cannot destructure {} value(s) into {} name(s)",
+                values.len(),
+                targets.len()
+            ));
+            return;
+        }
+
+        if let Some(value) = values.iter().find(|value| !value.token_type.is_value()) {
+            self.error(&format!(
+                "`=` expected a value, got identifier: `{}`",
+                value.value
+            ));
+            return;
+        }
+
+        let remaining = values.split_off(required);
+
+        // Each target is written before the next one is looked up, from left to right.
+        for (start, value) in targets.into_iter().zip(values) {
+            let Some((place, _)) = self.place(start) else {
+                return;
+            };
+
+            if self.should_abort {
+                return;
+            }
+
+            if place.path.is_empty() {
+                self.set_variable(place.name, value);
+            } else {
+                self.write_back(&place, value);
+            }
+
+            if self.should_abort {
+                return;
+            }
+        }
+
+        if let Some(name) = rest {
+            self.set_variable(
+                name,
+                Token {
+                    token_type: TokenType::List,
+                    items: remaining,
+                    ..Default::default()
+                },
+            );
+        }
+    }
 
-                self.stack.push(Token {
-                    token_type: TokenType::Boolean,
-                    value: (a.value.parse().unwrap() || b.value.parse().unwrap()).to_string(),
-                })
+    fn set_variable(&mut self, name: String, value: Token) {
+        self.variables.insert(
+            name,
+            Variable {
+                variable_type: VariableType::from(value.token_type),
+                value: value.value,
+                items: value.items,
+                keys: value.keys,
+            },
+        );
+
+        self.assigning = false;
+    }
+
+    fn is_field_name(&self) -> bool {
+        if self.index > 0 {
+            let previous = &self.tokens_on_line[self.index - 1];
+            if previous.token_type == TokenType::Operator && previous.value == "." {
+                return true;
             }
-        } else {
-            self.error(&format!("Unknown operator: `{}`", operator));
         }
-    }
 
-    #[allow(dead_code)]
-    fn next_token(
-        &mut self,
-        identifier: &str,
-        expected_argument_amount: u8,
-        default: Option<Token>,
-    ) -> Token {
-        if self.tokens_on_line.len() <= self.index + 1 {
-            return default.unwrap_or_else(|| {
-                self.error(&format!(
-                    "`{}` needs {} argument(s).",
-                    identifier, expected_argument_amount
-                ));
+        let is_followed_by_colon = self
+            .tokens_on_line
+            .get(self.index + 1)
+            .is_some_and(|next| next.token_type == TokenType::Operator && next.value == ":");
 
-                Token::default()
-            });
+        if !is_followed_by_colon {
+            return false;
         }
 
-        let to_return = self.tokens_on_line[self.index + 1].clone();
+        // Find the `{` this name is inside of, and check whether it constructs a struct.
+        let mut depth = 0;
 
-        let var = self.try_parse_variable(&to_return.value);
-        if let Some(variable) = var {
-            return variable;
+        for i in (0..self.index).rev() {
+            let token = &self.tokens_on_line[i];
+            if token.token_type != TokenType::Operator {
+                continue;
+            }
+
+            if token.value == "}" {
+                depth += 1;
+            } else if token.value == "{" && depth > 0 {
+                depth -= 1;
+            } else if token.value == "{" {
+                return i > 0
+                    && self.tokens_on_line[i - 1].token_type == TokenType::Identifier
+                    && self.structs.contains_key(&self.tokens_on_line[i - 1].value);
+            }
         }
 
-        to_return
+        false
     }
 
-    #[allow(dead_code)]
-    fn previous_token(
-        &mut self,
-        identifier: &str,
-        expected_argument_amount: u8,
-        default: Option<Token>,
-    ) -> Token {
+    // The name of the struct being constructed by the current `{`, e.g. `Point` in `Point { x: 1 }`.
+    fn previous_struct_name(&self) -> Option<String> {
         if self.index == 0 {
-            return default.unwrap_or_else(|| {
-                self.error(&format!(
-                    "`{}` needs {} argument(s).",
-                    identifier, expected_argument_amount
-                ));
-
-                Token::default()
-            });
+            return None;
         }
 
-        let to_return = self.tokens_on_line[self.index - 1].clone();
-
-        let var = self.try_parse_variable(&to_return.value);
-        if let Some(variable) = var {
-            return variable;
+        let previous = &self.tokens_on_line[self.index - 1];
+        if previous.token_type == TokenType::Identifier
+            && self.structs.contains_key(&previous.value)
+        {
+            return Some(previous.value.clone());
         }
 
-        to_return
+        None
     }
 
-    #[allow(dead_code)]
-    fn try_next_token(&self) -> Option<Token> {
-        if self.tokens_on_line.len() <= self.index + 1 {
-            return None;
+    fn construct_struct(&mut self, name: String, fields: Token) {
+        self.skip_tokens = 1;
+
+        let declared = self.structs[&name].clone();
+
+        if let Some(unknown) = fields
+            .keys
+            .iter()
+            .find(|field| !declared.contains(&field.value))
+        {
+            self.error(&format!("`{}` has no field `{}`", name, unknown.value));
+            return;
         }
 
-        let to_return = self.tokens_on_line[self.index + 1].clone();
+        let mut instance = Token {
+            token_type: TokenType::Struct,
+            value: name.clone(),
+            ..Default::default()
+        };
+
+        // Store the fields in declaration order, regardless of the order they were written in.
+        for field in declared {
+            let Some(position) = fields.keys.iter().position(|key| key.value == field) else {
+                self.error(&format!("Missing field `{}` in `{}`", field, name));
+                return;
+            };
 
-        let var = self.try_parse_variable(&to_return.value);
-        if let Some(variable) = var {
-            return Some(variable);
+            instance.keys.push(fields.keys[position].clone());
+            instance.items.push(fields.items[position].clone());
         }
 
-        Some(to_return)
+        self.stack.push(instance);
     }
 
-    fn try_previous_token(&self) -> Option<Token> {
-        if self.index == 0 {
-            return None;
+    fn access_field(&mut self) {
+        // A method call (`p.len()`) leaves its arguments on the stack, a field (`p.x`) leaves its name.
+        let call = self
+            .tokens_on_line
+            .get(self.index + 1)
+            .and_then(|next| next.value.strip_suffix('('))
+            .map(str::to_string);
+
+        let (name, arguments) = if let Some(name) = call {
+            let Some(arguments) = self.pop_until_closing_bracket(&name, ")") else {
+                return;
+            };
+
+            (name, Some(arguments))
+        } else {
+            (self.pop_stack(".", 2, None).value, None)
+        };
+
+        if self.should_abort {
+            return;
         }
 
-        let to_return = self.tokens_on_line[self.index - 1].clone();
+        // Mutating methods write back into what they were called on, if it can be written to.
+        let receiver = match self.index {
+            0 => None,
+            index => match self.place(self.operand_start(index - 1)) {
+                Some((place, end)) if end == index => Some(place),
+                _ => None,
+            },
+        };
+
+        if self.should_abort {
+            return;
+        }
 
-        let var = self.try_parse_variable(&to_return.value);
-        if let Some(variable) = var {
-            return Some(variable);
+        let Some(value) = self.try_previous_operand() else {
+            self.error("Operator `.` expected a value on its left, got nothing");
+            return;
+        };
+
+        if self.should_abort {
+            return;
         }
 
-        Some(to_return)
-    }
+        if value.token_type == TokenType::Identifier && self.enums.contains_key(&value.value) {
+            self.construct_variant(&value.value, &name, arguments.unwrap_or_default());
+            return;
+        }
 
-    fn get_surrounding_operator(&mut self, operator: &str) -> Option<(Token, Token)> {
-        let a = self.try_previous_token();
-        let b = self.stack.pop();
+        if value.token_type == TokenType::Identifier {
+            self.error(&format!("Unknown identifier: `{}`", value.value));
+            return;
+        }
 
-        if a.is_none() {
+        let Some(arguments) = arguments else {
+            if let Some(field) = self.get_field(&value, &name) {
+                self.stack.push(field);
+            }
+            return;
+        };
+
+        let type_name = type_name(&value);
+
+        if value.token_type == TokenType::Module {
+            match builtins::get_in(&value.value, &name) {
+                Some(builtin) => (builtin.function)(self, arguments),
+                None => self.error(&format!(
+                    "Module `{}` doesn't export `{}`",
+                    value.value, name
+                )),
+            }
+            return;
+        }
+
+        if let Some(method) = self
+            .methods
+            .get(&type_name)
+            .and_then(|methods| methods.get(&name))
+            .cloned()
+        {
+            self.call_method(&name, method, value, receiver, arguments);
+        } else if is_builtin_method(value.token_type, &name) {
+            self.call_builtin_method(&name, value, receiver, arguments);
+        } else {
+            self.error(&format!("No method `{}` on type `{}`", name, type_name));
+        }
+    }
+
+    fn get_field(&mut self, value: &Token, field: &str) -> Option<Token> {
+        if value.token_type != TokenType::Struct && value.token_type != TokenType::Module {
             self.error(&format!(
-                "Operator `{}` expected a token on its left, got nothing",
-                operator
+                "Operator `.` expected a struct, got: `{}`",
+                format_item(value)
             ));
             return None;
-        } else if b.is_none() {
+        }
+
+        let Some(position) = value.keys.iter().position(|key| key.value == field) else {
+            if value.token_type == TokenType::Module {
+                self.error(&format!(
+                    "Module `{}` doesn't export `{}`",
+                    value.value, field
+                ));
+            } else {
+                self.error(&format!("`{}` has no field `{}`", value.value, field));
+            }
+            return None;
+        };
+
+        Some(value.items[position].clone())
+    }
+
+    // Assigns to a field or an index, e.g. `p.x = 1` or `xs[1][0] = 7`.
+    fn assign_place(&mut self) {
+        self.skip_line = true;
+
+        let value = self.pop_stack("=", 2, None);
+        if self.should_abort {
+            return;
+        }
+
+        if !value.token_type.is_value() {
             self.error(&format!(
-                "Operator `{}` expected a token on its right, got nothing",
-                operator
+                "`=` expected a value, got identifier: `{}`",
+                value.value
             ));
-            return None;
+            return;
         }
 
-        let a = a.unwrap();
-        let b = b.unwrap();
+        let place = self.place(self.operand_start(self.index - 1));
 
-        self.skip_next = true;
-        Some((a, b))
+        if self.should_abort {
+            return;
+        }
+
+        match place {
+            Some((place, end)) if end == self.index => self.write_back(&place, value),
+            _ => self.syntax_error("`=` expected a name on its left"),
+        }
     }
 
-    fn try_parse_variable(&self, identifier: &String) -> Option<Token> {
-        if !self.resolve_variables {
-            return None
+    // The place the tokens from `start` refer to, and where it ends, e.g. `p.items` in
+    // `p.items.push(1)`. Indices are evaluated, but nothing is looked up yet.
+    fn place(&mut self, start: usize) -> Option<(Place, usize)> {
+        let tokens = self.tokens_on_line.clone();
+
+        let name = tokens.get(start).filter(|token| {
+            token.token_type == TokenType::Identifier && !token.value.ends_with('(')
+        })?;
+
+        let mut place = Place {
+            name: name.value.clone(),
+            path: vec![],
+        };
+
+        let mut i = start + 1;
+
+        while let Some(token) = tokens
+            .get(i)
+            .filter(|token| token.token_type == TokenType::Operator)
+        {
+            if token.value == "." {
+                // A method call isn't part of the place, e.g. `.push(` in `xs.push(1)`.
+                let Some(field) = tokens.get(i + 1).filter(|field| {
+                    field.token_type == TokenType::Identifier && !field.value.ends_with('(')
+                }) else {
+                    break;
+                };
+
+                place.path.push(Step::Field(field.value.clone()));
+                i += 2;
+            } else if token.value == "[]" {
+                let end = checker::closing(&tokens, i);
+
+                if end >= tokens.len() {
+                    return None;
+                }
+
+                place.path.push(Step::Index(
+                    self.evaluate_tokens(tokens[i + 1..end].to_vec())?,
+                ));
+                i = end + 1;
+            } else {
+                break;
+            }
         }
 
-        let var: Option<&Variable> = self.variables.get(identifier);
-        if let Some(variable) = var {
-            return Some(Token {
-                token_type: TokenType::from(variable.variable_type),
-                value: variable.value.clone(),
-            });
+        Some((place, i))
+    }
+
+    // Writes `value` into `place`, e.g. the list `push` added to, or the right side of `p.x = 1`.
+    fn write_back(&mut self, place: &Place, value: Token) {
+        let Some(mut root) = self.try_parse_variable(&place.name) else {
+            self.error(&format!("Unknown identifier: `{}`", place.name));
+            return;
+        };
+
+        if self.set_in(&mut root, place.name.clone(), &place.path, value) {
+            self.set_variable(place.name.clone(), root);
         }
+    }
 
-        None
+    // Replaces what `path` leads to inside of `owner`, where `name` is what `owner` is called.
+    fn set_in(&mut self, owner: &mut Token, name: String, path: &[Step], value: Token) -> bool {
+        let Some((step, path)) = path.split_first() else {
+            *owner = value;
+            return true;
+        };
+
+        let (position, name) = match step {
+            Step::Field(field) => {
+                if self.get_field(owner, field).is_none() {
+                    return false;
+                }
+
+                let position = owner.keys.iter().position(|key| key.value == *field);
+                (position.unwrap(), format!("{}.{}", name, field))
+            }
+            Step::Index(index) if owner.token_type == TokenType::List => {
+                let Some(position) = self.resolve_index(index, "list", owner.items.len()) else {
+                    return false;
+                };
+
+                (position, format!("{}[{}]", name, format_item(index)))
+            }
+            Step::Index(index) if owner.token_type == TokenType::Map => {
+                if !self.is_valid_key(index) {
+                    return false;
+                }
+
+                // Assigning to a key that isn't there yet adds it.
+                let position = match owner.keys.iter().position(|key| key == index) {
+                    Some(position) => position,
+                    None if path.is_empty() => {
+                        owner.keys.push(index.clone());
+                        owner.items.push(nil());
+                        owner.items.len() - 1
+                    }
+                    None => {
+                        self.error(&format!("Key `{}` not found in map", index.value));
+                        return false;
+                    }
+                };
+
+                (position, format!("{}[{}]", name, format_item(index)))
+            }
+            Step::Index(_) => {
+                self.error(&format!(
+                    "Cannot assign to an index of `{}`, which is a {:?}",
+                    name,
+                    VariableType::from(owner.token_type)
+                ));
+                return false;
+            }
+        };
+
+        self.set_in(&mut owner.items[position], name, path, value)
+    }
+
+    fn is_valid_key(&mut self, key: &Token) -> bool {
+        if key.token_type != TokenType::String && key.token_type != TokenType::Number {
+            self.error(&format!(
+                "Map keys must be strings or numbers, got: `{}`",
+                key.value
+            ));
+            return false;
+        }
+
+        true
+    }
+
+    // Writes a list or map back into what was passed as the first argument, e.g. `xs` in
+    // `push(xs, 1)`, `p.items` in `p.items.push(1)` or `m["a"]` in `remove(m["a"], "b")`. Values
+    // that aren't stored anywhere, e.g. `push([1], 2)`, aren't written back.
+    fn update_argument(&mut self, collection: &Token) {
+        let place = match self.method_receiver.clone() {
+            Some(place) => place,
+            None => match self.place(self.index + 1) {
+                // The argument has to be the place itself, not e.g. `xs ?? []`.
+                Some((place, end))
+                    if self.tokens_on_line.get(end).and_then(precedence).is_none() =>
+                {
+                    Some(place)
+                }
+                _ => None,
+            },
+        };
+
+        if let Some(place) = place.filter(|_| !self.should_abort) {
+            self.write_back(&place, collection.clone());
+        }
     }
 
     fn pop_stack(
@@ -552,17 +3374,383 @@ impl Parser {
     }
 
     fn error(&mut self, message: &str) {
+        self.raise(ErrorKind::Runtime, message);
+    }
+
+    // An error in how the code is written, rather than in what it does.
+    fn syntax_error(&mut self, message: &str) {
+        self.raise(ErrorKind::Parse, message);
+    }
+
+    // Reports code that couldn't be lexed, as if it happened while running it.
+    pub fn lex_error(&mut self, error: LexError) {
+        self.line = error.line;
+        self.raise(ErrorKind::Lex, &error.message);
+    }
+
+    fn raise(&mut self, kind: ErrorKind, message: &str) {
+        self.error_kind = kind;
+        self.throw(Token {
+            token_type: TokenType::String,
+            value: message.to_string(),
+            ..Default::default()
+        });
+    }
+
+    // Stops running the script with `value` as the error, unless a `try` block catches it.
+    fn throw(&mut self, value: Token) {
+        self.should_abort = true;
+
+        if self.try_depth > 0 {
+            self.exception = Some((value, self.line));
+            return;
+        }
+
+        let message = if value.token_type == TokenType::String {
+            value.value
+        } else {
+            format_item(&value)
+        };
+
+        // Errors in imported modules say which file they happened in.
+        let file = match &self.file_path {
+            Some(path) if !self.import_chain.is_empty() => format!(" in {}", path.display()),
+            _ => String::new(),
+        };
+
+        // Messages with more lines, e.g. a diff, go on after where the error happened.
+        let (message, details) = match message.split_once('\n') {
+            Some((message, details)) => (message.to_string(), format!("\n{}", details)),
+            None => (message, String::new()),
+        };
+
         io::stderr()
             .write_all(
                 format!(
-                    "\x1b[31mERROR: {}. Error occurred on line {}.\x1b[0m\n",
+                    "\x1b[31mERROR: {}. Error occurred on line {}{}.{}\x1b[0m\n",
                     message,
-                    self.line + 1
+                    self.line + 1,
+                    file,
+                    details
                 )
                 .as_bytes(),
             )
             .expect("Encountered error while printing error. Error-ception!");
 
+        self.failure = Some(self.error_kind);
         self.should_abort = true;
     }
 }
+
+// Whether to print the code, its tokens and the variables around the output, for working on Milo
+// itself. Only debug builds do, and only with `MILO_DEBUG` set, so their output can be compared.
+pub fn is_debugging() -> bool {
+    cfg!(debug_assertions) && std::env::var_os("MILO_DEBUG").is_some()
+}
+
+// The file `import target` refers to, and the name it is imported as without `as`. `import name` is
+// a dependency of the project called `name`, or else `name.milo` next to the importing file or in
+// one of the project's module paths.
+fn import_path(
+    target: &Token,
+    importer: Option<&Path>,
+    dependencies: &HashMap<String, PathBuf>,
+    module_paths: &[PathBuf],
+) -> Option<(PathBuf, String)> {
+    let relative = match target.token_type {
+        TokenType::String => target.value.clone(),
+        TokenType::Identifier => format!("{}.milo", target.value),
+        _ => return None,
+    };
+
+    let name = Path::new(&relative)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or(relative.clone());
+
+    if let Some(dependency) = dependencies.get(&target.value) {
+        if target.token_type == TokenType::Identifier {
+            return Some((dependency.clone(), name));
+        }
+    }
+
+    let path = match importer.and_then(|path| path.parent()) {
+        Some(directory) => directory.join(&relative),
+        None => PathBuf::from(&relative),
+    };
+
+    if path.exists() {
+        return Some((path, name));
+    }
+
+    let path = module_paths
+        .iter()
+        .map(|directory| directory.join(&relative))
+        .find(|path| path.exists())
+        .unwrap_or(path);
+
+    Some((path, name))
+}
+
+// Compares two numbers by value, so `1 < 1.5`.
+pub(crate) fn compare_numbers(a: &Token, b: &Token) -> Option<Ordering> {
+    Number::parse(a)?.compare(Number::parse(b)?)
+}
+
+// Whether two values are the same. Numbers are compared by value, so `1 == 1.0`.
+pub(crate) fn is_equal(a: &Token, b: &Token) -> bool {
+    match compare_numbers(a, b) {
+        Some(ordering) => ordering == Ordering::Equal,
+        None => a == b,
+    }
+}
+
+// How tightly a binary operator binds, higher binding tighter. Lines are evaluated from right to left,
+// so an operator takes everything that binds at least as tightly to its left as its left side.
+fn precedence(token: &Token) -> Option<u8> {
+    if token.token_type != TokenType::Operator {
+        return None;
+    }
+
+    match token.value.as_str() {
+        "*" | "/" => Some(6),
+        "+" | "-" => Some(5),
+        "??" => Some(4),
+        "==" | "!=" | "<" | ">" | "<=" | ">=" => Some(3),
+        "&&" => Some(2),
+        "||" => Some(1),
+        _ => None,
+    }
+}
+
+// Where each target on the left of an `=` that destructures starts, e.g. `a` and `p.x` in
+// `a, p.x = 1, 2` or `[a, p.x] = [1, 2]`. `None` if there is only one target, and an error if one of
+// them isn't a name, field or index.
+fn destructuring_targets(targets: &[Token]) -> Option<Result<Vec<usize>, String>> {
+    let is_pattern = targets.len() >= 3
+        && ((targets[0].value == "[" && targets[targets.len() - 1].value == "]")
+            || (targets[0].value == "(" && targets[targets.len() - 1].value == ")"))
+        && targets[0].token_type == TokenType::Operator;
+
+    let (start, end) = if is_pattern {
+        (1, targets.len() - 1)
+    } else {
+        (0, targets.len())
+    };
+
+    if !is_pattern && !matches!(target_end(targets, 0), Some(after) if after < end) {
+        return None;
+    }
+
+    let mut starts = vec![];
+    let mut i = start;
+
+    while i < end {
+        let Some(after) = target_end(targets, i).filter(|&after| after <= end) else {
+            return Some(Err(
+                "`=` can only destructure into names, fields and indices".to_string(),
+            ));
+        };
+
+        starts.push(i);
+        i = after;
+    }
+
+    Some(Ok(starts))
+}
+
+// Where the target of an assignment starting at `start` ends, e.g. after `p.items[0]`, or `None`
+// if it doesn't start with a name.
+fn target_end(tokens: &[Token], start: usize) -> Option<usize> {
+    let is_name =
+        |token: &Token| token.token_type == TokenType::Identifier && !token.value.ends_with('(');
+
+    tokens.get(start).filter(|token| is_name(token))?;
+    let mut i = start + 1;
+
+    while let Some(token) = tokens
+        .get(i)
+        .filter(|token| token.token_type == TokenType::Operator)
+    {
+        match token.value.as_str() {
+            "." if tokens.get(i + 1).is_some_and(is_name) => i += 2,
+            "[]" => i = checker::closing(tokens, i) + 1,
+            _ => break,
+        }
+    }
+
+    Some(i.min(tokens.len()))
+}
+
+// The first bracket on a line that is never closed, found the way the line is evaluated: from
+// right to left, with each opening bracket taking everything up to its closing one. Otherwise the
+// first closing bracket that is left over, which has nothing to close.
+fn unclosed_bracket(tokens: &[Token]) -> Option<String> {
+    let mut closings = vec![];
+
+    for token in tokens.iter().rev() {
+        let (opening, closing) = match token.token_type {
+            TokenType::Identifier => match token.value.strip_suffix('(') {
+                Some(name) => (name, ")"),
+                None => continue,
+            },
+            TokenType::Operator => match token.value.as_str() {
+                ")" | "]" | "}" => {
+                    closings.push(token.value.as_str());
+                    continue;
+                }
+                "(" => ("(", ")"),
+                "[" => ("[", "]"),
+                "[]" => ("[]", "]"),
+                "{" => ("{", "}"),
+                _ => continue,
+            },
+            _ => continue,
+        };
+
+        match closings.iter().rposition(|&other| other == closing) {
+            Some(position) => closings.truncate(position),
+            None => return Some(missing_closing(opening, closing)),
+        }
+    }
+
+    closings.last().map(|&closing| {
+        let opening = match closing {
+            ")" => "(",
+            "]" => "[",
+            _ => "{",
+        };
+
+        format!("`{}` has no opening `{}`", closing, opening)
+    })
+}
+
+fn missing_closing(opening: &str, closing: &str) -> String {
+    format!("`{}` is missing a closing `{}`", opening, closing)
+}
+
+// Whether `tokens` starts with a keyword, a name, and a `{` at `length - 1`, e.g. `impl Point {`.
+fn is_block_start(tokens: &[Token], length: usize) -> bool {
+    tokens.len() >= length
+        && tokens[1].token_type == TokenType::Identifier
+        && tokens[length - 1].token_type == TokenType::Operator
+        && tokens[length - 1].value == "{"
+}
+
+// The name of a value's type, as used in `impl` blocks and error messages.
+fn type_name(token: &Token) -> String {
+    if token.token_type == TokenType::Struct {
+        return token.value.clone();
+    }
+
+    // The variant comes last, e.g. `Shape` in `Shape.Circle` and `shapes.Shape` in `shapes.Shape.Circle`.
+    if token.token_type == TokenType::Enum {
+        return token
+            .value
+            .rsplit_once('.')
+            .map_or(token.value.clone(), |(name, _)| name.to_string());
+    }
+
+    format!("{:?}", VariableType::from(token.token_type))
+}
+
+// Gives the types of a module's values the name they are known by in the importing file, e.g.
+// `geometry.Point`.
+fn rename_types(mut value: Token, qualify: &impl Fn(&str) -> Option<String>) -> Token {
+    match value.token_type {
+        TokenType::Struct => {
+            if let Some(qualified) = qualify(&value.value) {
+                value.value = qualified;
+            }
+        }
+        TokenType::Enum => {
+            if let Some((enum_name, variant)) = value.value.rsplit_once('.') {
+                if let Some(qualified) = qualify(enum_name) {
+                    value.value = format!("{}.{}", qualified, variant);
+                }
+            }
+        }
+        _ => {}
+    }
+
+    value.items = value
+        .items
+        .into_iter()
+        .map(|item| rename_types(item, qualify))
+        .collect();
+    value
+}
+
+// Built-in functions that can also be called as methods on their first argument, e.g. `xs.len()`.
+fn is_builtin_method(receiver: TokenType, name: &str) -> bool {
+    builtins::get(name).is_some_and(|builtin| builtin.methods.contains(&receiver))
+}
+
+fn nil() -> Token {
+    Token {
+        token_type: TokenType::Nil,
+        value: "nil".to_string(),
+        ..Default::default()
+    }
+}
+
+fn format_item(token: &Token) -> String {
+    match token.token_type {
+        TokenType::String => format!("{:?}", token.value),
+        TokenType::Number => {
+            Number::parse(token).map_or(token.value.clone(), |number| number.to_string())
+        }
+        TokenType::List => format!(
+            "[{}]",
+            token
+                .items
+                .iter()
+                .map(format_item)
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        TokenType::Tuple => format!(
+            "({})",
+            token
+                .items
+                .iter()
+                .map(format_item)
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        TokenType::Struct => format!(
+            "{} {{ {} }}",
+            token.value,
+            token
+                .keys
+                .iter()
+                .zip(&token.items)
+                .map(|(key, value)| format!("{}: {}", key.value, format_item(value)))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        TokenType::Module => format!("<module {}>", token.value),
+        TokenType::Enum if token.items.is_empty() => token.value.clone(),
+        TokenType::Enum => format!(
+            "{}({})",
+            token.value,
+            token
+                .items
+                .iter()
+                .map(format_item)
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        TokenType::Map => format!(
+            "{{{}}}",
+            token
+                .keys
+                .iter()
+                .zip(&token.items)
+                .map(|(key, value)| format!("{}: {}", format_item(key), format_item(value)))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        _ => token.value.clone(),
+    }
+}
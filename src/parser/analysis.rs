@@ -0,0 +1,1442 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use crate::{
+    lexer::{LexError, Lexer, Token, TokenType},
+    patterns::Pattern,
+};
+
+use super::{
+    builtins,
+    checker::{closing, count_values, is_binary_operator, is_keyword, is_operator, top_level},
+    walk::{self, Inside, Visitor},
+};
+
+const KEYWORDS: &[&str] = &[
+    "struct", "enum", "impl", "fn", "match", "return", "throw", "try", "catch", "finally",
+    "import", "export", "as", "for", "in",
+];
+
+// Where something is in the code. Lines and columns start at 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+}
+
+impl Span {
+    fn contains(&self, line: usize, column: usize) -> bool {
+        self.line == line && (self.column..=self.column + self.length).contains(&column)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    Variable,
+    Parameter,
+    Method,
+    Struct,
+    Enum,
+    Field,
+    Variant,
+    Module,
+}
+
+// Something with a name that is defined in the code.
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    // Where its name is first written.
+    pub span: Span,
+    // The type of a variable if it is known, or how a method, struct, enum or variant is written.
+    pub detail: String,
+    // The struct or enum a method, field or variant belongs to.
+    pub parent: Option<String>,
+    scope: usize,
+}
+
+// What a token is, for highlighting it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    Keyword,
+    Variable,
+    Parameter,
+    Function,
+    Method,
+    Struct,
+    Enum,
+    Variant,
+    Field,
+    Module,
+    String,
+    Number,
+    Operator,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompletionKind {
+    Keyword,
+    Function,
+    Method,
+    Variable,
+    Struct,
+    Enum,
+    Field,
+    Variant,
+    Module,
+    Constant,
+}
+
+#[derive(Debug, Clone)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    pub detail: String,
+}
+
+// A symbol and the symbols inside of it, e.g. a struct with its fields and methods.
+#[derive(Debug, Clone)]
+pub struct Outline {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub span: Span,
+    pub detail: String,
+    pub children: Vec<Outline>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ScopeKind {
+    File,
+    Method,
+    // A block inside of a file or method, e.g. a match arm.
+    Block,
+}
+
+#[derive(Debug)]
+struct Scope {
+    kind: ScopeKind,
+    parent: Option<usize>,
+    // The lines it covers.
+    start: usize,
+    end: usize,
+    // Its variables, by name.
+    variables: HashMap<String, usize>,
+}
+
+// What is known about a file without running it: every symbol, where each one is used and what
+// every token is. This is what the language server answers questions with.
+#[derive(Debug, Default)]
+pub struct Analysis {
+    pub symbols: Vec<Symbol>,
+    // Every use of a symbol, with the symbol's index.
+    pub references: Vec<(Span, usize)>,
+    // Calls of built-in functions, by name, e.g. `len` or `math.sqrt`.
+    pub builtins: Vec<(Span, String)>,
+    tokens: HashMap<Span, TokenKind>,
+    scopes: Vec<Scope>,
+    // The built-in modules that are imported, by the name they are imported as.
+    modules: HashMap<String, String>,
+}
+
+impl Analysis {
+    pub fn new(code: &str) -> Result<Analysis, LexError> {
+        let mut lexer = Lexer::default();
+        let lines = lexer.lex_code(code.to_string())?;
+
+        let lines: Vec<(usize, Vec<usize>, Vec<Token>)> = lexer
+            .line_numbers
+            .into_iter()
+            .zip(lexer.columns)
+            .zip(lines)
+            .map(|((line, columns), tokens)| (line, columns, tokens))
+            .collect();
+
+        let mut walker = Walker {
+            analysis: Analysis::default(),
+            types: declared_types(&lines),
+            line: 0,
+            columns: vec![],
+            blocks: vec![],
+            variables: vec![],
+            methods: vec![],
+            type_names: vec![],
+            fields: vec![],
+            variants: vec![],
+        };
+
+        walker.analysis.scopes.push(Scope {
+            kind: ScopeKind::File,
+            parent: None,
+            start: 0,
+            end: usize::MAX,
+            variables: HashMap::new(),
+        });
+        walker.blocks.push(Block::Scope(0));
+
+        for (line, columns, tokens) in lines {
+            walker.line = line;
+            walker.columns = columns;
+            walk::statement(&mut walker, &tokens, 0);
+        }
+
+        Ok(walker.finish())
+    }
+
+    // The symbol defined or used at a position.
+    pub fn symbol_at(&self, line: usize, column: usize) -> Option<usize> {
+        self.symbols
+            .iter()
+            .position(|symbol| symbol.span.contains(line, column))
+            .or_else(|| {
+                self.references
+                    .iter()
+                    .find(|(span, _)| span.contains(line, column))
+                    .map(|(_, symbol)| *symbol)
+            })
+    }
+
+    // Where a symbol is defined. Methods can't be told apart by type, so every method with the
+    // same name is included.
+    pub fn definitions(&self, symbol: usize) -> Vec<Span> {
+        self.related(symbol)
+            .into_iter()
+            .map(|symbol| self.symbols[symbol].span)
+            .collect()
+    }
+
+    // Every place a symbol is defined or used, in order.
+    pub fn occurrences(&self, symbol: usize) -> Vec<Span> {
+        let related = self.related(symbol);
+
+        let mut spans: Vec<Span> = related
+            .iter()
+            .map(|symbol| self.symbols[*symbol].span)
+            .chain(
+                self.references
+                    .iter()
+                    .filter(|(_, symbol)| related.contains(symbol))
+                    .map(|(span, _)| *span),
+            )
+            .collect();
+
+        spans.sort();
+        spans.dedup();
+        spans
+    }
+
+    fn related(&self, symbol: usize) -> Vec<usize> {
+        let found = &self.symbols[symbol];
+
+        if found.kind != SymbolKind::Method {
+            return vec![symbol];
+        }
+
+        (0..self.symbols.len())
+            .filter(|other| {
+                let other = &self.symbols[*other];
+                other.kind == SymbolKind::Method && other.name == found.name
+            })
+            .collect()
+    }
+
+    // Markdown describing what is at a position, and where it is.
+    pub fn hover(&self, line: usize, column: usize) -> Option<(String, Span)> {
+        if let Some((span, name)) = self
+            .builtins
+            .iter()
+            .find(|(span, _)| span.contains(line, column))
+        {
+            let builtin = match name.split_once('.') {
+                Some((module, function)) => builtins::get_in(module, function),
+                None => builtins::get(name),
+            }?;
+
+            let arity = builtin.arity.clone();
+            let text = format!(
+                "```milo\n{}\n```\nBuilt-in function that takes {}.",
+                name,
+                describe_arity(*arity.start(), *arity.end())
+            );
+
+            return Some((text, *span));
+        }
+
+        let symbol = self.symbol_at(line, column)?;
+        let found = &self.symbols[symbol];
+
+        let span = self
+            .references
+            .iter()
+            .find(|(span, _)| span.contains(line, column))
+            .map(|(span, _)| *span)
+            .unwrap_or(found.span);
+
+        let code = match found.kind {
+            SymbolKind::Variable | SymbolKind::Parameter if found.detail.is_empty() => {
+                found.name.clone()
+            }
+            SymbolKind::Variable | SymbolKind::Parameter => {
+                format!("{}: {}", found.name, found.detail)
+            }
+            SymbolKind::Method => match &found.parent {
+                Some(parent) => format!("impl {}\n{}", parent, found.detail),
+                None => found.detail.clone(),
+            },
+            _ => found.detail.clone(),
+        };
+
+        Some((format!("```milo\n{}\n```", code), span))
+    }
+
+    // What could be written at the end of `before`, which is the start of line `line` up to the
+    // cursor.
+    pub fn completions(&self, line: usize, before: &str) -> Vec<Completion> {
+        let word_start = before
+            .char_indices()
+            .rev()
+            .take_while(|(_, char)| char.is_alphanumeric() || *char == '_')
+            .last()
+            .map_or(before.len(), |(i, _)| i);
+
+        match before[..word_start].strip_suffix('.') {
+            Some(receiver) => {
+                let receiver_start = receiver
+                    .char_indices()
+                    .rev()
+                    .take_while(|(_, char)| char.is_alphanumeric() || *char == '_')
+                    .last()
+                    .map_or(receiver.len(), |(i, _)| i);
+
+                self.member_completions(&receiver[receiver_start..], line)
+            }
+            None => self.name_completions(line),
+        }
+    }
+
+    fn name_completions(&self, line: usize) -> Vec<Completion> {
+        let mut completions: Vec<Completion> = KEYWORDS
+            .iter()
+            .chain(&["true", "false", "nil"])
+            .map(|keyword| Completion {
+                label: keyword.to_string(),
+                kind: CompletionKind::Keyword,
+                detail: String::new(),
+            })
+            .collect();
+
+        completions.extend(builtins::BUILTINS.iter().map(|builtin| Completion {
+            label: builtin.name.to_string(),
+            kind: CompletionKind::Function,
+            detail: format!(
+                "takes {}",
+                describe_arity(*builtin.arity.start(), *builtin.arity.end())
+            ),
+        }));
+
+        let mut seen = HashSet::new();
+
+        for symbol in self.visible(line) {
+            let kind = match symbol.kind {
+                SymbolKind::Variable | SymbolKind::Parameter => CompletionKind::Variable,
+                SymbolKind::Struct => CompletionKind::Struct,
+                SymbolKind::Enum => CompletionKind::Enum,
+                SymbolKind::Module => CompletionKind::Module,
+                _ => continue,
+            };
+
+            if seen.insert(symbol.name.clone()) {
+                completions.push(Completion {
+                    label: symbol.name.clone(),
+                    kind,
+                    detail: symbol.detail.clone(),
+                });
+            }
+        }
+
+        completions
+    }
+
+    // The variables that can be used on a line, the innermost first, and every struct, enum and
+    // module.
+    fn visible(&self, line: usize) -> impl Iterator<Item = &Symbol> {
+        let mut scopes: Vec<usize> = (0..self.scopes.len())
+            .filter(|scope| {
+                let scope = &self.scopes[*scope];
+                scope.start <= line && line <= scope.end
+            })
+            .collect();
+        scopes.reverse();
+
+        scopes
+            .into_iter()
+            .flat_map(move |scope| self.scopes[scope].variables.values())
+            .map(|symbol| &self.symbols[*symbol])
+            .chain(self.symbols.iter().filter(|symbol| {
+                matches!(symbol.kind, SymbolKind::Struct | SymbolKind::Enum) && symbol.scope == 0
+            }))
+    }
+
+    fn member_completions(&self, receiver: &str, line: usize) -> Vec<Completion> {
+        if let Some(module) = self.modules.get(receiver) {
+            let constants = builtins::module(module).map(|module| module.keys);
+
+            return builtins::modules()
+                .filter(|(name, _)| name == module)
+                .flat_map(|(_, functions)| functions)
+                .map(|builtin| Completion {
+                    label: builtin.name.to_string(),
+                    kind: CompletionKind::Function,
+                    detail: format!(
+                        "takes {}",
+                        describe_arity(*builtin.arity.start(), *builtin.arity.end())
+                    ),
+                })
+                .chain(constants.into_iter().flatten().map(|key| Completion {
+                    label: key.value,
+                    kind: CompletionKind::Constant,
+                    detail: String::new(),
+                }))
+                .collect();
+        }
+
+        let is_type = |kind| {
+            self.symbols
+                .iter()
+                .any(|symbol| symbol.kind == kind && symbol.name == receiver)
+        };
+
+        let members = |kind, parent: &str| -> Vec<Completion> {
+            self.symbols
+                .iter()
+                .filter(|symbol| symbol.kind == kind && symbol.parent.as_deref() == Some(parent))
+                .map(|symbol| Completion {
+                    label: symbol.name.clone(),
+                    kind: match kind {
+                        SymbolKind::Variant => CompletionKind::Variant,
+                        SymbolKind::Field => CompletionKind::Field,
+                        _ => CompletionKind::Method,
+                    },
+                    detail: symbol.detail.clone(),
+                })
+                .collect()
+        };
+
+        if is_type(SymbolKind::Enum) {
+            return members(SymbolKind::Variant, receiver);
+        }
+
+        // A variable holding a struct has its fields and methods.
+        let struct_name = self
+            .visible(line)
+            .find(|symbol| symbol.name == receiver)
+            .map(|symbol| symbol.detail.clone())
+            .filter(|detail| {
+                self.symbols
+                    .iter()
+                    .any(|symbol| symbol.kind == SymbolKind::Struct && symbol.name == *detail)
+            });
+
+        if let Some(struct_name) = struct_name {
+            let mut completions = members(SymbolKind::Field, &struct_name);
+            completions.extend(members(SymbolKind::Method, &struct_name));
+            return completions;
+        }
+
+        // Otherwise it could be any method.
+        let mut seen = HashSet::new();
+
+        self.symbols
+            .iter()
+            .filter(|symbol| symbol.kind == SymbolKind::Method)
+            .map(|symbol| (symbol.name.clone(), symbol.detail.clone()))
+            .chain(
+                builtins::BUILTINS
+                    .iter()
+                    .filter(|builtin| !builtin.methods.is_empty())
+                    .map(|builtin| (builtin.name.to_string(), String::new())),
+            )
+            .filter(|(name, _)| seen.insert(name.clone()))
+            .map(|(label, detail)| Completion {
+                label,
+                kind: CompletionKind::Method,
+                detail,
+            })
+            .collect()
+    }
+
+    // What every token is, in order.
+    pub fn semantic_tokens(&self) -> Vec<(Span, TokenKind)> {
+        let mut tokens: Vec<(Span, TokenKind)> = self
+            .tokens
+            .iter()
+            .map(|(span, kind)| (*span, *kind))
+            .collect();
+        tokens.sort_by_key(|(span, _)| *span);
+        tokens
+    }
+
+    // The structs and enums with their members, and every global.
+    pub fn outline(&self) -> Vec<Outline> {
+        let outline = |symbol: &Symbol, children| Outline {
+            name: symbol.name.clone(),
+            kind: symbol.kind,
+            span: symbol.span,
+            detail: symbol.detail.clone(),
+            children,
+        };
+
+        let types: HashSet<&str> = self
+            .symbols
+            .iter()
+            .filter(|symbol| matches!(symbol.kind, SymbolKind::Struct | SymbolKind::Enum))
+            .map(|symbol| symbol.name.as_str())
+            .collect();
+
+        let mut outlines = vec![];
+
+        for symbol in &self.symbols {
+            match symbol.kind {
+                SymbolKind::Struct | SymbolKind::Enum => {
+                    let children = self
+                        .symbols
+                        .iter()
+                        .filter(|member| {
+                            matches!(
+                                member.kind,
+                                SymbolKind::Field | SymbolKind::Variant | SymbolKind::Method
+                            ) && member.parent.as_deref() == Some(symbol.name.as_str())
+                        })
+                        .map(|member| outline(member, vec![]))
+                        .collect();
+
+                    outlines.push(outline(symbol, children));
+                }
+                SymbolKind::Method
+                    if !symbol
+                        .parent
+                        .as_deref()
+                        .is_some_and(|parent| types.contains(parent)) =>
+                {
+                    outlines.push(outline(symbol, vec![]))
+                }
+                SymbolKind::Variable | SymbolKind::Module if symbol.scope == 0 => {
+                    outlines.push(outline(symbol, vec![]))
+                }
+                _ => {}
+            }
+        }
+
+        outlines.sort_by_key(|outline| outline.span);
+        outlines
+    }
+}
+
+fn describe_arity(start: usize, end: usize) -> String {
+    match (start, end) {
+        (0, 0) => "no arguments".to_string(),
+        (1, 1) => "1 argument".to_string(),
+        (start, end) if start == end => format!("{} arguments", start),
+        (start, usize::MAX) => format!("at least {} argument(s)", start),
+        (start, end) => format!("{} to {} arguments", start, end),
+    }
+}
+
+// The names of every struct and enum, so they can be told apart from variables before they are
+// declared.
+fn declared_types(lines: &[(usize, Vec<usize>, Vec<Token>)]) -> HashSet<String> {
+    lines
+        .iter()
+        .filter_map(|(_, _, tokens)| {
+            let tokens = match tokens.as_slice() {
+                [export, rest @ ..] if is_keyword(export, "export") => rest,
+                tokens => tokens,
+            };
+
+            match tokens {
+                [keyword, name, ..]
+                    if is_keyword(keyword, "struct") || is_keyword(keyword, "enum") =>
+                {
+                    Some(name.value.clone())
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+// A block of code that is still open.
+enum Block {
+    // The fields of a struct or the variants of an enum, by its symbol.
+    Declaration(usize),
+    Impl(String),
+    Match,
+    Scope(usize),
+}
+
+// Goes through the code once, collecting symbols. Uses that can only be resolved once everything
+// is known, e.g. methods that are called before they are declared, are resolved at the end.
+struct Walker {
+    analysis: Analysis,
+    types: HashSet<String>,
+    line: usize,
+    // The column of each token on the current line.
+    columns: Vec<usize>,
+    blocks: Vec<Block>,
+    // Uses of names that weren't defined yet when they were found.
+    variables: Vec<(Span, String)>,
+    methods: Vec<(Span, String)>,
+    type_names: Vec<(Span, String)>,
+    // Uses of fields, with the struct if it is known.
+    fields: Vec<(Span, Option<String>, String)>,
+    // Uses of variants, with their enum.
+    variants: Vec<(Span, String, String)>,
+}
+
+impl Walker {
+    // Where the token at `i` is, not counting the `(` of a call or the `...` of a rest binding.
+    fn span(&self, tokens: &[Token], offset: usize, i: usize) -> Span {
+        let token = &tokens[i];
+
+        let length = match token.token_type {
+            TokenType::String => token.value.chars().count() + 2,
+            TokenType::Operator if token.value == "[]" => 1,
+            _ => name(&token.value).chars().count(),
+        };
+
+        Span {
+            line: self.line,
+            column: self.columns.get(offset + i).copied().unwrap_or_default(),
+            length,
+        }
+    }
+
+    fn mark(&mut self, span: Span, kind: TokenKind) {
+        self.analysis.tokens.insert(span, kind);
+    }
+
+    fn scope(&self) -> usize {
+        self.blocks
+            .iter()
+            .rev()
+            .find_map(|block| match block {
+                Block::Scope(scope) => Some(*scope),
+                _ => None,
+            })
+            .unwrap_or(0)
+    }
+
+    fn open(&mut self, kind: ScopeKind) {
+        let parent = self.scope();
+
+        self.analysis.scopes.push(Scope {
+            kind,
+            parent: Some(parent),
+            start: self.line,
+            end: usize::MAX,
+            variables: HashMap::new(),
+        });
+        self.blocks
+            .push(Block::Scope(self.analysis.scopes.len() - 1));
+    }
+
+    fn add(&mut self, name: &str, kind: SymbolKind, span: Span, detail: String) -> usize {
+        let parent = self.blocks.iter().rev().find_map(|block| match block {
+            Block::Impl(name) => Some(name.clone()),
+            Block::Declaration(symbol) => Some(self.analysis.symbols[*symbol].name.clone()),
+            _ => None,
+        });
+
+        let parent = match kind {
+            SymbolKind::Method | SymbolKind::Field | SymbolKind::Variant => parent,
+            _ => None,
+        };
+
+        let scope = self.scope();
+
+        self.analysis.symbols.push(Symbol {
+            name: name.to_string(),
+            kind,
+            span,
+            detail,
+            parent,
+            scope,
+        });
+
+        let symbol = self.analysis.symbols.len() - 1;
+
+        if matches!(
+            kind,
+            SymbolKind::Variable | SymbolKind::Parameter | SymbolKind::Module
+        ) {
+            self.analysis.scopes[scope]
+                .variables
+                .entry(name.to_string())
+                .or_insert(symbol);
+        }
+
+        symbol
+    }
+
+    fn lookup(&self, name: &str) -> Option<usize> {
+        let mut scope = Some(self.scope());
+
+        while let Some(current) = scope {
+            if let Some(symbol) = self.analysis.scopes[current].variables.get(name) {
+                return Some(*symbol);
+            }
+
+            scope = self.analysis.scopes[current].parent;
+        }
+
+        None
+    }
+
+    // Sums up a struct or enum by its fields or variants, once its `}` is reached.
+    fn describe(&mut self, symbol: usize) {
+        let is_struct = self.analysis.symbols[symbol].kind == SymbolKind::Struct;
+        let parent = self.analysis.symbols[symbol].name.clone();
+
+        let members: Vec<String> = self
+            .analysis
+            .symbols
+            .iter()
+            .filter(|member| {
+                member.parent.as_deref() == Some(parent.as_str())
+                    && matches!(member.kind, SymbolKind::Field | SymbolKind::Variant)
+            })
+            .map(|member| {
+                member
+                    .detail
+                    .trim_start_matches(&format!("{}.", parent))
+                    .to_string()
+            })
+            .collect();
+
+        let keyword = if is_struct { "struct" } else { "enum" };
+        self.analysis.symbols[symbol].detail =
+            format!("{} {} {{ {} }}", keyword, parent, members.join(", "));
+    }
+
+    fn pattern(&mut self, tokens: &[Token], offset: usize, bindings: &HashSet<String>) {
+        // The struct whose fields are being matched, e.g. `Point` in `Point { x: 0, y }`.
+        let mut structs: Vec<Option<String>> = vec![];
+
+        for (i, token) in tokens.iter().enumerate() {
+            let span = self.span(tokens, offset, i);
+            let next = tokens.get(i + 1);
+            let previous = i.checked_sub(1).map(|i| &tokens[i]);
+
+            match token.token_type {
+                TokenType::Operator if token.value == "{" => {
+                    structs.push(previous.map(|previous| previous.value.clone()));
+                }
+                TokenType::Operator if token.value == "}" => {
+                    structs.pop();
+                }
+                TokenType::Identifier
+                    if previous.is_some_and(|previous| is_operator(previous, ".")) =>
+                {
+                    let enum_name = tokens[i - 2].value.clone();
+                    self.mark(span, TokenKind::Variant);
+                    self.variants
+                        .push((span, enum_name, name(&token.value).to_string()));
+                }
+                TokenType::Identifier
+                    if next
+                        .is_some_and(|next| is_operator(next, ".") || is_operator(next, "{")) =>
+                {
+                    self.type_name(tokens, offset, i);
+                }
+                TokenType::Identifier
+                    if next.is_some_and(|next| is_operator(next, ":"))
+                        || structs.last().is_some_and(Option::is_some)
+                            && !bindings.contains(name(&token.value)) =>
+                {
+                    let parent = structs.last().cloned().flatten();
+                    self.mark(span, TokenKind::Field);
+                    self.fields.push((span, parent, token.value.clone()));
+                }
+                TokenType::Identifier if bindings.contains(name(&token.value)) => {
+                    // `y` in `Point { x: 0, y }` is both a field and a binding.
+                    if let Some(Some(parent)) = structs.last() {
+                        if !next.is_some_and(|next| is_operator(next, ":")) {
+                            self.fields
+                                .push((span, Some(parent.clone()), token.value.clone()));
+                        }
+                    }
+
+                    self.mark(span, TokenKind::Variable);
+                    self.add(
+                        name(&token.value),
+                        SymbolKind::Variable,
+                        span,
+                        String::new(),
+                    );
+                }
+                _ => self.literal(tokens, offset, i),
+            }
+        }
+    }
+
+    // Assigns to the variable at `i`, which is defined by its first assignment in a method or
+    // file.
+    fn assign(&mut self, tokens: &[Token], offset: usize, i: usize, detail: String) {
+        let name = name(&tokens[i].value).to_string();
+        let span = self.span(tokens, offset, i);
+        self.mark(span, TokenKind::Variable);
+
+        // Blocks don't have variables of their own, other than the names they bind.
+        let mut scope = Some(self.scope());
+
+        while let Some(current) = scope {
+            let found = &self.analysis.scopes[current];
+
+            if let Some(symbol) = found.variables.get(&name) {
+                let symbol = *symbol;
+
+                if self.analysis.symbols[symbol].kind == SymbolKind::Parameter {
+                    self.mark(span, TokenKind::Parameter);
+                }
+
+                self.analysis.references.push((span, symbol));
+                return;
+            }
+
+            if found.kind != ScopeKind::Block {
+                break;
+            }
+
+            scope = found.parent;
+        }
+
+        let scope = scope.unwrap_or(0);
+
+        self.analysis.symbols.push(Symbol {
+            name: name.clone(),
+            kind: SymbolKind::Variable,
+            span,
+            detail,
+            parent: None,
+            scope,
+        });
+
+        let symbol = self.analysis.symbols.len() - 1;
+        self.analysis.scopes[scope].variables.insert(name, symbol);
+    }
+
+    // The type of a value, if it is obvious. Named like `type_of` names them.
+    fn infer(&self, tokens: &[Token]) -> String {
+        let top: Vec<&Token> = top_level(tokens).map(|i| &tokens[i]).collect();
+
+        let is_boolean = top.iter().any(|token| {
+            token.token_type == TokenType::Operator
+                && matches!(
+                    token.value.as_str(),
+                    "==" | "!=" | "<" | ">" | "<=" | ">=" | "&&" | "||"
+                )
+        });
+
+        if is_boolean {
+            return "bool".to_string();
+        }
+
+        let joins_strings = top.iter().any(|token| is_operator(token, "+"))
+            && top
+                .iter()
+                .any(|token| token.token_type == TokenType::String);
+
+        if joins_strings {
+            return "string".to_string();
+        }
+
+        let Some(first) = tokens.first() else {
+            return String::new();
+        };
+
+        let inferred = match first.token_type {
+            TokenType::Number if first.value.contains('.') => "float",
+            TokenType::Number => "int",
+            TokenType::String => "string",
+            TokenType::Boolean => "bool",
+            TokenType::Nil => "nil",
+            TokenType::Operator => match first.value.as_str() {
+                "[" => "list",
+                "{" => "map",
+                "(" if count_values(&tokens[1..closing(tokens, 0).min(tokens.len())]) > 1 => {
+                    "tuple"
+                }
+                "(" => return self.infer(&tokens[1..closing(tokens, 0).min(tokens.len())]),
+                _ => "",
+            },
+            TokenType::Identifier => {
+                let next = tokens.get(1);
+
+                if self.types.contains(&first.value)
+                    && next.is_some_and(|next| is_operator(next, "{") || is_operator(next, "."))
+                {
+                    return first.value.clone();
+                }
+
+                if first.value.ends_with('(') && tokens.len() == closing(tokens, 0) + 1 {
+                    return returns(name(&first.value)).to_string();
+                }
+
+                if tokens.len() == 1 {
+                    return self
+                        .lookup(&first.value)
+                        .map(|symbol| self.analysis.symbols[symbol].detail.clone())
+                        .unwrap_or_default();
+                }
+
+                ""
+            }
+            _ => "",
+        };
+
+        inferred.to_string()
+    }
+
+    // Records every use of a name, call and operator in a value.
+    fn expression(&mut self, tokens: &[Token], offset: usize) {
+        let mut i = 0;
+
+        while i < tokens.len() {
+            let token = &tokens[i];
+            let span = self.span(tokens, offset, i);
+            let previous = i.checked_sub(1).map(|i| &tokens[i]);
+            let next = tokens.get(i + 1);
+
+            if token.token_type != TokenType::Identifier {
+                if is_operator(token, ".") {
+                    self.member(tokens, offset, i);
+                    i += 2;
+                    continue;
+                }
+
+                self.literal(tokens, offset, i);
+                i += 1;
+                continue;
+            }
+
+            if previous.is_some_and(|previous| is_operator(previous, ".")) {
+                i += 1;
+                continue;
+            }
+
+            if KEYWORDS.contains(&token.value.as_str()) {
+                self.mark(span, TokenKind::Keyword);
+            } else if token.value.ends_with('(') {
+                if builtins::get(name(&token.value)).is_some() {
+                    self.mark(span, TokenKind::Function);
+                    self.analysis
+                        .builtins
+                        .push((span, name(&token.value).to_string()));
+                }
+            } else if next.is_some_and(|next| is_operator(next, "{"))
+                && self.types.contains(&token.value)
+            {
+                self.type_name(tokens, offset, i);
+
+                // The fields of a struct literal, e.g. `x` in `Point { x: 1 }`.
+                let end = closing(tokens, i + 1);
+                for field in top_level(&tokens[i + 2..end.min(tokens.len())]) {
+                    let field = i + 2 + field;
+
+                    if tokens[field].token_type == TokenType::Identifier
+                        && tokens
+                            .get(field + 1)
+                            .is_some_and(|next| is_operator(next, ":"))
+                    {
+                        let span = self.span(tokens, offset, field);
+                        self.mark(span, TokenKind::Field);
+                        self.fields.push((
+                            span,
+                            Some(token.value.clone()),
+                            tokens[field].value.clone(),
+                        ));
+                    }
+                }
+            } else if next.is_some_and(|next| is_operator(next, ":")) {
+                // A field, already handled by its struct.
+            } else if self.types.contains(&token.value) {
+                self.type_name(tokens, offset, i);
+            } else {
+                self.reference(tokens, offset, i);
+            }
+
+            i += 1;
+        }
+    }
+
+    // The use of a variable at `i`.
+    fn reference(&mut self, tokens: &[Token], offset: usize, i: usize) {
+        let span = self.span(tokens, offset, i);
+        let name = name(&tokens[i].value).to_string();
+
+        match self.lookup(&name) {
+            Some(symbol) => {
+                let kind = match self.analysis.symbols[symbol].kind {
+                    SymbolKind::Parameter => TokenKind::Parameter,
+                    SymbolKind::Module => TokenKind::Module,
+                    _ => TokenKind::Variable,
+                };
+
+                self.mark(span, kind);
+                self.analysis.references.push((span, symbol));
+            }
+            None => {
+                self.mark(span, TokenKind::Variable);
+                self.variables.push((span, name));
+            }
+        }
+    }
+
+    fn type_name(&mut self, tokens: &[Token], offset: usize, i: usize) {
+        let span = self.span(tokens, offset, i);
+        self.mark(span, TokenKind::Struct);
+        self.type_names.push((span, tokens[i].value.clone()));
+    }
+
+    // `.` at `i`, e.g. `p.x`, `xs.len()`, `Shape.Circle(1)` or `math.sqrt(2)`.
+    fn member(&mut self, tokens: &[Token], offset: usize, i: usize) {
+        let Some(member) = tokens.get(i + 1) else {
+            return;
+        };
+
+        let span = self.span(tokens, offset, i + 1);
+        let member_name = name(&member.value).to_string();
+        let receiver = i.checked_sub(1).map(|i| &tokens[i]).filter(|receiver| {
+            receiver.token_type == TokenType::Identifier
+                && !(i >= 2 && is_operator(&tokens[i - 2], "."))
+        });
+
+        let receiver_name = receiver.map(|receiver| receiver.value.clone());
+        let module = receiver_name
+            .as_ref()
+            .and_then(|receiver| self.analysis.modules.get(receiver))
+            .filter(|_| {
+                receiver_name
+                    .as_ref()
+                    .and_then(|receiver| self.lookup(receiver))
+                    .is_some_and(|symbol| self.analysis.symbols[symbol].kind == SymbolKind::Module)
+            })
+            .cloned();
+
+        if let Some(module) = module {
+            if builtins::get_in(&module, &member_name).is_some() {
+                self.mark(span, TokenKind::Function);
+                self.analysis
+                    .builtins
+                    .push((span, format!("{}.{}", module, member_name)));
+            } else {
+                self.mark(span, TokenKind::Variable);
+            }
+            return;
+        }
+
+        if let Some(receiver) = receiver_name.filter(|receiver| self.types.contains(receiver)) {
+            self.mark(span, TokenKind::Variant);
+            self.variants.push((span, receiver, member_name));
+            return;
+        }
+
+        if member.value.ends_with('(') {
+            self.mark(span, TokenKind::Method);
+            self.methods.push((span, member_name));
+            return;
+        }
+
+        // A field, of a struct that is known if the receiver is a variable holding one.
+        let struct_name = receiver
+            .and_then(|receiver| self.lookup(&receiver.value))
+            .map(|symbol| self.analysis.symbols[symbol].detail.clone())
+            .filter(|detail| self.types.contains(detail));
+
+        self.mark(span, TokenKind::Field);
+        self.fields.push((span, struct_name, member_name));
+    }
+
+    // Strings, numbers and operators.
+    fn literal(&mut self, tokens: &[Token], offset: usize, i: usize) {
+        let token = &tokens[i];
+        let span = self.span(tokens, offset, i);
+
+        let kind = match token.token_type {
+            TokenType::String => TokenKind::String,
+            TokenType::Number => TokenKind::Number,
+            TokenType::Boolean | TokenType::Nil => TokenKind::Keyword,
+            TokenType::Operator
+                if is_binary_operator(token)
+                    || matches!(token.value.as_str(), "=" | "=>" | ".." | "..=" | "!") =>
+            {
+                TokenKind::Operator
+            }
+            _ => return,
+        };
+
+        self.mark(span, kind);
+    }
+
+    // Resolves the uses that were found before what they use.
+    fn finish(mut self) -> Analysis {
+        let find = |analysis: &Analysis, kind: SymbolKind, name: &str, parent: Option<&str>| {
+            analysis.symbols.iter().position(|symbol| {
+                symbol.kind == kind
+                    && symbol.name == name
+                    && (parent.is_none() || symbol.parent.as_deref() == parent)
+            })
+        };
+
+        for (span, name) in std::mem::take(&mut self.variables) {
+            let symbol = self.analysis.scopes[0]
+                .variables
+                .get(&name)
+                .copied()
+                .or_else(|| find(&self.analysis, SymbolKind::Struct, &name, None))
+                .or_else(|| find(&self.analysis, SymbolKind::Enum, &name, None));
+
+            if let Some(symbol) = symbol {
+                self.analysis.references.push((span, symbol));
+            }
+        }
+
+        for (span, name) in std::mem::take(&mut self.type_names) {
+            let symbol = find(&self.analysis, SymbolKind::Struct, &name, None)
+                .or_else(|| find(&self.analysis, SymbolKind::Enum, &name, None));
+
+            if let Some(symbol) = symbol {
+                if self.analysis.symbols[symbol].kind == SymbolKind::Enum {
+                    self.mark(span, TokenKind::Enum);
+                }
+
+                self.analysis.references.push((span, symbol));
+            }
+        }
+
+        for (span, name) in std::mem::take(&mut self.methods) {
+            if let Some(symbol) = find(&self.analysis, SymbolKind::Method, &name, None) {
+                self.analysis.references.push((span, symbol));
+            }
+        }
+
+        for (span, parent, name) in std::mem::take(&mut self.fields) {
+            let symbol = match &parent {
+                Some(parent) => find(&self.analysis, SymbolKind::Field, &name, Some(parent)),
+                // Without knowing the struct, only a field name used by one struct is certain.
+                None => {
+                    let mut fields =
+                        self.analysis
+                            .symbols
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, symbol)| {
+                                symbol.kind == SymbolKind::Field && symbol.name == name
+                            });
+
+                    match (fields.next(), fields.next()) {
+                        (Some((symbol, _)), None) => Some(symbol),
+                        _ => None,
+                    }
+                }
+            };
+
+            if let Some(symbol) = symbol {
+                self.analysis.references.push((span, symbol));
+            }
+        }
+
+        for (span, parent, name) in std::mem::take(&mut self.variants) {
+            if let Some(symbol) = find(&self.analysis, SymbolKind::Variant, &name, Some(&parent)) {
+                self.analysis.references.push((span, symbol));
+            }
+        }
+
+        self.analysis
+    }
+}
+
+impl Visitor for Walker {
+    fn inside(&self) -> Inside {
+        match self.blocks.last() {
+            Some(Block::Declaration(_)) => Inside::Declaration,
+            Some(Block::Impl(_)) => Inside::Impl,
+            Some(Block::Match) => Inside::Match,
+            _ => Inside::Code,
+        }
+    }
+
+    fn close(&mut self) {
+        match self.blocks.pop() {
+            Some(Block::Scope(0)) | None => self.blocks.push(Block::Scope(0)),
+            Some(Block::Scope(scope)) => self.analysis.scopes[scope].end = self.line,
+            Some(Block::Declaration(symbol)) => self.describe(symbol),
+            Some(_) => {}
+        }
+    }
+
+    fn value(&mut self, tokens: &[Token], offset: usize) {
+        self.expression(tokens, offset);
+    }
+
+    fn declaration(&mut self, tokens: &[Token], offset: usize) {
+        let Some(name) = tokens.get(1) else {
+            return;
+        };
+
+        let (kind, token_kind) = match tokens[0].value.as_str() {
+            "struct" => (SymbolKind::Struct, TokenKind::Struct),
+            _ => (SymbolKind::Enum, TokenKind::Enum),
+        };
+
+        let span = self.span(tokens, offset, 1);
+        self.mark(span, token_kind);
+
+        let symbol = self.add(&name.value, kind, span, String::new());
+        self.blocks.push(Block::Declaration(symbol));
+    }
+
+    fn members(&mut self, tokens: &[Token], offset: usize) {
+        let Some(Block::Declaration(symbol)) = self.blocks.last() else {
+            return;
+        };
+
+        let is_struct = self.analysis.symbols[*symbol].kind == SymbolKind::Struct;
+        let parent = self.analysis.symbols[*symbol].name.clone();
+        let mut i = 0;
+
+        while i < tokens.len() {
+            let token = &tokens[i];
+
+            if token.token_type != TokenType::Identifier {
+                i += 1;
+                continue;
+            }
+
+            let span = self.span(tokens, offset, i);
+            let name = name(&token.value).to_string();
+
+            if is_struct {
+                self.mark(span, TokenKind::Field);
+                self.add(
+                    &name,
+                    SymbolKind::Field,
+                    span,
+                    format!("{}.{}", parent, name),
+                );
+                i += 1;
+                continue;
+            }
+
+            // A variant, with the names of its values if it has any.
+            let mut detail = format!("{}.{}", parent, name);
+
+            if token.value.ends_with('(') {
+                let end = closing(tokens, i);
+                let payload: Vec<&str> = tokens[i + 1..end.min(tokens.len())]
+                    .iter()
+                    .map(|token| token.value.as_str())
+                    .collect();
+
+                detail = format!("{}({})", detail, payload.join(", "));
+                i = end;
+            }
+
+            self.mark(span, TokenKind::Variant);
+            self.add(&name, SymbolKind::Variant, span, detail);
+            i += 1;
+        }
+    }
+
+    fn implementation(&mut self, tokens: &[Token], offset: usize) {
+        let Some(name) = tokens.get(1) else {
+            return;
+        };
+
+        self.type_name(tokens, offset, 1);
+        self.blocks.push(Block::Impl(name.value.clone()));
+    }
+
+    fn method(&mut self, tokens: &[Token], offset: usize) {
+        let Some(method) = tokens.get(1) else {
+            return;
+        };
+
+        let parameters: Vec<&Token> = tokens[2..]
+            .iter()
+            .take_while(|token| !is_operator(token, ")"))
+            .collect();
+
+        let detail = format!(
+            "fn {}({})",
+            name(&method.value),
+            parameters
+                .iter()
+                .map(|parameter| parameter.value.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        let span = self.span(tokens, offset, 1);
+        self.mark(span, TokenKind::Method);
+        self.add(name(&method.value), SymbolKind::Method, span, detail);
+
+        let parent = self.blocks.iter().rev().find_map(|block| match block {
+            Block::Impl(name) => Some(name.clone()),
+            _ => None,
+        });
+
+        self.open(ScopeKind::Method);
+
+        for i in 0..parameters.len() {
+            let parameter = name(&tokens[2 + i].value).to_string();
+            let span = self.span(tokens, offset, 2 + i);
+            let detail = match parameter.as_str() {
+                "self" => parent.clone().unwrap_or_default(),
+                _ => String::new(),
+            };
+
+            self.mark(span, TokenKind::Parameter);
+            self.add(&parameter, SymbolKind::Parameter, span, detail);
+        }
+    }
+
+    fn test(&mut self, tokens: &[Token], offset: usize) {
+        self.mark(self.span(tokens, offset, 1), TokenKind::String);
+        self.open(ScopeKind::Method);
+    }
+
+    fn block(
+        &mut self,
+        _keyword: &'static str,
+        tokens: &[Token],
+        offset: usize,
+        names: Range<usize>,
+    ) {
+        self.open(ScopeKind::Block);
+
+        for i in names {
+            let span = self.span(tokens, offset, i);
+            self.mark(span, TokenKind::Variable);
+            self.add(&tokens[i].value, SymbolKind::Variable, span, String::new());
+        }
+    }
+
+    fn start_match(&mut self, _value: &[Token], _offset: usize) {
+        self.blocks.push(Block::Match);
+    }
+
+    fn arm(&mut self, tokens: &[Token], offset: usize, pattern: Option<&Pattern>) {
+        let bindings = pattern
+            .map(Pattern::names)
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+
+        self.open(ScopeKind::Block);
+        self.pattern(tokens, offset, &bindings);
+    }
+
+    fn import(&mut self, tokens: &[Token], offset: usize) {
+        let (target, i) = match tokens {
+            [_, target, keyword, _] if is_keyword(keyword, "as") => {
+                self.mark(self.span(tokens, offset, 2), TokenKind::Keyword);
+                (target, 3)
+            }
+            [_, target] => (target, 1),
+            _ => return,
+        };
+
+        let name = match target.token_type {
+            TokenType::String if i == 1 => std::path::Path::new(&target.value)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            _ => tokens[i].value.clone(),
+        };
+
+        let builtin =
+            target.token_type == TokenType::Identifier && builtins::module(&target.value).is_some();
+
+        let detail = if builtin {
+            format!("module {}", target.value)
+        } else {
+            format!("module {} from {}", name, target.value)
+        };
+
+        if builtin {
+            self.analysis
+                .modules
+                .insert(name.clone(), target.value.clone());
+        }
+
+        if target.token_type == TokenType::String {
+            self.mark(self.span(tokens, offset, 1), TokenKind::String);
+        }
+
+        let span = self.span(tokens, offset, i);
+        self.mark(span, TokenKind::Module);
+        self.add(&name, SymbolKind::Module, span, detail);
+    }
+
+    fn target(
+        &mut self,
+        tokens: &[Token],
+        offset: usize,
+        range: Range<usize>,
+        value: Option<&[Token]>,
+    ) {
+        let target = &tokens[range.clone()];
+
+        if target
+            .iter()
+            .any(|token| is_operator(token, ".") || is_operator(token, "[]"))
+        {
+            self.expression(target, offset + range.start);
+            return;
+        }
+
+        let detail = value.map(|value| self.infer(value)).unwrap_or_default();
+
+        for i in range {
+            if tokens[i].token_type == TokenType::Identifier {
+                self.assign(tokens, offset, i, detail.clone());
+            }
+        }
+    }
+
+    fn invalid_targets(&mut self, targets: &[Token], offset: usize, _message: String) {
+        self.expression(targets, offset);
+    }
+
+    fn syntax(&mut self, tokens: &[Token], offset: usize, i: usize) {
+        let kind = match tokens[i].token_type {
+            TokenType::Operator => TokenKind::Operator,
+            _ => TokenKind::Keyword,
+        };
+
+        self.mark(self.span(tokens, offset, i), kind);
+    }
+}
+
+// A name without the `(` of a call or the `...` of a rest binding.
+fn name(value: &str) -> &str {
+    value.trim_end_matches('(').trim_end_matches("...")
+}
+
+// What a built-in function gives back, named like `type_of` names it.
+fn returns(builtin: &str) -> &'static str {
+    match builtin {
+        "str" | "upper" | "lower" | "trim" | "input" | "join" | "replace" | "substring"
+        | "read_all_stdin" | "type_of" | "repeat" => "string",
+        "int" | "len" | "find" => "int",
+        "float" => "float",
+        "bool" | "has" | "contains" | "starts_with" | "ends_with" => "bool",
+        "split" | "chars" | "keys" | "values" | "read_lines" | "args" => "list",
+        "env_vars" => "map",
+        _ => "",
+    }
+}
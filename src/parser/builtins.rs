@@ -0,0 +1,818 @@
+use std::ops::RangeInclusive;
+
+use crate::{
+    lexer::{Token, TokenType},
+    permissions::Permission,
+};
+
+use super::{format_item, nil, Parser};
+
+pub(super) use assert::difference;
+
+mod assert;
+mod convert;
+mod env;
+mod fs;
+mod io;
+mod math;
+mod process;
+mod time;
+
+pub(super) struct Builtin {
+    pub name: &'static str,
+    pub function: fn(&mut Parser, Vec<Token>),
+    // How many arguments it takes, counting the value it is called on as a method.
+    pub arity: RangeInclusive<usize>,
+    // The types it can be called as a method on, with the value as the first argument, e.g. `xs.len()`.
+    pub methods: &'static [TokenType],
+}
+
+// A module that is built in rather than loaded from a file, e.g. `import math`.
+pub(super) struct BuiltinModule {
+    pub name: &'static str,
+    pub functions: &'static [Builtin],
+    pub constants: fn() -> Vec<(&'static str, Token)>,
+}
+
+const MODULES: &[BuiltinModule] = &[math::MODULE, fs::MODULE, process::MODULE, time::MODULE];
+
+const STRING: &[TokenType] = &[TokenType::String];
+
+// Every built-in function, by name.
+pub(super) const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "print",
+        function: print,
+        arity: 0..=1,
+        methods: &[],
+    },
+    Builtin {
+        name: "str",
+        function: convert::str,
+        arity: 1..=1,
+        methods: &[],
+    },
+    Builtin {
+        name: "int",
+        function: convert::int,
+        arity: 1..=1,
+        methods: &[],
+    },
+    Builtin {
+        name: "float",
+        function: convert::float,
+        arity: 1..=1,
+        methods: &[],
+    },
+    Builtin {
+        name: "bool",
+        function: convert::bool,
+        arity: 1..=1,
+        methods: &[],
+    },
+    Builtin {
+        name: "type_of",
+        function: convert::type_of,
+        arity: 1..=1,
+        methods: &[],
+    },
+    Builtin {
+        name: "input",
+        function: io::input,
+        arity: 0..=1,
+        methods: &[],
+    },
+    Builtin {
+        name: "read_all_stdin",
+        function: io::read_all_stdin,
+        arity: 0..=0,
+        methods: &[],
+    },
+    Builtin {
+        name: "read_lines",
+        function: io::read_lines,
+        arity: 0..=0,
+        methods: &[],
+    },
+    Builtin {
+        name: "exit",
+        function: io::exit,
+        arity: 0..=1,
+        methods: &[],
+    },
+    Builtin {
+        name: "args",
+        function: env::args,
+        arity: 0..=0,
+        methods: &[],
+    },
+    Builtin {
+        name: "env",
+        function: env::env,
+        arity: 1..=1,
+        methods: &[],
+    },
+    Builtin {
+        name: "env_vars",
+        function: env::env_vars,
+        arity: 0..=0,
+        methods: &[],
+    },
+    Builtin {
+        name: "len",
+        function: len,
+        arity: 1..=1,
+        methods: &[
+            TokenType::List,
+            TokenType::Map,
+            TokenType::Tuple,
+            TokenType::String,
+        ],
+    },
+    Builtin {
+        name: "push",
+        function: push,
+        arity: 2..=2,
+        methods: &[TokenType::List],
+    },
+    Builtin {
+        name: "pop",
+        function: pop,
+        arity: 1..=1,
+        methods: &[TokenType::List],
+    },
+    Builtin {
+        name: "keys",
+        function: keys,
+        arity: 1..=1,
+        methods: &[TokenType::Map],
+    },
+    Builtin {
+        name: "values",
+        function: values,
+        arity: 1..=1,
+        methods: &[TokenType::Map],
+    },
+    Builtin {
+        name: "has",
+        function: has,
+        arity: 2..=2,
+        methods: &[TokenType::Map],
+    },
+    Builtin {
+        name: "remove",
+        function: remove,
+        arity: 2..=2,
+        methods: &[TokenType::Map],
+    },
+    Builtin {
+        name: "upper",
+        function: upper,
+        arity: 1..=1,
+        methods: STRING,
+    },
+    Builtin {
+        name: "lower",
+        function: lower,
+        arity: 1..=1,
+        methods: STRING,
+    },
+    Builtin {
+        name: "trim",
+        function: trim,
+        arity: 1..=1,
+        methods: STRING,
+    },
+    Builtin {
+        name: "split",
+        function: split,
+        arity: 1..=2,
+        methods: STRING,
+    },
+    Builtin {
+        name: "join",
+        function: join,
+        arity: 1..=2,
+        methods: &[TokenType::List],
+    },
+    Builtin {
+        name: "replace",
+        function: replace,
+        arity: 3..=3,
+        methods: STRING,
+    },
+    Builtin {
+        name: "contains",
+        function: contains,
+        arity: 2..=2,
+        methods: &[TokenType::String, TokenType::List],
+    },
+    Builtin {
+        name: "starts_with",
+        function: starts_with,
+        arity: 2..=2,
+        methods: STRING,
+    },
+    Builtin {
+        name: "ends_with",
+        function: ends_with,
+        arity: 2..=2,
+        methods: STRING,
+    },
+    Builtin {
+        name: "find",
+        function: find,
+        arity: 2..=2,
+        methods: STRING,
+    },
+    Builtin {
+        name: "substring",
+        function: substring,
+        arity: 2..=3,
+        methods: STRING,
+    },
+    Builtin {
+        name: "slice",
+        function: slice,
+        arity: 2..=3,
+        methods: &[TokenType::String, TokenType::List],
+    },
+    Builtin {
+        name: "repeat",
+        function: repeat,
+        arity: 2..=2,
+        methods: STRING,
+    },
+    Builtin {
+        name: "chars",
+        function: chars,
+        arity: 1..=1,
+        methods: STRING,
+    },
+    Builtin {
+        name: "assert",
+        function: assert::assert,
+        arity: 1..=2,
+        methods: &[],
+    },
+    Builtin {
+        name: "assert_eq",
+        function: assert::assert_eq,
+        arity: 2..=2,
+        methods: &[],
+    },
+    Builtin {
+        name: "assert_throws",
+        function: assert::assert_throws,
+        arity: 1..=2,
+        methods: &[],
+    },
+];
+
+pub(super) fn get(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+// A function of a built-in module, e.g. `sqrt` in `math`.
+pub(super) fn get_in(module: &str, name: &str) -> Option<&'static Builtin> {
+    MODULES
+        .iter()
+        .find(|builtin_module| builtin_module.name == module)?
+        .functions
+        .iter()
+        .find(|builtin| builtin.name == name)
+}
+
+// The name and functions of every built-in module.
+pub(super) fn modules() -> impl Iterator<Item = (&'static str, &'static [Builtin])> {
+    MODULES.iter().map(|module| (module.name, module.functions))
+}
+
+// The value of a built-in module, holding its constants. Its functions are found through `get_in`.
+pub(super) fn module(name: &str) -> Option<Token> {
+    let module = MODULES.iter().find(|module| module.name == name)?;
+    let (keys, items) = (module.constants)()
+        .into_iter()
+        .map(|(name, value)| (string(name.to_string()), value))
+        .unzip();
+
+    Some(Token {
+        token_type: TokenType::Module,
+        value: name.to_string(),
+        items,
+        keys,
+    })
+}
+
+pub(super) fn string(value: String) -> Token {
+    Token {
+        token_type: TokenType::String,
+        value,
+        ..Default::default()
+    }
+}
+
+pub(super) fn number(value: usize) -> Token {
+    Token {
+        token_type: TokenType::Number,
+        value: value.to_string(),
+        ..Default::default()
+    }
+}
+
+fn boolean(value: bool) -> Token {
+    Token {
+        token_type: TokenType::Boolean,
+        value: value.to_string(),
+        ..Default::default()
+    }
+}
+
+fn list(items: Vec<Token>) -> Token {
+    Token {
+        token_type: TokenType::List,
+        items,
+        ..Default::default()
+    }
+}
+
+// Checks that the script was given `permission`, and errors if it wasn't.
+fn require(parser: &mut Parser, permission: Permission) -> bool {
+    if parser.permissions.allows(permission) {
+        return true;
+    }
+
+    parser.error(&permission.denied());
+    false
+}
+
+// Checks that every argument of `name` is a string.
+fn strings<const N: usize>(
+    parser: &mut Parser,
+    name: &str,
+    arguments: Vec<Token>,
+) -> Option<[String; N]> {
+    let arguments = parser.arguments::<N>(name, arguments)?;
+
+    if let Some(argument) = arguments
+        .iter()
+        .find(|argument| argument.token_type != TokenType::String)
+    {
+        parser.error(&format!(
+            "`{}` expected a string, got: `{}`",
+            name,
+            format_item(argument)
+        ));
+        return None;
+    }
+
+    Some(arguments.map(|argument| argument.value))
+}
+
+fn print(parser: &mut Parser, mut arguments: Vec<Token>) {
+    if arguments.len() > 1 {
+        parser.error(&format!(
+            "`print` expected 0 or 1 argument(s), got {}",
+            arguments.len()
+        ));
+        return;
+    }
+
+    let token = arguments.pop().unwrap_or(string("".to_string()));
+
+    let mut to_print = &token.value;
+
+    let to_print_if_number = &format!("\x1b[33m{}\x1b[0m", format_item(&token));
+
+    let to_print_yellow = &format!("\x1b[33m{}\x1b[0m", to_print);
+    let to_print_list = &format_item(&token);
+
+    to_print = match token.token_type {
+        // Numbers are printed without leading zeroes.
+        TokenType::Number => to_print_if_number,
+        TokenType::Boolean | TokenType::Nil => to_print_yellow,
+        TokenType::List
+        | TokenType::Map
+        | TokenType::Tuple
+        | TokenType::Struct
+        | TokenType::Enum
+        | TokenType::Module => to_print_list,
+        TokenType::Identifier | TokenType::Operator | TokenType::String => to_print,
+    };
+
+    println!("{}", to_print);
+}
+
+fn len(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([token]) = parser.arguments("len", arguments) else {
+        return;
+    };
+
+    let length = match token.token_type {
+        TokenType::List | TokenType::Map | TokenType::Tuple => token.items.len(),
+        TokenType::String => token.value.chars().count(),
+        _ => {
+            parser.error(&format!(
+                "`len` expected a list, map, tuple, or string, got: `{}`",
+                token.value
+            ));
+            return;
+        }
+    };
+
+    parser.stack.push(number(length));
+}
+
+fn push(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([list, item]) = parser.arguments("push", arguments) else {
+        return;
+    };
+
+    if list.token_type != TokenType::List {
+        parser.error(&format!("`push` expected a list, got: `{}`", list.value));
+        return;
+    }
+
+    let mut list = list;
+    list.items.push(item);
+    parser.update_argument(&list);
+}
+
+fn pop(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([list]) = parser.arguments("pop", arguments) else {
+        return;
+    };
+
+    if list.token_type != TokenType::List {
+        parser.error(&format!("`pop` expected a list, got: `{}`", list.value));
+        return;
+    }
+
+    let mut list = list;
+
+    // Popping from an empty list gives nothing back.
+    let item = list.items.pop().unwrap_or(nil());
+
+    parser.update_argument(&list);
+    parser.stack.push(item);
+}
+
+fn keys(parser: &mut Parser, arguments: Vec<Token>) {
+    keys_or_values(parser, "keys", arguments);
+}
+
+fn values(parser: &mut Parser, arguments: Vec<Token>) {
+    keys_or_values(parser, "values", arguments);
+}
+
+fn keys_or_values(parser: &mut Parser, name: &str, arguments: Vec<Token>) {
+    let Some([map]) = parser.arguments(name, arguments) else {
+        return;
+    };
+
+    if map.token_type != TokenType::Map {
+        parser.error(&format!(
+            "`{}` expected a map, got: `{}`",
+            name,
+            format_item(&map)
+        ));
+        return;
+    }
+
+    parser
+        .stack
+        .push(list(if name == "keys" { map.keys } else { map.items }));
+}
+
+fn has(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some((_, position)) = find_key(parser, "has", arguments) else {
+        return;
+    };
+
+    parser.stack.push(boolean(position.is_some()));
+}
+
+fn remove(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some((mut map, position)) = find_key(parser, "remove", arguments) else {
+        return;
+    };
+
+    // Removing a key that isn't there gives nothing back.
+    let Some(position) = position else {
+        parser.stack.push(nil());
+        return;
+    };
+
+    map.keys.remove(position);
+    let value = map.items.remove(position);
+
+    parser.update_argument(&map);
+    parser.stack.push(value);
+}
+
+// Gives back the map passed to `name`, and the position of the key passed to it.
+fn find_key(
+    parser: &mut Parser,
+    name: &str,
+    arguments: Vec<Token>,
+) -> Option<(Token, Option<usize>)> {
+    let [map, key] = parser.arguments(name, arguments)?;
+
+    if map.token_type != TokenType::Map {
+        parser.error(&format!(
+            "`{}` expected a map, got: `{}`",
+            name,
+            format_item(&map)
+        ));
+        return None;
+    }
+
+    let position = map.keys.iter().position(|k| *k == key);
+    Some((map, position))
+}
+
+fn upper(parser: &mut Parser, arguments: Vec<Token>) {
+    if let Some([text]) = strings(parser, "upper", arguments) {
+        parser.stack.push(string(text.to_uppercase()));
+    }
+}
+
+fn lower(parser: &mut Parser, arguments: Vec<Token>) {
+    if let Some([text]) = strings(parser, "lower", arguments) {
+        parser.stack.push(string(text.to_lowercase()));
+    }
+}
+
+fn trim(parser: &mut Parser, arguments: Vec<Token>) {
+    if let Some([text]) = strings(parser, "trim", arguments) {
+        parser.stack.push(string(text.trim().to_string()));
+    }
+}
+
+// split(text) splits on whitespace, split(text, separator) on the separator.
+fn split(parser: &mut Parser, arguments: Vec<Token>) {
+    let parts = if arguments.len() == 1 {
+        let Some([text]) = strings(parser, "split", arguments) else {
+            return;
+        };
+
+        text.split_whitespace()
+            .map(str::to_string)
+            .collect::<Vec<String>>()
+    } else {
+        let Some([text, separator]) = strings(parser, "split", arguments) else {
+            return;
+        };
+
+        if separator.is_empty() {
+            parser.error("`split` expected a separator that isn't empty, use `chars` instead");
+            return;
+        }
+
+        text.split(separator.as_str()).map(str::to_string).collect()
+    };
+
+    parser
+        .stack
+        .push(list(parts.into_iter().map(string).collect()));
+}
+
+// join(list) or join(list, separator). Values that aren't strings are joined as they would be printed.
+fn join(parser: &mut Parser, mut arguments: Vec<Token>) {
+    if arguments.len() == 1 {
+        arguments.push(string("".to_string()));
+    }
+
+    let Some([items, separator]) = parser.arguments("join", arguments) else {
+        return;
+    };
+
+    if items.token_type != TokenType::List {
+        parser.error(&format!(
+            "`join` expected a list, got: `{}`",
+            format_item(&items)
+        ));
+        return;
+    }
+
+    if separator.token_type != TokenType::String {
+        parser.error(&format!(
+            "`join` expected a string as the separator, got: `{}`",
+            format_item(&separator)
+        ));
+        return;
+    }
+
+    let joined = items
+        .items
+        .iter()
+        .map(|item| match item.token_type {
+            TokenType::String => item.value.clone(),
+            _ => format_item(item),
+        })
+        .collect::<Vec<String>>()
+        .join(&separator.value);
+
+    parser.stack.push(string(joined));
+}
+
+fn replace(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([text, from, to]) = strings(parser, "replace", arguments) else {
+        return;
+    };
+
+    if from.is_empty() {
+        parser.error("`replace` expected a string to replace that isn't empty");
+        return;
+    }
+
+    parser.stack.push(string(text.replace(&from, &to)));
+}
+
+// contains(text, part), or contains(list, item).
+fn contains(parser: &mut Parser, arguments: Vec<Token>) {
+    if arguments
+        .first()
+        .is_some_and(|first| first.token_type == TokenType::List)
+    {
+        let Some([items, item]) = parser.arguments("contains", arguments) else {
+            return;
+        };
+
+        parser.stack.push(boolean(items.items.contains(&item)));
+        return;
+    }
+
+    if let Some([text, part]) = strings(parser, "contains", arguments) {
+        parser.stack.push(boolean(text.contains(&part)));
+    }
+}
+
+fn starts_with(parser: &mut Parser, arguments: Vec<Token>) {
+    if let Some([text, prefix]) = strings(parser, "starts_with", arguments) {
+        parser.stack.push(boolean(text.starts_with(&prefix)));
+    }
+}
+
+fn ends_with(parser: &mut Parser, arguments: Vec<Token>) {
+    if let Some([text, suffix]) = strings(parser, "ends_with", arguments) {
+        parser.stack.push(boolean(text.ends_with(&suffix)));
+    }
+}
+
+// The index of the first character of `part` in `text`, or nil if it isn't in there.
+fn find(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([text, part]) = strings(parser, "find", arguments) else {
+        return;
+    };
+
+    // Indices count characters, not bytes.
+    let index = text
+        .find(&part)
+        .map(|byte| number(text[..byte].chars().count()))
+        .unwrap_or(nil());
+
+    parser.stack.push(index);
+}
+
+fn substring(parser: &mut Parser, arguments: Vec<Token>) {
+    slice_of(parser, "substring", arguments);
+}
+
+fn slice(parser: &mut Parser, arguments: Vec<Token>) {
+    slice_of(parser, "slice", arguments);
+}
+
+// name(value, start) or name(value, start, end), where `value` is a string, or for `slice` also a list.
+// Negative indices count from the end.
+fn slice_of(parser: &mut Parser, name: &str, mut arguments: Vec<Token>) {
+    let value = if arguments.is_empty() {
+        None
+    } else {
+        Some(arguments.remove(0))
+    };
+
+    let Some(value) = value.filter(|_| arguments.len() == 1 || arguments.len() == 2) else {
+        parser.error(&format!(
+            "`{}` expected 2 or 3 argument(s), got {}",
+            name,
+            arguments.len() + 1
+        ));
+        return;
+    };
+
+    let items = match value.token_type {
+        TokenType::String => value
+            .value
+            .chars()
+            .map(|char| string(char.to_string()))
+            .collect::<Vec<Token>>(),
+        TokenType::List if name == "slice" => value.items.clone(),
+        _ => {
+            parser.error(&format!(
+                "`{}` expected a {}, got: `{}`",
+                name,
+                if name == "slice" {
+                    "string or list"
+                } else {
+                    "string"
+                },
+                format_item(&value)
+            ));
+            return;
+        }
+    };
+
+    let length = items.len();
+    let mut bounds = [0, length];
+
+    for (bound, index) in bounds.iter_mut().zip(&arguments) {
+        let Ok(number) = index.value.parse::<i64>() else {
+            parser.error(&format!(
+                "Expected a number as the index, got: `{}`",
+                index.value
+            ));
+            return;
+        };
+
+        let resolved = if number < 0 {
+            number + length as i64
+        } else {
+            number
+        };
+
+        if resolved < 0 || resolved > length as i64 {
+            parser.error(&format!(
+                "Index `{}` is out of bounds for a {} of length {}",
+                number,
+                if value.token_type == TokenType::String {
+                    "string"
+                } else {
+                    "list"
+                },
+                length
+            ));
+            return;
+        }
+
+        *bound = resolved as usize;
+    }
+
+    let [start, end] = bounds;
+    let items = items[start..end.max(start)].to_vec();
+
+    if value.token_type == TokenType::String {
+        parser
+            .stack
+            .push(string(items.into_iter().map(|item| item.value).collect()));
+    } else {
+        parser.stack.push(list(items));
+    }
+}
+
+// The longest string, in bytes, that `repeat` makes, so a typo in the count can't use up all of the
+// memory.
+const MAX_STRING_LENGTH: usize = 1 << 28;
+
+fn repeat(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([text, times]) = parser.arguments("repeat", arguments) else {
+        return;
+    };
+
+    if text.token_type != TokenType::String {
+        parser.error(&format!(
+            "`repeat` expected a string, got: `{}`",
+            format_item(&text)
+        ));
+        return;
+    }
+
+    let Some(times) = (times.token_type == TokenType::Number)
+        .then(|| times.value.parse::<usize>().ok())
+        .flatten()
+    else {
+        parser.error(&format!(
+            "`repeat` expected a number that isn't negative, got: `{}`",
+            format_item(&times)
+        ));
+        return;
+    };
+
+    match times.checked_mul(text.value.len()) {
+        Some(length) if length <= MAX_STRING_LENGTH => {
+            parser.stack.push(string(text.value.repeat(times)))
+        }
+        _ => parser.error(&format!(
+            "`repeat` can't repeat a string {} times, that is too long",
+            times
+        )),
+    }
+}
+
+fn chars(parser: &mut Parser, arguments: Vec<Token>) {
+    if let Some([text]) = strings(parser, "chars", arguments) {
+        parser.stack.push(list(
+            text.chars().map(|char| string(char.to_string())).collect(),
+        ));
+    }
+}
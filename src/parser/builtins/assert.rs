@@ -0,0 +1,80 @@
+use crate::{
+    diff,
+    lexer::{Token, TokenType},
+    parser::{format_item, is_equal, Parser},
+};
+
+// assert(condition) or assert(condition, message)
+pub(super) fn assert(parser: &mut Parser, mut arguments: Vec<Token>) {
+    if arguments.is_empty() || arguments.len() > 2 {
+        parser.error(&format!(
+            "`assert` expected 1 or 2 argument(s), got {}",
+            arguments.len()
+        ));
+        return;
+    }
+
+    let message = (arguments.len() == 2).then(|| arguments.remove(1));
+    let condition = arguments.remove(0);
+
+    if condition.token_type != TokenType::Boolean {
+        parser.error(&format!(
+            "`assert` expected a boolean, got: `{}`",
+            format_item(&condition)
+        ));
+        return;
+    }
+
+    if condition.value == "true" {
+        return;
+    }
+
+    match message {
+        Some(message) if message.token_type == TokenType::String => {
+            parser.error(&format!("Assertion failed: {}", message.value))
+        }
+        Some(message) => parser.error(&format!("Assertion failed: {}", format_item(&message))),
+        None => parser.error("Assertion failed"),
+    }
+}
+
+// assert_eq(actual, expected)
+pub(super) fn assert_eq(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([actual, expected]) = parser.arguments("assert_eq", arguments) else {
+        return;
+    };
+
+    if !is_equal(&actual, &expected) {
+        parser.error(&format!(
+            "Values are not equal\n{}",
+            difference(&expected, &actual)
+        ));
+    }
+}
+
+// Only called when the value didn't throw, as `assert_throws` statements catch errors themselves.
+pub(super) fn assert_throws(parser: &mut Parser, arguments: Vec<Token>) {
+    let value = arguments.first().map_or("nothing".to_string(), format_item);
+
+    parser.error(&format!("Expected an error, but got `{}`", value));
+}
+
+// How `actual` differs from `expected`, with a line starting with `-` for what was expected and
+// `+` for what it was instead. Strings with multiple lines are compared line by line.
+pub(in crate::parser) fn difference(expected: &Token, actual: &Token) -> String {
+    let is_text = |token: &Token| token.token_type == TokenType::String;
+
+    if !(is_text(expected) && is_text(actual))
+        || !(expected.value.contains('\n') || actual.value.contains('\n'))
+    {
+        return format!(
+            "- expected: {}\n+ actual:   {}",
+            format_item(expected),
+            format_item(actual)
+        );
+    }
+
+    let mut lines = vec!["- expected".to_string(), "+ actual".to_string()];
+    lines.extend(diff::lines(&expected.value, &actual.value));
+    lines.join("\n")
+}
@@ -0,0 +1,142 @@
+use crate::{
+    lexer::{Token, TokenType},
+    parser::{format_item, number::Number, Parser},
+};
+
+use super::{boolean, string};
+
+pub(super) fn str(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([value]) = parser.arguments("str", arguments) else {
+        return;
+    };
+
+    if value.token_type == TokenType::String {
+        parser.stack.push(value);
+    } else {
+        parser.stack.push(string(format_item(&value)));
+    }
+}
+
+// Floats are rounded towards zero, strings must hold a whole number.
+pub(super) fn int(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([value]) = parser.arguments("int", arguments) else {
+        return;
+    };
+
+    let number = match value.token_type {
+        TokenType::Number => match Number::parse(&value) {
+            Some(Number::Decimal(decimal)) if decimal.abs() < i64::MAX as f64 => {
+                Some(decimal.trunc() as i64)
+            }
+            Some(Number::Whole(whole)) => Some(whole),
+            _ => None,
+        },
+        TokenType::String => value.value.trim().parse::<i64>().ok(),
+        TokenType::Boolean => Some((value.value == "true") as i64),
+        _ => {
+            parser.error(&format!(
+                "`int` expected a number, string, or boolean, got: `{}`",
+                format_item(&value)
+            ));
+            return;
+        }
+    };
+
+    match number {
+        Some(number) => parser.stack.push(Number::Whole(number).to_token()),
+        None => parser.error(&format!(
+            "`int` could not convert `{}` to a whole number",
+            value.value
+        )),
+    }
+}
+
+pub(super) fn float(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([value]) = parser.arguments("float", arguments) else {
+        return;
+    };
+
+    let number = match value.token_type {
+        TokenType::Number => Number::parse(&value).map(Number::as_f64),
+        TokenType::String => value.value.trim().parse::<f64>().ok(),
+        TokenType::Boolean => Some(if value.value == "true" { 1.0 } else { 0.0 }),
+        _ => {
+            parser.error(&format!(
+                "`float` expected a number, string, or boolean, got: `{}`",
+                format_item(&value)
+            ));
+            return;
+        }
+    };
+
+    // `inf` and `NaN` parse as floats, but aren't numbers Milo can work with.
+    match number.and_then(Number::decimal) {
+        Some(number) => parser.stack.push(number.to_token()),
+        None => parser.error(&format!(
+            "`float` could not convert `{}` to a number",
+            value.value
+        )),
+    }
+}
+
+// Strings must be `true` or `false`. Numbers are true unless they are 0, and nil is false.
+pub(super) fn bool(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([value]) = parser.arguments("bool", arguments) else {
+        return;
+    };
+
+    let result = match value.token_type {
+        TokenType::Boolean => value.value == "true",
+        TokenType::Nil => false,
+        TokenType::Number => Number::parse(&value).is_some_and(|number| number.as_f64() != 0.0),
+        TokenType::String => match value.value.trim() {
+            "true" => true,
+            "false" => false,
+            _ => {
+                parser.error(&format!(
+                    "`bool` could not convert `{}` to a boolean",
+                    value.value
+                ));
+                return;
+            }
+        },
+        _ => {
+            parser.error(&format!(
+                "`bool` expected a boolean, number, string, or nil, got: `{}`",
+                format_item(&value)
+            ));
+            return;
+        }
+    };
+
+    parser.stack.push(boolean(result));
+}
+
+// The name of a value's type. Structs and enums give back the name they were declared with.
+pub(super) fn type_of(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([value]) = parser.arguments("type_of", arguments) else {
+        return;
+    };
+
+    let name = match value.token_type {
+        TokenType::String => "string",
+        TokenType::Number => match Number::parse(&value) {
+            Some(Number::Decimal(_)) => "float",
+            _ => "int",
+        },
+        TokenType::Boolean => "bool",
+        TokenType::Nil => "nil",
+        TokenType::List => "list",
+        TokenType::Map => "map",
+        TokenType::Tuple => "tuple",
+        TokenType::Module => "module",
+        TokenType::Struct => value.value.as_str(),
+        TokenType::Enum => value.value.split('.').next().unwrap_or_default(),
+        TokenType::Identifier | TokenType::Operator => {
+            parser.error(&format!("Unknown identifier: `{}`", value.value));
+            return;
+        }
+    };
+
+    parser.stack.push(string(name.to_string()));
+}
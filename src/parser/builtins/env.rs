@@ -0,0 +1,64 @@
+use std::env;
+
+use crate::{
+    lexer::{Token, TokenType},
+    parser::{nil, Parser},
+    permissions::Permission,
+};
+
+use super::{list, require, string, strings};
+
+// The values given after the file on the command line, e.g. `milo script.milo a b` gives `["a", "b"]`.
+pub(super) fn args(parser: &mut Parser, arguments: Vec<Token>) {
+    if parser.arguments::<0>("args", arguments).is_none() {
+        return;
+    }
+
+    let values = parser.script_args.iter().cloned().map(string).collect();
+    parser.stack.push(list(values));
+}
+
+// env(name). Gives back the value of an environment variable, or nil if it isn't set.
+pub(super) fn env(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([name]) = strings(parser, "env", arguments) else {
+        return;
+    };
+
+    if !require(parser, Permission::Env) {
+        return;
+    }
+
+    // Values that aren't valid UTF-8 get `�` in place of the bytes that aren't.
+    let value =
+        env::var_os(name).map_or(nil(), |value| string(value.to_string_lossy().into_owned()));
+    parser.stack.push(value);
+}
+
+// Every environment variable as a map, sorted by name.
+pub(super) fn env_vars(parser: &mut Parser, arguments: Vec<Token>) {
+    if parser.arguments::<0>("env_vars", arguments).is_none() || !require(parser, Permission::Env) {
+        return;
+    }
+
+    let mut variables = env::vars_os()
+        .map(|(name, value)| {
+            (
+                name.to_string_lossy().into_owned(),
+                value.to_string_lossy().into_owned(),
+            )
+        })
+        .collect::<Vec<(String, String)>>();
+    variables.sort();
+
+    let (keys, items) = variables
+        .into_iter()
+        .map(|(name, value)| (string(name), string(value)))
+        .unzip();
+
+    parser.stack.push(Token {
+        token_type: TokenType::Map,
+        items,
+        keys,
+        ..Default::default()
+    });
+}
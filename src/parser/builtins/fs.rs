@@ -0,0 +1,248 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    lexer::{Token, TokenType},
+    parser::{format_item, nil, Parser},
+    permissions::Permission,
+};
+
+use super::{boolean, list, string, Builtin, BuiltinModule};
+
+pub(in crate::parser) const MODULE: BuiltinModule = BuiltinModule {
+    name: "fs",
+    functions: FUNCTIONS,
+    constants: Vec::new,
+};
+
+const FUNCTIONS: &[Builtin] = &[
+    function("read_file", 1..=1, read_file),
+    function("write_file", 2..=2, write_file),
+    function("append_file", 2..=2, append_file),
+    function("exists", 1..=1, exists),
+    function("list_dir", 1..=1, list_dir),
+    function("remove_file", 1..=1, remove_file),
+    function("join", 0..=usize::MAX, join),
+    function("basename", 1..=1, basename),
+    function("dirname", 1..=1, dirname),
+    function("extension", 1..=1, extension),
+];
+
+const fn function(
+    name: &'static str,
+    arity: RangeInclusive<usize>,
+    function: fn(&mut Parser, Vec<Token>),
+) -> Builtin {
+    Builtin {
+        name,
+        function,
+        arity,
+        methods: &[],
+    }
+}
+
+// Checks that `fs.name` got `N` strings.
+fn strings<const N: usize>(
+    parser: &mut Parser,
+    name: &str,
+    arguments: Vec<Token>,
+) -> Option<[String; N]> {
+    super::strings(parser, &format!("fs.{}", name), arguments)
+}
+
+fn check_read(parser: &mut Parser, path: &str) -> bool {
+    if parser.permissions.can_read(Path::new(path)) {
+        return true;
+    }
+
+    parser.error(&format!("{} to read `{}`", Permission::Read.denied(), path));
+    false
+}
+
+fn check_write(parser: &mut Parser, path: &str) -> bool {
+    if parser.permissions.can_write(Path::new(path)) {
+        return true;
+    }
+
+    parser.error(&format!(
+        "{} to write to `{}`",
+        Permission::Write.denied(),
+        path
+    ));
+    false
+}
+
+// Reports a failed file operation, without the OS error code.
+fn io_error(parser: &mut Parser, action: &str, path: &str, error: io::Error) {
+    let message = error.to_string();
+    let message = message.split(" (os error").next().unwrap_or_default();
+
+    parser.error(&format!("Could not {} `{}`: {}", action, path, message));
+}
+
+fn read_file(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([path]) = strings(parser, "read_file", arguments) else {
+        return;
+    };
+
+    if !check_read(parser, &path) {
+        return;
+    }
+
+    match fs::read_to_string(&path) {
+        Ok(contents) => parser.stack.push(string(contents)),
+        Err(error) => io_error(parser, "read", &path, error),
+    }
+}
+
+fn write_file(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([path, contents]) = strings(parser, "write_file", arguments) else {
+        return;
+    };
+
+    if !check_write(parser, &path) {
+        return;
+    }
+
+    if let Err(error) = fs::write(&path, contents) {
+        io_error(parser, "write to", &path, error);
+    }
+}
+
+fn append_file(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([path, contents]) = strings(parser, "append_file", arguments) else {
+        return;
+    };
+
+    if !check_write(parser, &path) {
+        return;
+    }
+
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()));
+
+    if let Err(error) = result {
+        io_error(parser, "append to", &path, error);
+    }
+}
+
+fn exists(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([path]) = strings(parser, "exists", arguments) else {
+        return;
+    };
+
+    if check_read(parser, &path) {
+        parser.stack.push(boolean(Path::new(&path).exists()));
+    }
+}
+
+// The names of the entries in a directory, sorted.
+fn list_dir(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([path]) = strings(parser, "list_dir", arguments) else {
+        return;
+    };
+
+    if !check_read(parser, &path) {
+        return;
+    }
+
+    let entries = fs::read_dir(&path).and_then(|entries| {
+        entries
+            .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().to_string()))
+            .collect::<Result<Vec<String>, io::Error>>()
+    });
+
+    match entries {
+        Ok(mut entries) => {
+            entries.sort();
+            parser
+                .stack
+                .push(list(entries.into_iter().map(string).collect()));
+        }
+        Err(error) => io_error(parser, "list", &path, error),
+    }
+}
+
+fn remove_file(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([path]) = strings(parser, "remove_file", arguments) else {
+        return;
+    };
+
+    if !check_write(parser, &path) {
+        return;
+    }
+
+    if let Err(error) = fs::remove_file(&path) {
+        io_error(parser, "remove", &path, error);
+    }
+}
+
+// join(part, part, ...)
+fn join(parser: &mut Parser, arguments: Vec<Token>) {
+    if let Some(part) = arguments
+        .iter()
+        .find(|part| part.token_type != TokenType::String)
+    {
+        parser.error(&format!(
+            "`fs.join` expected a string, got: `{}`",
+            format_item(part)
+        ));
+        return;
+    }
+
+    let path = arguments
+        .iter()
+        .map(|part| part.value.as_str())
+        .collect::<PathBuf>();
+
+    parser
+        .stack
+        .push(string(path.to_string_lossy().to_string()));
+}
+
+fn basename(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([path]) = strings(parser, "basename", arguments) else {
+        return;
+    };
+
+    let name = Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    parser.stack.push(string(name));
+}
+
+fn dirname(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([path]) = strings(parser, "dirname", arguments) else {
+        return;
+    };
+
+    let directory = Path::new(&path)
+        .parent()
+        .map(|directory| directory.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    parser.stack.push(string(directory));
+}
+
+// The extension without the `.`, or nil if there is none.
+fn extension(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([path]) = strings(parser, "extension", arguments) else {
+        return;
+    };
+
+    let extension = Path::new(&path)
+        .extension()
+        .map(|extension| string(extension.to_string_lossy().to_string()))
+        .unwrap_or(nil());
+
+    parser.stack.push(extension);
+}
@@ -0,0 +1,118 @@
+use std::io::{self, BufRead, Read, Write};
+
+use crate::{
+    lexer::{Token, TokenType},
+    parser::{format_item, nil, number::Number, Parser},
+};
+
+use super::{list, string};
+
+// input() or input(prompt). Gives back the next line of stdin without its line ending, or nil at the end.
+pub(super) fn input(parser: &mut Parser, mut arguments: Vec<Token>) {
+    if arguments.len() > 1 {
+        parser.error(&format!(
+            "`input` expected 0 or 1 argument(s), got {}",
+            arguments.len()
+        ));
+        return;
+    }
+
+    if let Some(prompt) = arguments.pop() {
+        if prompt.token_type == TokenType::String {
+            print!("{}", prompt.value);
+        } else {
+            print!("{}", format_item(&prompt));
+        }
+
+        io::stdout().flush().unwrap();
+    }
+
+    let mut line = String::new();
+
+    match io::stdin().lock().read_line(&mut line) {
+        Ok(0) => parser.stack.push(nil()),
+        Ok(_) => parser.stack.push(string(trim_line_ending(line))),
+        Err(error) => parser.error(&format!("`input` could not read from stdin: {}", error)),
+    }
+}
+
+// Gives back everything left on stdin as one string.
+pub(super) fn read_all_stdin(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([]) = parser.arguments("read_all_stdin", arguments) else {
+        return;
+    };
+
+    let mut text = String::new();
+
+    match io::stdin().lock().read_to_string(&mut text) {
+        Ok(_) => parser.stack.push(string(text)),
+        Err(error) => parser.error(&format!(
+            "`read_all_stdin` could not read from stdin: {}",
+            error
+        )),
+    }
+}
+
+// Gives back every line left on stdin as a list of strings, without their line endings. Like
+// `read_all_stdin`, it waits for the end of stdin, so `input()` is the way to handle each line as
+// soon as it comes, e.g. from `tail -f`.
+pub(super) fn read_lines(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([]) = parser.arguments("read_lines", arguments) else {
+        return;
+    };
+
+    let lines = io::stdin()
+        .lock()
+        .lines()
+        .map(|line| line.map(string))
+        .collect::<Result<Vec<Token>, io::Error>>();
+
+    match lines {
+        Ok(lines) => parser.stack.push(list(lines)),
+        Err(error) => parser.error(&format!(
+            "`read_lines` could not read from stdin: {}",
+            error
+        )),
+    }
+}
+
+fn trim_line_ending(mut line: String) -> String {
+    if line.ends_with('\n') {
+        line.pop();
+
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+
+    line
+}
+
+// exit() or exit(code). Stops the script, and the process with `code`, or 0 if there is none.
+pub(super) fn exit(parser: &mut Parser, mut arguments: Vec<Token>) {
+    if arguments.len() > 1 {
+        parser.error(&format!(
+            "`exit` expected 0 or 1 argument(s), got {}",
+            arguments.len()
+        ));
+        return;
+    }
+
+    let code = match arguments.pop() {
+        Some(code) => match Number::parse(&code) {
+            // Exit codes only go up to 255, anything else would be cut off by the system.
+            Some(Number::Whole(whole @ 0..=255)) => whole as i32,
+            _ => {
+                parser.error(&format!(
+                    "`exit` expected a whole number from 0 to 255, got: `{}`",
+                    format_item(&code)
+                ));
+                return;
+            }
+        },
+        None => 0,
+    };
+
+    parser.exit_code = Some(code);
+    parser.should_abort = true;
+}
@@ -0,0 +1,340 @@
+use std::{f64::consts, ops::RangeInclusive};
+
+use crate::{
+    lexer::{Token, TokenType},
+    parser::{format_item, number::Number, Parser},
+};
+
+use super::{Builtin, BuiltinModule};
+
+pub(in crate::parser) const MODULE: BuiltinModule = BuiltinModule {
+    name: "math",
+    functions: FUNCTIONS,
+    constants,
+};
+
+const FUNCTIONS: &[Builtin] = &[
+    function("abs", 1..=1, abs),
+    function("min", 1..=usize::MAX, min),
+    function("max", 1..=usize::MAX, max),
+    function("floor", 1..=1, floor),
+    function("ceil", 1..=1, ceil),
+    function("round", 1..=1, round),
+    function("sqrt", 1..=1, sqrt),
+    function("pow", 2..=2, pow),
+    function("sin", 1..=1, sin),
+    function("cos", 1..=1, cos),
+    function("tan", 1..=1, tan),
+    function("log", 1..=2, log),
+    function("exp", 1..=1, exp),
+    function("gcd", 2..=2, gcd),
+    function("lcm", 2..=2, lcm),
+];
+
+const fn function(
+    name: &'static str,
+    arity: RangeInclusive<usize>,
+    function: fn(&mut Parser, Vec<Token>),
+) -> Builtin {
+    Builtin {
+        name,
+        function,
+        arity,
+        methods: &[],
+    }
+}
+
+fn constants() -> Vec<(&'static str, Token)> {
+    vec![
+        ("PI", Number::Decimal(consts::PI).to_token()),
+        ("E", Number::Decimal(consts::E).to_token()),
+    ]
+}
+
+// Checks that `math.name` got `N` numbers.
+fn numbers<const N: usize>(
+    parser: &mut Parser,
+    name: &str,
+    arguments: Vec<Token>,
+) -> Option<[Number; N]> {
+    let name = format!("math.{}", name);
+    let arguments = parser.arguments::<N>(&name, arguments)?;
+
+    let mut numbers = [Number::Whole(0); N];
+
+    for (number, argument) in numbers.iter_mut().zip(&arguments) {
+        let Some(parsed) = Number::parse(argument) else {
+            parser.error(&format!(
+                "`{}` expected a number, got: `{}`",
+                name,
+                format_item(argument)
+            ));
+            return None;
+        };
+
+        *number = parsed;
+    }
+
+    Some(numbers)
+}
+
+// Pushes a float, or reports an error if it is infinite or not a number.
+fn push_decimal(parser: &mut Parser, name: &str, value: f64) {
+    match Number::decimal(value) {
+        Some(number) => parser.stack.push(number.to_token()),
+        None => parser.error(&format!(
+            "`math.{}` gave a result that is out of range",
+            name
+        )),
+    }
+}
+
+// Pushes a float as a whole number, e.g. for `math.floor`.
+fn push_whole(parser: &mut Parser, name: &str, value: f64) {
+    if !value.is_finite() || value.abs() >= i64::MAX as f64 {
+        parser.error(&format!(
+            "`math.{}` gave a result that is out of range",
+            name
+        ));
+        return;
+    }
+
+    parser.stack.push(Number::Whole(value as i64).to_token());
+}
+
+fn abs(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([number]) = numbers(parser, "abs", arguments) else {
+        return;
+    };
+
+    match number {
+        Number::Whole(whole) => match whole.checked_abs() {
+            Some(whole) => parser.stack.push(Number::Whole(whole).to_token()),
+            None => parser.error("`math.abs` gave a result that is out of range"),
+        },
+        Number::Decimal(decimal) => push_decimal(parser, "abs", decimal.abs()),
+    }
+}
+
+fn min(parser: &mut Parser, arguments: Vec<Token>) {
+    min_or_max(parser, "min", arguments);
+}
+
+fn max(parser: &mut Parser, arguments: Vec<Token>) {
+    min_or_max(parser, "max", arguments);
+}
+
+// min(a, b, ...) or min(list), and the same for max.
+fn min_or_max(parser: &mut Parser, name: &str, mut arguments: Vec<Token>) {
+    if arguments.len() == 1 && arguments[0].token_type == TokenType::List {
+        arguments = arguments.remove(0).items;
+    }
+
+    let mut best: Option<(Number, Token)> = None;
+
+    for argument in arguments {
+        let Some(number) = Number::parse(&argument) else {
+            parser.error(&format!(
+                "`math.{}` expected a number, got: `{}`",
+                name,
+                format_item(&argument)
+            ));
+            return;
+        };
+
+        let is_better = best.as_ref().is_none_or(|(best, _)| {
+            let ordering = number.compare(*best);
+
+            if name == "min" {
+                ordering == Some(std::cmp::Ordering::Less)
+            } else {
+                ordering == Some(std::cmp::Ordering::Greater)
+            }
+        });
+
+        if is_better {
+            best = Some((number, argument));
+        }
+    }
+
+    match best {
+        Some((_, token)) => parser.stack.push(token),
+        None => parser.error(&format!("`math.{}` expected at least 1 number", name)),
+    }
+}
+
+fn floor(parser: &mut Parser, arguments: Vec<Token>) {
+    if let Some([number]) = numbers(parser, "floor", arguments) {
+        push_whole(parser, "floor", number.as_f64().floor());
+    }
+}
+
+fn ceil(parser: &mut Parser, arguments: Vec<Token>) {
+    if let Some([number]) = numbers(parser, "ceil", arguments) {
+        push_whole(parser, "ceil", number.as_f64().ceil());
+    }
+}
+
+// Rounds halfway cases away from zero.
+fn round(parser: &mut Parser, arguments: Vec<Token>) {
+    if let Some([number]) = numbers(parser, "round", arguments) {
+        push_whole(parser, "round", number.as_f64().round());
+    }
+}
+
+fn sqrt(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([number]) = numbers(parser, "sqrt", arguments) else {
+        return;
+    };
+
+    if number.as_f64() < 0.0 {
+        parser.error(&format!(
+            "`math.sqrt` expected a number that isn't negative, got: `{}`",
+            number
+        ));
+        return;
+    }
+
+    push_decimal(parser, "sqrt", number.as_f64().sqrt());
+}
+
+// Whole numbers to a whole power that isn't negative stay exact.
+fn pow(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([base, exponent]) = numbers(parser, "pow", arguments) else {
+        return;
+    };
+
+    if let (Number::Whole(base), Number::Whole(exponent)) = (base, exponent) {
+        if let Ok(exponent) = u32::try_from(exponent) {
+            match base.checked_pow(exponent) {
+                Some(result) => parser.stack.push(Number::Whole(result).to_token()),
+                None => parser.error("`math.pow` gave a result that is out of range"),
+            }
+            return;
+        }
+    }
+
+    let result = base.as_f64().powf(exponent.as_f64());
+
+    if result.is_nan() {
+        parser.error(&format!(
+            "`math.pow` is undefined for `{}` to the power of `{}`",
+            base, exponent
+        ));
+        return;
+    }
+
+    push_decimal(parser, "pow", result);
+}
+
+fn sin(parser: &mut Parser, arguments: Vec<Token>) {
+    if let Some([number]) = numbers(parser, "sin", arguments) {
+        push_decimal(parser, "sin", number.as_f64().sin());
+    }
+}
+
+fn cos(parser: &mut Parser, arguments: Vec<Token>) {
+    if let Some([number]) = numbers(parser, "cos", arguments) {
+        push_decimal(parser, "cos", number.as_f64().cos());
+    }
+}
+
+fn tan(parser: &mut Parser, arguments: Vec<Token>) {
+    if let Some([number]) = numbers(parser, "tan", arguments) {
+        push_decimal(parser, "tan", number.as_f64().tan());
+    }
+}
+
+// log(x) is the natural logarithm, log(x, base) uses the given base.
+fn log(parser: &mut Parser, mut arguments: Vec<Token>) {
+    if arguments.len() == 1 {
+        arguments.push(Number::Decimal(consts::E).to_token());
+    }
+
+    let Some([number, base]) = numbers(parser, "log", arguments) else {
+        return;
+    };
+
+    if number.as_f64() <= 0.0 {
+        parser.error(&format!(
+            "`math.log` expected a number above 0, got: `{}`",
+            number
+        ));
+        return;
+    }
+
+    if base.as_f64() <= 0.0 || base.as_f64() == 1.0 {
+        parser.error(&format!(
+            "`math.log` expected a base above 0 that isn't 1, got: `{}`",
+            base
+        ));
+        return;
+    }
+
+    // The common bases have their own, more precise, functions.
+    let result = match base.as_f64() {
+        10.0 => number.as_f64().log10(),
+        2.0 => number.as_f64().log2(),
+        base => number.as_f64().log(base),
+    };
+
+    push_decimal(parser, "log", result);
+}
+
+fn exp(parser: &mut Parser, arguments: Vec<Token>) {
+    if let Some([number]) = numbers(parser, "exp", arguments) {
+        push_decimal(parser, "exp", number.as_f64().exp());
+    }
+}
+
+fn gcd(parser: &mut Parser, arguments: Vec<Token>) {
+    if let Some([a, b]) = whole_numbers(parser, "gcd", arguments) {
+        push_whole_i128(parser, "gcd", greatest_common_divisor(a, b));
+    }
+}
+
+fn lcm(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([a, b]) = whole_numbers(parser, "lcm", arguments) else {
+        return;
+    };
+
+    let divisor = greatest_common_divisor(a, b);
+    let multiple = if divisor == 0 {
+        0
+    } else {
+        (a / divisor * b).abs()
+    };
+
+    push_whole_i128(parser, "lcm", multiple);
+}
+
+fn whole_numbers(parser: &mut Parser, name: &str, arguments: Vec<Token>) -> Option<[i128; 2]> {
+    let [a, b] = numbers(parser, name, arguments)?;
+
+    let (Number::Whole(a), Number::Whole(b)) = (a, b) else {
+        parser.error(&format!("`math.{}` expected whole numbers", name));
+        return None;
+    };
+
+    Some([a as i128, b as i128])
+}
+
+fn push_whole_i128(parser: &mut Parser, name: &str, value: i128) {
+    match i64::try_from(value) {
+        Ok(value) => parser.stack.push(Number::Whole(value).to_token()),
+        Err(_) => parser.error(&format!(
+            "`math.{}` gave a result that is out of range",
+            name
+        )),
+    }
+}
+
+fn greatest_common_divisor(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
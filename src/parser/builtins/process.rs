@@ -0,0 +1,77 @@
+use std::process::Command;
+
+use crate::{
+    lexer::{Token, TokenType},
+    parser::{format_item, number::Number, Parser},
+    permissions::Permission,
+};
+
+use super::{require, string, Builtin, BuiltinModule};
+
+pub(in crate::parser) const MODULE: BuiltinModule = BuiltinModule {
+    name: "process",
+    functions: &[Builtin {
+        name: "run",
+        function: run,
+        arity: 1..=usize::MAX,
+        methods: &[],
+    }],
+    constants: Vec::new,
+};
+
+// run(program, argument, ...). Waits for the program to finish and gives back a map with its
+// `status`, `stdout` and `stderr`. A program that fails still gives back a map, only one that can't
+// be started is an error.
+fn run(parser: &mut Parser, arguments: Vec<Token>) {
+    if arguments.is_empty() {
+        parser.error("`process.run` expected a program to run, got nothing");
+        return;
+    }
+
+    if let Some(argument) = arguments
+        .iter()
+        .find(|argument| argument.token_type != TokenType::String)
+    {
+        parser.error(&format!(
+            "`process.run` expected a string, got: `{}`",
+            format_item(argument)
+        ));
+        return;
+    }
+
+    if !require(parser, Permission::Run) {
+        return;
+    }
+
+    let program = &arguments[0].value;
+    let output = Command::new(program)
+        .args(arguments[1..].iter().map(|argument| &argument.value))
+        .output();
+
+    let output = match output {
+        Ok(output) => output,
+        Err(error) => {
+            let message = error.to_string();
+            let message = message.split(" (os error").next().unwrap_or_default();
+
+            parser.error(&format!("Could not run `{}`: {}", program, message));
+            return;
+        }
+    };
+
+    // Programs stopped by a signal have no exit code.
+    let status = output.status.code().map_or(-1, i64::from);
+
+    parser.stack.push(Token {
+        token_type: TokenType::Map,
+        keys: ["status", "stdout", "stderr"]
+            .map(|key| string(key.to_string()))
+            .to_vec(),
+        items: vec![
+            Number::Whole(status).to_token(),
+            string(String::from_utf8_lossy(&output.stdout).to_string()),
+            string(String::from_utf8_lossy(&output.stderr).to_string()),
+        ],
+        ..Default::default()
+    });
+}
@@ -0,0 +1,70 @@
+use std::{
+    ops::RangeInclusive,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    lexer::Token,
+    parser::{format_item, number::Number, Parser},
+    permissions::Permission,
+};
+
+use super::{require, Builtin, BuiltinModule};
+
+pub(in crate::parser) const MODULE: BuiltinModule = BuiltinModule {
+    name: "time",
+    functions: FUNCTIONS,
+    constants: Vec::new,
+};
+
+const FUNCTIONS: &[Builtin] = &[function("now", 0..=0, now), function("sleep", 1..=1, sleep)];
+
+const fn function(
+    name: &'static str,
+    arity: RangeInclusive<usize>,
+    function: fn(&mut Parser, Vec<Token>),
+) -> Builtin {
+    Builtin {
+        name,
+        function,
+        arity,
+        methods: &[],
+    }
+}
+
+// The seconds since the start of 1970, as a decimal.
+fn now(parser: &mut Parser, arguments: Vec<Token>) {
+    if parser.arguments::<0>("time.now", arguments).is_none() || !require(parser, Permission::Clock)
+    {
+        return;
+    }
+
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+
+    parser.stack.push(Number::Decimal(seconds).to_token());
+}
+
+// sleep(seconds)
+fn sleep(parser: &mut Parser, arguments: Vec<Token>) {
+    let Some([seconds]) = parser.arguments::<1>("time.sleep", arguments) else {
+        return;
+    };
+
+    match Number::parse(&seconds).map(Number::as_f64) {
+        Some(seconds) if seconds >= 0.0 => match Duration::try_from_secs_f64(seconds) {
+            Ok(duration) => thread::sleep(duration),
+            Err(_) => parser.error(&format!(
+                "`time.sleep` can't wait for {} seconds, that is too long",
+                seconds
+            )),
+        },
+        _ => parser.error(&format!(
+            "`time.sleep` expected a positive number of seconds, got: `{}`",
+            format_item(&seconds)
+        )),
+    }
+}
@@ -0,0 +1,1330 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    lexer::{Lexer, Token, TokenType},
+    patterns::Pattern,
+};
+
+use super::{
+    builtins, import_path, missing_closing, precedence, unclosed_bracket,
+    walk::{self, Inside, Visitor},
+};
+
+// A problem found without running the code.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub file: Option<PathBuf>,
+    // The line it is on, starting at 0.
+    pub line: usize,
+    pub message: String,
+}
+
+// Finds unknown names, names used before they are defined, calls with the wrong number of
+// arguments and operators used on the wrong kind of literal, without running anything. Imported
+// files are checked too.
+#[derive(Default)]
+pub struct Checker {
+    // Where `import name` looks after the importing file's directory, from `milo.toml`.
+    pub module_paths: Vec<PathBuf>,
+    // The file each dependency in `milo.toml` is imported from, by name.
+    pub dependencies: HashMap<String, PathBuf>,
+    pub diagnostics: Vec<Diagnostic>,
+    // Every file checked so far by its canonical path, and what it exports. `None` while it is
+    // still being checked, so circular imports don't check forever.
+    modules: HashMap<PathBuf, Option<Exports>>,
+}
+
+// What a file makes available to the files importing it.
+#[derive(Debug, Clone, Default)]
+struct Exports {
+    names: Vec<String>,
+    types: Types,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Types {
+    // The fields of every struct.
+    structs: HashMap<String, Vec<String>>,
+    // The variants of every enum, and how many values they hold.
+    enums: HashMap<String, Vec<(String, usize)>>,
+    // How many arguments the methods with a name take, not counting `self`.
+    methods: HashMap<String, HashSet<usize>>,
+}
+
+impl Types {
+    fn extend(&mut self, other: Types) {
+        self.structs.extend(other.structs);
+        self.enums.extend(other.enums);
+
+        for (name, arities) in other.methods {
+            self.methods.entry(name).or_default().extend(arities);
+        }
+    }
+}
+
+enum Module {
+    Builtin(String),
+    File(Exports),
+}
+
+// A block of code that is still open.
+enum Block {
+    // The fields of a struct or the variants of an enum.
+    Declaration,
+    Impl,
+    // The names assigned in a method, starting with its parameters.
+    Method(HashSet<String>),
+    Match,
+    // The names that only exist inside of the block, e.g. the error in a `catch` block.
+    Scope(HashSet<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Type {
+    Number,
+    String,
+    Boolean,
+    Nil,
+    List,
+}
+
+impl Checker {
+    pub fn check_file(&mut self, path: &Path) {
+        match fs::read_to_string(path) {
+            Ok(code) => {
+                self.check(code, Some(path.to_path_buf()));
+            }
+            Err(error) => self.diagnostics.push(Diagnostic {
+                file: Some(path.to_path_buf()),
+                line: 0,
+                message: format!("Cannot read `{}`, {}", path.display(), error),
+            }),
+        }
+    }
+
+    // Checks code that may not be saved, e.g. from an editor. Imports are found relative to `path`.
+    pub fn check_code(&mut self, code: String, path: Option<PathBuf>) {
+        self.check(code, path);
+    }
+
+    fn check(&mut self, code: String, path: Option<PathBuf>) -> Exports {
+        let mut lexer = Lexer::default();
+
+        let lines = match lexer.lex_code(code) {
+            Ok(lines) => lines,
+            Err(error) => {
+                self.diagnostics.push(Diagnostic {
+                    file: path,
+                    line: error.line,
+                    message: error.message,
+                });
+                return Exports::default();
+            }
+        };
+
+        let mut file = File {
+            checker: self,
+            path,
+            lines: lexer.line_numbers.into_iter().zip(lines).collect(),
+            line: 0,
+            types: Types::default(),
+            globals: HashMap::new(),
+            defined: HashSet::new(),
+            modules: HashMap::new(),
+            exports: vec![],
+            blocks: vec![],
+            opened: vec![],
+        };
+
+        file.declare();
+        file.check();
+
+        let mut types = Types::default();
+
+        for name in &file.exports {
+            if let Some(fields) = file.types.structs.remove(name) {
+                types.structs.insert(name.clone(), fields);
+            }
+
+            if let Some(variants) = file.types.enums.remove(name) {
+                types.enums.insert(name.clone(), variants);
+            }
+        }
+
+        // Methods can't be told apart by type, so all of them come along.
+        types.methods = file.types.methods;
+
+        Exports {
+            names: file.exports,
+            types,
+        }
+    }
+}
+
+// The state of checking a single file.
+struct File<'a> {
+    checker: &'a mut Checker,
+    path: Option<PathBuf>,
+    lines: Vec<(usize, Vec<Token>)>,
+    // The line being checked.
+    line: usize,
+    types: Types,
+    // Every name assigned outside of a method, and the line it is first assigned on.
+    globals: HashMap<String, usize>,
+    // The names outside of methods that have been assigned so far.
+    defined: HashSet<String>,
+    modules: HashMap<String, Module>,
+    exports: Vec<String>,
+    blocks: Vec<Block>,
+    // The keyword that opened each block and the line it is on, to report blocks never closed.
+    opened: Vec<(&'static str, usize)>,
+}
+
+impl File<'_> {
+    fn error(&mut self, message: String) {
+        self.checker.diagnostics.push(Diagnostic {
+            file: self.path.clone(),
+            line: self.line,
+            message,
+        });
+    }
+
+    // Finds every type, method, import and global name first, since methods can use names that
+    // are only assigned further down.
+    fn declare(&mut self) {
+        let lines = std::mem::take(&mut self.lines);
+        let mut lines_iter = lines.iter();
+        let mut depth = 0;
+
+        while let Some((line, tokens)) = lines_iter.next() {
+            self.line = *line;
+
+            let Some(first) = tokens.first() else {
+                continue;
+            };
+
+            if depth == 0 && is_keyword(first, "impl") {
+                if let Some(name) = tokens.get(1) {
+                    self.declare_impl(&name.value.clone(), tokens, &mut lines_iter);
+                }
+                continue;
+            }
+
+            let tokens = match tokens.as_slice() {
+                [export, rest @ ..] if is_keyword(export, "export") => {
+                    if let Some(name) = rest
+                        .get(1)
+                        .filter(|_| is_keyword(&rest[0], "struct") || is_keyword(&rest[0], "enum"))
+                    {
+                        self.exports.push(name.value.clone());
+                    } else if let Some(name) = rest.first() {
+                        self.exports.push(name.value.clone());
+                    }
+
+                    rest
+                }
+                tokens => tokens,
+            };
+
+            match tokens.first() {
+                Some(keyword) if is_keyword(keyword, "struct") || is_keyword(keyword, "enum") => {
+                    let mut declaration = tokens.to_vec();
+
+                    // The fields or variants may go on over multiple lines.
+                    while !declaration.iter().any(|token| is_operator(token, "}")) {
+                        let Some((_, tokens)) = lines_iter.next() else {
+                            break;
+                        };
+                        declaration.extend(tokens.iter().cloned());
+                    }
+
+                    self.declare_type(&declaration);
+                    continue;
+                }
+                Some(keyword) if is_keyword(keyword, "import") => {
+                    self.declare_import(tokens);
+                    continue;
+                }
+                _ => {}
+            }
+
+            if depth == 0 {
+                for name in assigned_names(tokens) {
+                    self.globals.entry(name).or_insert(*line);
+                }
+            }
+
+            for token in tokens {
+                if is_operator(token, "{") {
+                    depth += 1;
+                } else if is_operator(token, "}") {
+                    depth -= 1;
+                }
+            }
+
+            depth = depth.max(0);
+        }
+
+        self.lines = lines;
+    }
+
+    fn declare_type(&mut self, tokens: &[Token]) {
+        let [keyword, name, opening, body @ ..] = tokens else {
+            return;
+        };
+
+        if !is_operator(opening, "{") {
+            return;
+        }
+
+        let body = &body[..body
+            .iter()
+            .position(|token| is_operator(token, "}"))
+            .unwrap_or(body.len())];
+
+        if keyword.value == "struct" {
+            let fields = body
+                .iter()
+                .filter(|token| token.token_type == TokenType::Identifier)
+                .map(|token| token.value.clone())
+                .collect();
+
+            self.types.structs.insert(name.value.clone(), fields);
+            return;
+        }
+
+        let mut variants = vec![];
+        let mut i = 0;
+
+        while i < body.len() {
+            let token = &body[i];
+            i += 1;
+
+            match token.value.strip_suffix('(') {
+                Some(variant) => {
+                    let length = body[i..]
+                        .iter()
+                        .position(|token| is_operator(token, ")"))
+                        .unwrap_or(body.len() - i);
+
+                    variants.push((variant.to_string(), length));
+                    i += length + 1;
+                }
+                None => variants.push((token.value.clone(), 0)),
+            }
+        }
+
+        self.types.enums.insert(name.value.clone(), variants);
+    }
+
+    fn declare_impl<'b>(
+        &mut self,
+        type_name: &str,
+        tokens: &[Token],
+        lines: &mut impl Iterator<Item = &'b (usize, Vec<Token>)>,
+    ) {
+        let mut depth = brace_balance(tokens);
+
+        for (line, tokens) in lines {
+            self.line = *line;
+
+            if depth == 1 {
+                if let [keyword, name, rest @ ..] = tokens.as_slice() {
+                    if is_keyword(keyword, "fn") && name.value.ends_with('(') {
+                        let parameters = rest
+                            .iter()
+                            .take_while(|token| !is_operator(token, ")"))
+                            .count();
+
+                        self.types
+                            .methods
+                            .entry(name.value.trim_end_matches('(').to_string())
+                            .or_default()
+                            .insert(parameters.saturating_sub(1));
+                    }
+                }
+            }
+
+            depth += brace_balance(tokens);
+
+            if depth <= 0 {
+                break;
+            }
+        }
+
+        if !self.types.structs.contains_key(type_name) && !self.types.enums.contains_key(type_name)
+        {
+            self.error(format!(
+                "Cannot implement methods for `{}`, which is not a struct or enum",
+                type_name
+            ));
+        }
+    }
+
+    fn declare_import(&mut self, tokens: &[Token]) {
+        let (target, alias) = match tokens {
+            [_, target] => (target, None),
+            [_, target, keyword, alias] if is_keyword(keyword, "as") => {
+                (target, Some(alias.value.clone()))
+            }
+            _ => {
+                self.error(
+                    "`import` expected a module name or path, and optionally `as name`".to_string(),
+                );
+                return;
+            }
+        };
+
+        if target.token_type == TokenType::Identifier && builtins::module(&target.value).is_some() {
+            let name = alias.unwrap_or(target.value.clone());
+            self.globals.entry(name.clone()).or_insert(self.line);
+            self.modules
+                .insert(name, Module::Builtin(target.value.clone()));
+            return;
+        }
+
+        let Some((path, default_name)) = import_path(
+            target,
+            self.path.as_deref(),
+            &self.checker.dependencies,
+            &self.checker.module_paths,
+        ) else {
+            self.error(format!(
+                "`import` expected a module name or path, got: `{}`",
+                target.value
+            ));
+            return;
+        };
+
+        let Ok(canonical) = fs::canonicalize(&path) else {
+            self.error(format!("Cannot import `{}`, no such file", path.display()));
+            return;
+        };
+
+        let exports = match self.checker.modules.get(&canonical) {
+            Some(Some(exports)) => exports.clone(),
+            // A circular import, which is reported when it runs.
+            Some(None) => Exports::default(),
+            None => {
+                self.checker.modules.insert(canonical.clone(), None);
+
+                let exports = match fs::read_to_string(&path) {
+                    Ok(code) => self.checker.check(code, Some(path.clone())),
+                    Err(error) => {
+                        self.error(format!("Cannot import `{}`, {}", path.display(), error));
+                        Exports::default()
+                    }
+                };
+
+                self.checker
+                    .modules
+                    .insert(canonical, Some(exports.clone()));
+                exports
+            }
+        };
+
+        let name = alias.unwrap_or(default_name);
+        self.globals.entry(name.clone()).or_insert(self.line);
+
+        // The types are only known by their name in the module, e.g. `geometry.Point`.
+        let mut types = exports.types.clone();
+        let qualify = |type_name: String| format!("{}.{}", name, type_name);
+        types.structs = types
+            .structs
+            .into_iter()
+            .map(|(type_name, fields)| (qualify(type_name), fields))
+            .collect();
+        types.enums = types
+            .enums
+            .into_iter()
+            .map(|(type_name, variants)| (qualify(type_name), variants))
+            .collect();
+
+        self.types.extend(types);
+        self.modules.insert(name, Module::File(exports));
+    }
+
+    fn check(&mut self) {
+        for (line, mut tokens) in std::mem::take(&mut self.lines) {
+            self.line = line;
+
+            let types = &self.types;
+            qualify_types(&mut tokens, |name| {
+                types.structs.contains_key(name) || types.enums.contains_key(name)
+            });
+
+            walk::statement(self, &tokens, 0);
+        }
+
+        // Like when running, the outermost block is the one reported, or the method in an `impl`.
+        let unclosed = match self.opened.as_slice() {
+            [("impl", _), method @ ("fn", _), ..] => Some(*method),
+            [block, ..] => Some(*block),
+            [] => None,
+        };
+
+        if let Some((keyword, line)) = unclosed {
+            self.line = line;
+            self.error(missing_closing(keyword, "}"));
+        }
+    }
+
+    fn open(&mut self, block: Block, keyword: &'static str) {
+        self.blocks.push(block);
+        self.opened.push((keyword, self.line));
+    }
+
+    // Reports a bracket that is never closed, which stops the line from running.
+    fn brackets(&mut self, tokens: &[Token]) {
+        if let Some(message) = unclosed_bracket(tokens) {
+            self.error(message);
+        }
+    }
+
+    fn define(&mut self, name: String) {
+        for block in self.blocks.iter_mut().rev() {
+            if let Block::Method(names) = block {
+                names.insert(name);
+                return;
+            }
+        }
+
+        self.defined.insert(name);
+    }
+
+    fn check_pattern(&mut self, pattern: &Pattern) {
+        let mut errors = vec![];
+
+        pattern.walk(&mut |pattern| match pattern {
+            Pattern::Struct { name, fields } => match self.types.structs.get(name) {
+                Some(declared) => {
+                    for (field, _) in fields {
+                        if !declared.contains(field) {
+                            errors.push(format!("`{}` has no field `{}`", name, field));
+                        }
+                    }
+                }
+                None => errors.push(format!("Unknown struct: `{}`", name)),
+            },
+            Pattern::Variant {
+                enum_name,
+                variant,
+                payload,
+            } => match self.types.enums.get(enum_name) {
+                Some(variants) => match variants.iter().find(|(name, _)| name == variant) {
+                    Some((_, length)) if *length != payload.len() => errors.push(format!(
+                        "`{}.{}` expected {} argument(s), got {}",
+                        enum_name,
+                        variant,
+                        length,
+                        payload.len()
+                    )),
+                    Some(_) => {}
+                    None => errors.push(format!("`{}` has no variant `{}`", enum_name, variant)),
+                },
+                None => errors.push(format!("Unknown enum: `{}`", enum_name)),
+            },
+            _ => {}
+        });
+
+        for error in errors {
+            self.error(error);
+        }
+    }
+
+    // Checks every name, call and operator in a value.
+    fn expression(&mut self, tokens: &[Token]) {
+        let mut i = 0;
+
+        while i < tokens.len() {
+            let token = &tokens[i];
+
+            match token.token_type {
+                TokenType::Identifier if token.value.ends_with('(') => {
+                    let end = closing(tokens, i);
+                    let arguments = &tokens[i + 1..end.min(tokens.len())];
+                    self.call(token.value.trim_end_matches('('), arguments);
+                    self.expression(arguments);
+                    i = end + 1;
+                    continue;
+                }
+                TokenType::Identifier
+                    if tokens.get(i + 1).is_some_and(|next| is_operator(next, "{"))
+                        && self.types.structs.contains_key(&token.value) =>
+                {
+                    let end = closing(tokens, i + 1);
+                    self.struct_literal(&token.value, &tokens[i + 2..end.min(tokens.len())]);
+                    i = end + 1;
+                    continue;
+                }
+                TokenType::Identifier => self.reference(&token.value),
+                TokenType::Operator if token.value == "." => {
+                    i = self.member(tokens, i);
+                    continue;
+                }
+                TokenType::Operator => self.operator(tokens, i),
+                _ => {}
+            }
+
+            i += 1;
+        }
+    }
+
+    fn reference(&mut self, name: &str) {
+        if name == "_" || name == "as" {
+            return;
+        }
+
+        let mut in_method = false;
+
+        for block in self.blocks.iter().rev() {
+            match block {
+                Block::Method(names) | Block::Scope(names) if names.contains(name) => return,
+                Block::Method(_) => in_method = true,
+                _ => {}
+            }
+        }
+
+        // Methods run when they are called, so they can use names assigned below them.
+        if self.defined.contains(name)
+            || in_method && self.globals.contains_key(name)
+            || self.types.structs.contains_key(name)
+            || self.types.enums.contains_key(name)
+        {
+            return;
+        }
+
+        match self.globals.get(name) {
+            Some(line) => self.error(format!(
+                "`{}` is used before it is defined on line {}",
+                name,
+                line + 1
+            )),
+            None => self.error(format!("Unknown identifier: `{}`", name)),
+        }
+    }
+
+    fn call(&mut self, name: &str, arguments: &[Token]) {
+        let Some(builtin) = builtins::get(name) else {
+            self.error(format!("Unknown function: `{}`", name));
+            return;
+        };
+
+        let count = count_values(arguments);
+
+        if !builtin.arity.contains(&count) {
+            self.error(arity_error(name, builtin.arity.clone(), count));
+        }
+    }
+
+    // `.` at `i`, e.g. `p.x`, `xs.len()`, `Shape.Circle(1)` or `math.sqrt(2)`. Gives back where
+    // the value goes on after it.
+    fn member(&mut self, tokens: &[Token], i: usize) -> usize {
+        let Some(member) = tokens.get(i + 1) else {
+            return i + 1;
+        };
+
+        // Only names can be told apart before running, e.g. `math` but not `list()[0]`.
+        let receiver = tokens
+            .get(i.wrapping_sub(1))
+            .filter(|token| token.token_type == TokenType::Identifier && i >= 1)
+            .filter(|_| i < 2 || !is_operator(&tokens[i - 2], "."))
+            .map(|token| token.value.clone());
+
+        let Some(name) = member.value.strip_suffix('(') else {
+            if let Some(receiver) = receiver {
+                self.field(&receiver, &member.value);
+            }
+            return i + 2;
+        };
+
+        let end = closing(tokens, i + 1);
+        let arguments = &tokens[i + 2..end.min(tokens.len())];
+        let count = count_values(arguments);
+
+        self.expression(arguments);
+
+        let module = receiver
+            .as_ref()
+            .filter(|receiver| !self.is_variable(receiver))
+            .and_then(|receiver| self.modules.get(receiver));
+
+        let message = match (module, &receiver) {
+            (Some(Module::Builtin(module)), Some(receiver)) => {
+                match builtins::get_in(module, name) {
+                    Some(builtin) if builtin.arity.contains(&count) => None,
+                    Some(builtin) => Some(arity_error(
+                        &format!("{}.{}", module, name),
+                        builtin.arity.clone(),
+                        count,
+                    )),
+                    None => Some(format!("Module `{}` doesn't export `{}`", receiver, name)),
+                }
+            }
+            (Some(Module::File(exports)), Some(receiver)) => {
+                (!exports.names.iter().any(|export| export == name))
+                    .then(|| format!("Module `{}` doesn't export `{}`", receiver, name))
+            }
+            _ => match receiver
+                .as_ref()
+                .filter(|receiver| !self.is_variable(receiver))
+                .and_then(|receiver| self.types.enums.get(receiver).map(|e| (receiver, e)))
+            {
+                Some((receiver, variants)) => {
+                    match variants.iter().find(|(variant, _)| variant == name) {
+                        Some((_, length)) if *length != count => Some(format!(
+                            "`{}.{}` expected {} argument(s), got {}",
+                            receiver, name, length, count
+                        )),
+                        Some(_) => None,
+                        None => Some(format!("`{}` has no variant `{}`", receiver, name)),
+                    }
+                }
+                None => self.method_call(name, count),
+            },
+        };
+
+        if let Some(message) = message {
+            self.error(message);
+        }
+
+        end + 1
+    }
+
+    // `value.name(...)` with `count` arguments, on a value whose type isn't known.
+    fn method_call(&self, name: &str, count: usize) -> Option<String> {
+        let methods = self.types.methods.get(name);
+        let builtin = builtins::get(name).filter(|builtin| !builtin.methods.is_empty());
+
+        if methods.is_some_and(|arities| arities.contains(&count))
+            || builtin.is_some_and(|builtin| builtin.arity.contains(&(count + 1)))
+        {
+            return None;
+        }
+
+        match (methods, builtin) {
+            (Some(arities), None) if arities.len() == 1 => Some(format!(
+                "`{}` expected {} argument(s), got {}",
+                name,
+                arities.iter().next().unwrap(),
+                count
+            )),
+            (None, Some(builtin)) => Some(arity_error(
+                name,
+                builtin.arity.start().saturating_sub(1)..=builtin.arity.end().saturating_sub(1),
+                count,
+            )),
+            (None, None) => Some(format!("Unknown method: `{}`", name)),
+            _ => None,
+        }
+    }
+
+    // `receiver.name` without parentheses.
+    fn field(&mut self, receiver: &str, name: &str) {
+        if self.is_variable(receiver) {
+            return;
+        }
+
+        let message = match self.modules.get(receiver) {
+            Some(Module::Builtin(module)) => {
+                let exports = builtins::module(module)
+                    .is_some_and(|module| module.keys.iter().any(|key| key.value == name))
+                    || builtins::get_in(module, name).is_some();
+
+                (!exports).then(|| format!("Module `{}` doesn't export `{}`", receiver, name))
+            }
+            Some(Module::File(exports)) => (!exports.names.iter().any(|export| export == name))
+                .then(|| format!("Module `{}` doesn't export `{}`", receiver, name)),
+            None => self.types.enums.get(receiver).and_then(|variants| {
+                (!variants.iter().any(|(variant, _)| variant == name))
+                    .then(|| format!("`{}` has no variant `{}`", receiver, name))
+            }),
+        };
+
+        if let Some(message) = message {
+            self.error(message);
+        }
+    }
+
+    // Whether `name` is a variable rather than a module or type, so nothing is known about it.
+    fn is_variable(&self, name: &str) -> bool {
+        !self.modules.contains_key(name)
+            && (self.defined.contains(name)
+                || self.blocks.iter().any(|block| {
+                    matches!(block, Block::Method(names) | Block::Scope(names) if names.contains(name))
+                }))
+    }
+
+    // The inside of `Name { field: value, ... }`.
+    fn struct_literal(&mut self, name: &str, tokens: &[Token]) {
+        // The fields of nested structs and maps are left to checking their values.
+        let fields: Vec<usize> = top_level(tokens)
+            .filter(|&i| {
+                tokens[i].token_type == TokenType::Identifier
+                    && tokens.get(i + 1).is_some_and(|next| is_operator(next, ":"))
+            })
+            .collect();
+
+        let given: Vec<String> = fields.iter().map(|&i| tokens[i].value.clone()).collect();
+
+        for (n, &i) in fields.iter().enumerate() {
+            let end = fields.get(n + 1).copied().unwrap_or(tokens.len());
+            self.expression(&tokens[i + 2..end]);
+        }
+
+        let fields = self.types.structs[name].clone();
+
+        for field in &given {
+            if !fields.contains(field) {
+                self.error(format!("`{}` has no field `{}`", name, field));
+            }
+        }
+
+        for field in fields {
+            if !given.contains(&field) {
+                self.error(format!("Missing field `{}` in `{}`", field, name));
+            }
+        }
+    }
+
+    // Reports operators used on a literal of the wrong type, e.g. `1 - "a"`.
+    fn operator(&mut self, tokens: &[Token], i: usize) {
+        let operator = tokens[i].value.as_str();
+
+        let expected = match operator {
+            "-" | "*" | "/" | "<" | ">" | "<=" | ">=" => Type::Number,
+            "&&" | "||" => Type::Boolean,
+            "+" => Type::String,
+            _ => return,
+        };
+
+        // A literal is only a whole side of the operator if the operator past it doesn't take it,
+        // e.g. `2` in `1 < 2 && 3` belongs to `<`, not `&&`.
+        let binding = precedence(&tokens[i]).unwrap_or(0);
+        let takes = |token: Option<&Token>, ties: bool| {
+            token
+                .and_then(precedence)
+                .is_some_and(|other| other > binding || ties && other == binding)
+        };
+
+        let left = i
+            .checked_sub(1)
+            .and_then(|j| operand(tokens, j, false))
+            .filter(|(start, _, _)| !takes(start.checked_sub(1).and_then(|j| tokens.get(j)), true));
+        let right =
+            operand(tokens, i + 1, true).filter(|(end, _, _)| !takes(tokens.get(end + 1), false));
+
+        // `+` joins strings with anything but lists, so both sides have to be known otherwise.
+        if expected == Type::String {
+            let sides = [&left, &right].map(|side| side.as_ref().map(|(_, kind, _)| *kind));
+
+            let wrong = if sides.contains(&Some(Type::List)) {
+                [&left, &right]
+                    .into_iter()
+                    .flatten()
+                    .find(|(_, kind, _)| *kind == Type::List)
+            } else if sides
+                .iter()
+                .all(|side| side.is_some_and(|kind| kind != Type::String))
+            {
+                [&left, &right]
+                    .into_iter()
+                    .flatten()
+                    .find(|(_, kind, _)| *kind != Type::Number)
+            } else {
+                None
+            };
+
+            if let Some((_, _, written)) = wrong {
+                self.error(format!(
+                    "`+` expected a string or number, got: `{}`",
+                    written
+                ));
+            }
+            return;
+        }
+
+        for (_, kind, written) in [left, right].into_iter().flatten() {
+            if kind != expected {
+                let expected = if expected == Type::Number {
+                    "a number"
+                } else {
+                    "a boolean"
+                };
+
+                self.error(format!(
+                    "`{}` expected {}, got: `{}`",
+                    operator, expected, written
+                ));
+                return;
+            }
+        }
+    }
+}
+
+impl Visitor for File<'_> {
+    fn inside(&self) -> Inside {
+        match self.blocks.last() {
+            Some(Block::Declaration) => Inside::Declaration,
+            Some(Block::Impl) => Inside::Impl,
+            Some(Block::Match) => Inside::Match,
+            _ => Inside::Code,
+        }
+    }
+
+    fn close(&mut self) {
+        if self.blocks.pop().is_none() {
+            self.error("`}` has no opening `{`".to_string());
+        }
+
+        self.opened.pop();
+    }
+
+    fn value(&mut self, tokens: &[Token], _offset: usize) {
+        self.brackets(tokens);
+        self.expression(tokens);
+    }
+
+    fn declaration(&mut self, tokens: &[Token], _offset: usize) {
+        let keyword = if tokens[0].value == "struct" {
+            "struct"
+        } else {
+            "enum"
+        };
+        self.open(Block::Declaration, keyword);
+    }
+
+    fn implementation(&mut self, _tokens: &[Token], _offset: usize) {
+        self.open(Block::Impl, "impl");
+    }
+
+    fn method(&mut self, tokens: &[Token], _offset: usize) {
+        let parameters = tokens[1..]
+            .iter()
+            .skip(1)
+            .take_while(|token| !is_operator(token, ")"))
+            .map(|token| token.value.clone())
+            .collect();
+
+        self.open(Block::Method(parameters), "fn");
+    }
+
+    // Tests run after the rest of the file, so like methods they can use any global.
+    fn test(&mut self, _tokens: &[Token], _offset: usize) {
+        self.open(Block::Method(HashSet::new()), "test");
+    }
+
+    fn block(
+        &mut self,
+        keyword: &'static str,
+        tokens: &[Token],
+        _offset: usize,
+        names: Range<usize>,
+    ) {
+        let names = tokens[names]
+            .iter()
+            .map(|name| name.value.clone())
+            .collect();
+
+        self.open(Block::Scope(names), keyword);
+    }
+
+    fn start_match(&mut self, _value: &[Token], _offset: usize) {
+        self.open(Block::Match, "match");
+    }
+
+    fn arm(&mut self, _tokens: &[Token], _offset: usize, pattern: Option<&Pattern>) {
+        let names = pattern.map(Pattern::names).unwrap_or_default();
+
+        if let Some(pattern) = pattern {
+            self.check_pattern(pattern);
+        }
+
+        self.open(Block::Scope(names.into_iter().collect()), "=>");
+    }
+
+    // Already handled by `declare`.
+    fn import(&mut self, tokens: &[Token], _offset: usize) {
+        if let Some(name) = match tokens {
+            [_, _, keyword, alias] if is_keyword(keyword, "as") => Some(alias),
+            [_, target] => Some(target),
+            _ => None,
+        } {
+            let name = Path::new(&name.value)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or(name.value.clone());
+            self.defined.insert(name);
+        }
+    }
+
+    fn target(
+        &mut self,
+        tokens: &[Token],
+        _offset: usize,
+        range: Range<usize>,
+        _value: Option<&[Token]>,
+    ) {
+        let target = &tokens[range];
+        self.brackets(target);
+
+        // Assigning to a field or index needs the value it is on to exist.
+        if target
+            .iter()
+            .any(|token| is_operator(token, ".") || is_operator(token, "[]"))
+        {
+            let field = target.len() >= 2 && is_operator(&target[target.len() - 2], ".");
+            self.expression(&target[..target.len() - if field { 2 } else { 0 }]);
+            return;
+        }
+
+        for token in target {
+            if token.token_type == TokenType::Identifier {
+                self.define(token.value.trim_end_matches("...").to_string());
+            }
+        }
+    }
+
+    fn invalid_targets(&mut self, _targets: &[Token], _offset: usize, message: String) {
+        self.error(message);
+    }
+}
+
+// The literal that starts (or, going left, ends) at `i`, with the position of its other end, its
+// type and how it is written, e.g. `1` or `[1, 2]`.
+fn operand(tokens: &[Token], i: usize, rightwards: bool) -> Option<(usize, Type, String)> {
+    let token = tokens.get(i)?;
+
+    if let Some(kind) = literal_type(token) {
+        return Some((i, kind, literal(token)));
+    }
+
+    // A list, unless it is indexed right away, e.g. `[1, 2][0]`.
+    let (start, end) = if rightwards && is_operator(token, "[") {
+        (i, closing(tokens, i))
+    } else if !rightwards && is_operator(token, "]") {
+        let mut depth = 0;
+        let start = (0..=i).rev().find(|&j| {
+            if is_closing(&tokens[j]) {
+                depth += 1;
+            } else if is_opening(&tokens[j]) {
+                depth -= 1;
+            }
+            depth == 0
+        })?;
+
+        (start, i)
+    } else {
+        return None;
+    };
+
+    let indexed = tokens
+        .get(end + 1)
+        .is_some_and(|next| is_operator(next, "[]") || is_operator(next, "."));
+
+    if !is_operator(&tokens[start], "[") || end >= tokens.len() || indexed {
+        return None;
+    }
+
+    Some((
+        if rightwards { end } else { start },
+        Type::List,
+        written(&tokens[start..=end]),
+    ))
+}
+
+// The code the tokens were written as, with the commas the lexer drops, e.g. `[1, [2, x]]`.
+fn written(tokens: &[Token]) -> String {
+    let mut text = String::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        if let Some(previous) = i.checked_sub(1).map(|i| &tokens[i]) {
+            let is_value = |token: &Token, start: bool| {
+                token.token_type.is_value()
+                    || token.token_type == TokenType::Identifier
+                        && (start || !token.value.ends_with('('))
+                    || is_operator(token, if start { "[" } else { "]" })
+                    || is_operator(token, if start { "(" } else { ")" })
+            };
+
+            if is_value(previous, false) && is_value(token, true) {
+                text.push_str(", ");
+            } else if !(is_opening(previous)
+                || is_closing(token)
+                || is_operator(previous, ".")
+                || is_operator(token, ".")
+                || is_operator(token, "[]"))
+            {
+                text.push(' ');
+            }
+        }
+
+        if is_operator(token, "[]") {
+            text.push('[');
+        } else {
+            text.push_str(&literal(token));
+        }
+    }
+
+    text
+}
+
+// `test "name" {`, which starts a test that only `milo test` runs.
+pub fn is_test(tokens: &[Token]) -> bool {
+    matches!(tokens, [keyword, name, opening, ..]
+        if is_keyword(keyword, "test")
+            && name.token_type == TokenType::String
+            && is_operator(opening, "{"))
+}
+
+pub(super) fn is_operator(token: &Token, operator: &str) -> bool {
+    token.token_type == TokenType::Operator && token.value == operator
+}
+
+pub(super) fn is_keyword(token: &Token, keyword: &str) -> bool {
+    token.token_type == TokenType::Identifier && token.value == keyword
+}
+
+pub(super) fn is_binary_operator(token: &Token) -> bool {
+    precedence(token).is_some()
+}
+
+pub(super) fn is_opening(token: &Token) -> bool {
+    token.token_type == TokenType::Identifier && token.value.ends_with('(')
+        || token.token_type == TokenType::Operator
+            && matches!(token.value.as_str(), "(" | "[" | "[]" | "{")
+}
+
+pub(super) fn is_closing(token: &Token) -> bool {
+    token.token_type == TokenType::Operator && matches!(token.value.as_str(), ")" | "]" | "}")
+}
+
+// The position of the bracket that closes the one at `opening`, or the end of `tokens`.
+pub(super) fn closing(tokens: &[Token], opening: usize) -> usize {
+    let mut depth = 0;
+
+    for (i, token) in tokens.iter().enumerate().skip(opening) {
+        if is_opening(token) {
+            depth += 1;
+        } else if is_closing(token) {
+            depth -= 1;
+
+            if depth == 0 {
+                return i;
+            }
+        }
+    }
+
+    tokens.len()
+}
+
+// The positions of the tokens that aren't inside of brackets.
+pub(super) fn top_level(tokens: &[Token]) -> impl Iterator<Item = usize> + '_ {
+    let mut depth = 0;
+
+    tokens.iter().enumerate().filter_map(move |(i, token)| {
+        if is_opening(token) {
+            depth += 1;
+        } else if is_closing(token) {
+            depth -= 1;
+        }
+
+        (depth == 0 && !is_closing(token)).then_some(i)
+    })
+}
+
+// Joins `module.Type` into a single name, which is what the types a module exports are called in
+// the files importing it.
+pub(super) fn qualify_types(tokens: &mut Vec<Token>, is_type: impl Fn(&str) -> bool) {
+    let mut i = 0;
+
+    while i + 2 < tokens.len() {
+        let is_qualified = tokens[i].token_type == TokenType::Identifier
+            && is_operator(&tokens[i + 1], ".")
+            && tokens[i + 2].token_type == TokenType::Identifier
+            && (i == 0 || !is_operator(&tokens[i - 1], "."));
+
+        if is_qualified {
+            let name = format!("{}.{}", tokens[i].value, tokens[i + 2].value);
+
+            if is_type(&name) {
+                tokens[i].value = name;
+                tokens.drain(i + 1..i + 3);
+            }
+        }
+
+        i += 1;
+    }
+}
+
+// The positions of `in` and of the `{` that starts the body in `for item in value {`, skipping over a
+// map or anything else in brackets in the value.
+pub(super) fn loop_bounds(tokens: &[Token]) -> Option<(usize, usize)> {
+    let in_index = tokens.iter().position(|token| is_keyword(token, "in"))?;
+    let mut i = in_index + 1;
+
+    while i < tokens.len() {
+        if i > in_index + 1 && is_operator(&tokens[i], "{") {
+            return Some((in_index, i));
+        }
+
+        i = if is_opening(&tokens[i]) {
+            closing(tokens, i) + 1
+        } else {
+            i + 1
+        };
+    }
+
+    None
+}
+
+// Where a statement ends: at a `}` that closes a block rather than a map or struct.
+pub(super) fn statement_end(tokens: &[Token]) -> usize {
+    let mut depth = 0;
+
+    for (i, token) in tokens.iter().enumerate() {
+        if is_opening(token) {
+            depth += 1;
+        } else if is_closing(token) {
+            if depth == 0 && is_operator(token, "}") {
+                return i;
+            }
+            // A `)` or `]` too many is left over when running, rather than ending anything.
+            depth = (depth - 1).max(0);
+        }
+    }
+
+    tokens.len()
+}
+
+// The tokens after the first `{`, e.g. the first statement of `try { x }`.
+pub(super) fn after_opening(tokens: &[Token]) -> &[Token] {
+    match tokens.iter().position(|token| is_operator(token, "{")) {
+        Some(opening) => &tokens[opening + 1..],
+        None => &[],
+    }
+}
+
+fn brace_balance(tokens: &[Token]) -> i32 {
+    tokens
+        .iter()
+        .map(|token| {
+            if is_operator(token, "{") {
+                1
+            } else if is_operator(token, "}") {
+                -1
+            } else {
+                0
+            }
+        })
+        .sum()
+}
+
+// The names a line assigns to, e.g. `a` and `b` in `a, b = 1, 2`.
+pub(super) fn assigned_names(tokens: &[Token]) -> Vec<String> {
+    let Some(equals) = top_level(tokens).find(|&i| is_operator(&tokens[i], "=")) else {
+        return vec![];
+    };
+
+    let targets = &tokens[..equals];
+
+    if targets
+        .iter()
+        .any(|token| token.token_type != TokenType::Identifier)
+    {
+        return vec![];
+    }
+
+    targets
+        .iter()
+        .map(|token| token.value.trim_end_matches("...").to_string())
+        .collect()
+}
+
+// How many values a list of arguments gives, e.g. 2 for `a + 1, b.len()`. Commas aren't kept by
+// the lexer, so every operator joins two values into one.
+pub(super) fn count_values(tokens: &[Token]) -> usize {
+    let mut values = 0_isize;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let token = &tokens[i];
+
+        if is_operator(token, ".") {
+            // A method call or field, which belongs to the value before it.
+            i = match tokens.get(i + 1) {
+                Some(member) if member.value.ends_with('(') => closing(tokens, i + 1) + 1,
+                _ => i + 2,
+            };
+            continue;
+        }
+
+        if is_operator(token, "[]") {
+            i = closing(tokens, i) + 1;
+            continue;
+        }
+
+        if is_opening(token) {
+            // A struct's `{` belongs to its name.
+            let is_struct = is_operator(token, "{")
+                && i > 0
+                && tokens[i - 1].token_type == TokenType::Identifier
+                && !tokens[i - 1].value.ends_with('(');
+
+            if !is_struct {
+                values += 1;
+            }
+
+            i = closing(tokens, i) + 1;
+            continue;
+        }
+
+        if is_binary_operator(token) {
+            values -= 1;
+        } else if token.token_type != TokenType::Operator {
+            values += 1;
+        }
+
+        i += 1;
+    }
+
+    values.max(0) as usize
+}
+
+pub(super) fn literal_type(token: &Token) -> Option<Type> {
+    match token.token_type {
+        TokenType::Number => Some(Type::Number),
+        TokenType::String => Some(Type::String),
+        TokenType::Boolean => Some(Type::Boolean),
+        TokenType::Nil => Some(Type::Nil),
+        _ => None,
+    }
+}
+
+// A literal the way it is written.
+fn literal(token: &Token) -> String {
+    match token.token_type {
+        TokenType::String => format!("\"{}\"", token.value),
+        _ => token.value.clone(),
+    }
+}
+
+fn arity_error(name: &str, arity: std::ops::RangeInclusive<usize>, count: usize) -> String {
+    let expected = if arity.start() == arity.end() {
+        arity.start().to_string()
+    } else if *arity.end() == usize::MAX - 1 || *arity.end() == usize::MAX {
+        format!("at least {}", arity.start())
+    } else {
+        format!("{} to {}", arity.start(), arity.end())
+    };
+
+    format!(
+        "`{}` expected {} argument(s), got {}",
+        name, expected, count
+    )
+}
@@ -0,0 +1,860 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt, fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    lexer::{Lexer, Token, TokenType},
+    patterns::Pattern,
+};
+
+use super::{
+    builtins,
+    checker::{
+        assigned_names, is_binary_operator, is_keyword, is_opening, is_operator, literal_type,
+        Diagnostic,
+    },
+    walk::{self, Inside, Visitor},
+};
+
+// What a warning is about. Every rule can be allowed or denied by its id, e.g. `unused-variable`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rule {
+    // A variable, parameter, match binding, caught error or import that is never used.
+    UnusedVariable,
+    // A value that is replaced before it is read, or that is never read although the variable is.
+    UnusedAssignment,
+    // Code after a `return`, `throw` or `exit()` in the same block.
+    UnreachableCode,
+    // `x == x`, which is always the same.
+    SelfComparison,
+    // A match value or guard made only of literals, e.g. `match 1 == 1 {` or `x if 1 == 1 => ...`.
+    ConstantCondition,
+    // A name that hides a variable from outside of its block or method.
+    Shadowing,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 6] = [
+        Rule::UnusedVariable,
+        Rule::UnusedAssignment,
+        Rule::UnreachableCode,
+        Rule::SelfComparison,
+        Rule::ConstantCondition,
+        Rule::Shadowing,
+    ];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Rule::UnusedVariable => "unused-variable",
+            Rule::UnusedAssignment => "unused-assignment",
+            Rule::UnreachableCode => "unreachable-code",
+            Rule::SelfComparison => "self-comparison",
+            Rule::ConstantCondition => "constant-condition",
+            Rule::Shadowing => "shadowing",
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(id: &str) -> Result<Rule, String> {
+        Rule::ALL
+            .into_iter()
+            .find(|rule| rule.id() == id)
+            .ok_or_else(|| {
+                let ids: Vec<&str> = Rule::ALL.iter().map(|rule| rule.id()).collect();
+                format!(
+                    "Unknown lint rule `{}`, expected one of: {}",
+                    id,
+                    ids.join(", ")
+                )
+            })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub rule: Rule,
+    pub file: Option<PathBuf>,
+    // The line it is on, starting at 0.
+    pub line: usize,
+    pub message: String,
+    // Denied rules are errors rather than warnings.
+    pub denied: bool,
+}
+
+// Warns about code that runs, but is probably a mistake.
+#[derive(Default)]
+pub struct Linter {
+    pub allowed: HashSet<Rule>,
+    pub denied: HashSet<Rule>,
+    pub warnings: Vec<Warning>,
+    // Files that can't be linted, e.g. because they don't lex.
+    pub errors: Vec<Diagnostic>,
+}
+
+impl Linter {
+    pub fn lint_file(&mut self, path: &Path) {
+        match fs::read_to_string(path) {
+            Ok(code) => self.lint(code, Some(path.to_path_buf())),
+            Err(error) => self.errors.push(Diagnostic {
+                file: Some(path.to_path_buf()),
+                line: 0,
+                message: format!("Cannot read `{}`, {}", path.display(), error),
+            }),
+        }
+    }
+
+    pub fn lint(&mut self, code: String, path: Option<PathBuf>) {
+        let allowed = self.inline_allows(&code, &path);
+        let mut lexer = Lexer::default();
+
+        let lines = match lexer.lex_code(code) {
+            Ok(lines) => lines,
+            Err(error) => {
+                self.errors.push(Diagnostic {
+                    file: path,
+                    line: error.line,
+                    message: error.message,
+                });
+                return;
+            }
+        };
+
+        let lines: Vec<(usize, Vec<Token>)> = lexer.line_numbers.into_iter().zip(lines).collect();
+        let (globals, exports) = globals(&lines);
+
+        let mut file = File {
+            globals,
+            exports,
+            line: 0,
+            blocks: vec![Block::Scope(Scope::new(ScopeKind::File, 0))],
+            scopes: 1,
+            method_reads: HashSet::new(),
+            overwritten: vec![],
+            found: vec![],
+        };
+
+        for (line, tokens) in &lines {
+            file.line = *line;
+            walk::statement(&mut file, tokens, 0);
+        }
+
+        file.finish();
+
+        let mut found = file.found;
+        found.sort_by_key(|(_, line, _)| *line);
+
+        for (rule, line, message) in found {
+            let allowed_here = allowed
+                .get(&line)
+                .is_some_and(|rules| rules.contains(&rule));
+
+            if allowed_here || self.allowed.contains(&rule) && !self.denied.contains(&rule) {
+                continue;
+            }
+
+            self.warnings.push(Warning {
+                rule,
+                file: path.clone(),
+                line,
+                message,
+                denied: self.denied.contains(&rule),
+            });
+        }
+    }
+
+    // The rules allowed on each line by a `# milo: allow(rule, ...)` comment. A comment after
+    // code allows them on its own line, and a comment on its own line allows them on the next.
+    fn inline_allows(&mut self, code: &str, path: &Option<PathBuf>) -> HashMap<usize, Vec<Rule>> {
+        let mut allowed: HashMap<usize, Vec<Rule>> = HashMap::new();
+        let mut pending = vec![];
+
+        for (number, line) in code.split('\n').enumerate() {
+            let (code, comment) = split_comment(line);
+
+            if code.trim().is_empty() && comment.is_none() {
+                continue;
+            }
+
+            let rules = comment
+                .and_then(|comment| comment.trim().strip_prefix("milo:"))
+                .and_then(|directive| directive.trim().strip_prefix("allow("))
+                .and_then(|directive| directive.split(')').next());
+
+            for id in rules.into_iter().flat_map(|rules| rules.split(',')) {
+                match id.trim().parse() {
+                    Ok(rule) => pending.push(rule),
+                    Err(message) => self.errors.push(Diagnostic {
+                        file: path.clone(),
+                        line: number,
+                        message,
+                    }),
+                }
+            }
+
+            if !code.trim().is_empty() {
+                allowed.entry(number).or_default().append(&mut pending);
+            }
+        }
+
+        allowed
+    }
+}
+
+// A line split into its code and its comment, if it has one.
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut in_string = false;
+
+    for (i, char) in line.char_indices() {
+        match char {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return (&line[..i], Some(&line[i + 1..])),
+            '/' if !in_string && line[i + 1..].starts_with('/') => {
+                return (&line[..i], Some(&line[i + 2..]))
+            }
+            _ => {}
+        }
+    }
+
+    (line, None)
+}
+
+// Every name assigned or imported outside of methods, with the line it is first defined on, and
+// the names that are exported.
+fn globals(lines: &[(usize, Vec<Token>)]) -> (HashMap<String, usize>, HashSet<String>) {
+    let mut globals = HashMap::new();
+    let mut exports = HashSet::new();
+    let mut depth = 0;
+
+    for (line, tokens) in lines {
+        let tokens = match tokens.as_slice() {
+            [export, rest @ ..] if is_keyword(export, "export") => {
+                let name = match rest {
+                    [keyword, name, ..]
+                        if is_keyword(keyword, "struct") || is_keyword(keyword, "enum") =>
+                    {
+                        name
+                    }
+                    [name, ..] => name,
+                    [] => continue,
+                };
+
+                exports.insert(name.value.clone());
+                rest
+            }
+            tokens => tokens,
+        };
+
+        if depth == 0 {
+            let names = match tokens {
+                [import, ..] if is_keyword(import, "import") => {
+                    import_name(tokens).into_iter().collect()
+                }
+                tokens => assigned_names(tokens),
+            };
+
+            for name in names {
+                globals.entry(name).or_insert(*line);
+            }
+        }
+
+        for token in tokens {
+            if is_operator(token, "{") {
+                depth += 1;
+            } else if is_operator(token, "}") {
+                depth -= 1;
+            }
+        }
+
+        depth = depth.max(0);
+    }
+
+    (globals, exports)
+}
+
+// The name `import x` or `import "x.milo" as y` makes available.
+fn import_name(tokens: &[Token]) -> Option<String> {
+    let name = match tokens {
+        [_, _, keyword, alias] if is_keyword(keyword, "as") => alias,
+        [_, target] => target,
+        _ => return None,
+    };
+
+    Some(
+        Path::new(&name.value)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or(name.value.clone()),
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Assigned,
+    Parameter,
+    Binding,
+    Import,
+}
+
+struct Variable {
+    line: usize,
+    kind: Kind,
+    read: bool,
+    // The line of the last value assigned to it that hasn't been read yet, and the scope it was
+    // assigned in.
+    unread: Option<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ScopeKind {
+    File,
+    Method,
+    // A block inside of a file or method, e.g. a match arm.
+    Block,
+}
+
+struct Scope {
+    kind: ScopeKind,
+    // Every scope gets its own id, so values assigned in different blocks aren't compared.
+    id: usize,
+    variables: BTreeMap<String, Variable>,
+    // The `return`, `throw` or `exit()` that ended the block early, and its line.
+    ended: Option<(&'static str, usize)>,
+}
+
+impl Scope {
+    fn new(kind: ScopeKind, id: usize) -> Scope {
+        Scope {
+            kind,
+            id,
+            variables: BTreeMap::new(),
+            ended: None,
+        }
+    }
+}
+
+// A block of code that is still open.
+enum Block {
+    // The fields of a struct or the variants of an enum.
+    Declaration,
+    Impl,
+    Match,
+    Scope(Scope),
+}
+
+// The state of linting a single file.
+struct File {
+    globals: HashMap<String, usize>,
+    exports: HashSet<String>,
+    line: usize,
+    blocks: Vec<Block>,
+    // How many scopes have been opened, for their ids.
+    scopes: usize,
+    // Globals read by methods, which can happen any time the method is called.
+    method_reads: HashSet<String>,
+    // Values of globals that are assigned again before being read, by name and line.
+    overwritten: Vec<(String, usize)>,
+    found: Vec<(Rule, usize, String)>,
+}
+
+impl File {
+    fn warn(&mut self, rule: Rule, line: usize, message: String) {
+        self.found.push((rule, line, message));
+    }
+
+    fn open(&mut self, kind: ScopeKind) {
+        self.blocks
+            .push(Block::Scope(Scope::new(kind, self.scopes)));
+        self.scopes += 1;
+    }
+
+    fn scope(&mut self) -> &mut Scope {
+        self.blocks
+            .iter_mut()
+            .rev()
+            .find_map(|block| match block {
+                Block::Scope(scope) => Some(scope),
+                _ => None,
+            })
+            .expect("the file is always in scope")
+    }
+
+    fn in_method(&self) -> bool {
+        self.blocks
+            .iter()
+            .any(|block| matches!(block, Block::Scope(scope) if scope.kind == ScopeKind::Method))
+    }
+
+    fn finish(&mut self) {
+        while self.blocks.len() > 1 {
+            self.blocks.pop();
+        }
+
+        let Some(Block::Scope(mut scope)) = self.blocks.pop() else {
+            return;
+        };
+
+        // Exported names are read by the files that import them.
+        for (name, variable) in &mut scope.variables {
+            if self.method_reads.contains(name) || self.exports.contains(name) {
+                variable.read = true;
+                variable.unread = None;
+            }
+        }
+
+        for (name, line) in std::mem::take(&mut self.overwritten) {
+            if !self.method_reads.contains(&name) {
+                self.warn(
+                    Rule::UnusedAssignment,
+                    line,
+                    format!("The value assigned to `{}` is never read", name),
+                );
+            }
+        }
+
+        self.report(scope);
+    }
+
+    // Warns about the variables of a scope that has ended, which can no longer be read.
+    fn report(&mut self, scope: Scope) {
+        for (name, variable) in scope.variables {
+            if name.starts_with('_') || name == "self" {
+                continue;
+            }
+
+            let (rule, message) = match (variable.kind, variable.read) {
+                (Kind::Assigned, false) => (
+                    Rule::UnusedVariable,
+                    format!("`{}` is assigned but never read", name),
+                ),
+                (Kind::Import, false) => (
+                    Rule::UnusedVariable,
+                    format!("`{}` is imported but never used", name),
+                ),
+                (_, false) => (Rule::UnusedVariable, format!("`{}` is never used", name)),
+                (_, true) => match variable.unread {
+                    Some((line, id)) if id == scope.id => {
+                        self.warn(
+                            Rule::UnusedAssignment,
+                            line,
+                            format!("The value assigned to `{}` is never read", name),
+                        );
+                        continue;
+                    }
+                    _ => continue,
+                },
+            };
+
+            self.warn(rule, variable.line, message);
+        }
+    }
+
+    fn assign(&mut self, name: String) {
+        let line = self.line;
+        let current = self.scope().id;
+        let in_method = self.in_method();
+        let global = self.globals.get(&name).copied();
+
+        let mut target = None;
+
+        for (i, block) in self.blocks.iter().enumerate().rev() {
+            let Block::Scope(scope) = block else {
+                continue;
+            };
+
+            if scope.variables.contains_key(&name) || scope.kind != ScopeKind::Block {
+                target = Some(i);
+                break;
+            }
+        }
+
+        let Some(Block::Scope(scope)) = target.and_then(|i| self.blocks.get_mut(i)) else {
+            return;
+        };
+
+        let kind = scope.kind;
+
+        match scope.variables.get_mut(&name) {
+            Some(variable) => {
+                if let Some((unread, id)) = variable.unread {
+                    if id == current {
+                        if kind == ScopeKind::File {
+                            self.overwritten.push((name.clone(), unread));
+                        } else {
+                            self.found.push((
+                                Rule::UnusedAssignment,
+                                unread,
+                                format!("The value assigned to `{}` is never read", name),
+                            ));
+                        }
+                    }
+                }
+
+                let Some(Block::Scope(scope)) = target.and_then(|i| self.blocks.get_mut(i)) else {
+                    return;
+                };
+
+                if let Some(variable) = scope.variables.get_mut(&name) {
+                    variable.unread = Some((line, current));
+                }
+            }
+            None => {
+                scope.variables.insert(
+                    name.clone(),
+                    Variable {
+                        line,
+                        kind: Kind::Assigned,
+                        read: false,
+                        unread: Some((line, current)),
+                    },
+                );
+
+                // Methods get their own variables, which hide the globals while they run.
+                if in_method && kind == ScopeKind::Method {
+                    if let Some(global) = global {
+                        self.shadows(&name, global);
+                    }
+                }
+            }
+        }
+    }
+
+    // Adds a parameter, binding or import to the innermost scope.
+    fn bind(&mut self, name: String, kind: Kind) {
+        if name == "_" {
+            return;
+        }
+
+        let line = self.line;
+        let outer = self.lookup(&name).or_else(|| {
+            self.in_method()
+                .then(|| self.globals.get(&name).copied())
+                .flatten()
+        });
+
+        self.scope().variables.insert(
+            name.clone(),
+            Variable {
+                line,
+                kind,
+                read: false,
+                unread: None,
+            },
+        );
+
+        if let Some(outer) = outer.filter(|_| name != "self") {
+            self.shadows(&name, outer);
+        }
+    }
+
+    fn shadows(&mut self, name: &str, line: usize) {
+        self.warn(
+            Rule::Shadowing,
+            self.line,
+            format!(
+                "`{}` shadows the variable defined on line {}",
+                name,
+                line + 1
+            ),
+        );
+    }
+
+    // The line a variable that is in scope was defined on.
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.blocks.iter().rev().find_map(|block| match block {
+            Block::Scope(scope) => scope.variables.get(name).map(|variable| variable.line),
+            _ => None,
+        })
+    }
+
+    fn read(&mut self, name: &str) {
+        if self.in_method() {
+            self.method_reads.insert(name.to_string());
+        }
+
+        for block in self.blocks.iter_mut().rev() {
+            if let Block::Scope(scope) = block {
+                if let Some(variable) = scope.variables.get_mut(name) {
+                    variable.read = true;
+                    variable.unread = None;
+                    return;
+                }
+            }
+        }
+    }
+
+    // Reads every variable in a value.
+    fn expression(&mut self, tokens: &[Token]) {
+        for (i, token) in tokens.iter().enumerate() {
+            match token.token_type {
+                TokenType::Identifier => {
+                    let is_member = i > 0 && is_operator(&tokens[i - 1], ".");
+                    let is_label = tokens.get(i + 1).is_some_and(|next| is_operator(next, ":"));
+
+                    if !is_member && !is_label && !token.value.ends_with('(') {
+                        self.read(&token.value);
+                    }
+                }
+                TokenType::Operator
+                    if matches!(token.value.as_str(), "==" | "!=" | "<" | ">" | "<=" | ">=") =>
+                {
+                    self.comparison(tokens, i);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Warns about `x == x` and the like, which is always the same.
+    fn comparison(&mut self, tokens: &[Token], i: usize) {
+        let left = path_before(tokens, i);
+        let right = path_after(tokens, i + 1);
+
+        if let (Some(left), Some(right)) = (left, right) {
+            if left == right {
+                self.warn(
+                    Rule::SelfComparison,
+                    self.line,
+                    format!(
+                        "`{}` is compared with itself using `{}`",
+                        left.join("."),
+                        tokens[i].value
+                    ),
+                );
+            }
+        }
+    }
+}
+
+impl Visitor for File {
+    fn inside(&self) -> Inside {
+        match self.blocks.last() {
+            Some(Block::Declaration) => Inside::Declaration,
+            Some(Block::Impl) => Inside::Impl,
+            Some(Block::Match) => Inside::Match,
+            _ => Inside::Code,
+        }
+    }
+
+    fn close(&mut self) {
+        // The file itself is never closed by a stray `}`.
+        if self.blocks.len() > 1 {
+            if let Some(Block::Scope(scope)) = self.blocks.pop() {
+                self.report(scope);
+            }
+        }
+    }
+
+    fn value(&mut self, tokens: &[Token], _offset: usize) {
+        self.expression(tokens);
+    }
+
+    fn declaration(&mut self, _tokens: &[Token], _offset: usize) {
+        self.blocks.push(Block::Declaration);
+    }
+
+    fn implementation(&mut self, _tokens: &[Token], _offset: usize) {
+        self.blocks.push(Block::Impl);
+    }
+
+    fn method(&mut self, tokens: &[Token], _offset: usize) {
+        self.open(ScopeKind::Method);
+
+        for parameter in tokens
+            .iter()
+            .skip(2)
+            .take_while(|token| !is_operator(token, ")"))
+        {
+            self.bind(
+                parameter.value.trim_end_matches("...").to_string(),
+                Kind::Parameter,
+            );
+        }
+    }
+
+    // Tests run on their own, so like methods they get their own variables.
+    fn test(&mut self, _tokens: &[Token], _offset: usize) {
+        self.open(ScopeKind::Method);
+    }
+
+    fn block(
+        &mut self,
+        _keyword: &'static str,
+        tokens: &[Token],
+        _offset: usize,
+        names: Range<usize>,
+    ) {
+        self.open(ScopeKind::Block);
+
+        for name in &tokens[names] {
+            self.bind(name.value.clone(), Kind::Binding);
+        }
+    }
+
+    // Milo has no `if`, so `match` is also how a condition is written, as in `match x > 0 {`.
+    fn start_match(&mut self, value: &[Token], _offset: usize) {
+        if is_constant(value) {
+            self.warn(
+                Rule::ConstantCondition,
+                self.line,
+                "This match value only uses literals, so it never changes".to_string(),
+            );
+        }
+
+        self.blocks.push(Block::Match);
+    }
+
+    fn arm(&mut self, _tokens: &[Token], _offset: usize, pattern: Option<&Pattern>) {
+        self.open(ScopeKind::Block);
+
+        for name in pattern.map(Pattern::names).unwrap_or_default() {
+            self.bind(name, Kind::Binding);
+        }
+    }
+
+    fn guard(&mut self, tokens: &[Token], _offset: usize) {
+        if is_constant(tokens) {
+            self.warn(
+                Rule::ConstantCondition,
+                self.line,
+                "This guard only uses literals, so it never changes".to_string(),
+            );
+        }
+
+        self.expression(tokens);
+    }
+
+    // Importing a file runs it and brings in its types, so only built-in modules can go unused.
+    fn import(&mut self, tokens: &[Token], _offset: usize) {
+        let builtin = tokens.get(1).is_some_and(|target| {
+            target.token_type == TokenType::Identifier && builtins::module(&target.value).is_some()
+        });
+
+        if let Some(name) = import_name(tokens).filter(|_| builtin) {
+            self.bind(name, Kind::Import);
+        }
+    }
+
+    fn target(
+        &mut self,
+        tokens: &[Token],
+        _offset: usize,
+        range: Range<usize>,
+        _value: Option<&[Token]>,
+    ) {
+        let target = &tokens[range];
+
+        // Assigning to a field or index reads the value it is on.
+        if target
+            .iter()
+            .any(|token| is_operator(token, ".") || is_operator(token, "[]"))
+        {
+            if let Some(name) = target.first() {
+                self.read(&name.value);
+            }
+
+            if let Some(index) = target.iter().position(|token| is_operator(token, "[]")) {
+                self.expression(&target[index + 1..]);
+            }
+            return;
+        }
+
+        for token in target {
+            if token.token_type == TokenType::Identifier {
+                self.assign(token.value.trim_end_matches("...").to_string());
+            }
+        }
+    }
+
+    // Warns about a statement that can't run, once per block.
+    fn reached(&mut self) {
+        let line = self.line;
+
+        if let Some((keyword, ended)) = self.scope().ended.take() {
+            self.warn(
+                Rule::UnreachableCode,
+                line,
+                format!("Unreachable code after `{}` on line {}", keyword, ended + 1),
+            );
+        }
+    }
+
+    // Marks the current block as ended by `keyword`.
+    fn ends(&mut self, keyword: &'static str) {
+        let line = self.line;
+        self.scope().ended = Some((keyword, line));
+    }
+}
+
+// Whether a value is made only of literals, e.g. `1 < 2`.
+fn is_constant(tokens: &[Token]) -> bool {
+    tokens.iter().any(|token| literal_type(token).is_some())
+        && tokens
+            .iter()
+            .all(|token| literal_type(token).is_some() || token.token_type == TokenType::Operator)
+}
+
+// A name or chain of fields right before `end`, e.g. `p.x`.
+fn path_before(tokens: &[Token], end: usize) -> Option<Vec<&str>> {
+    let mut path = vec![];
+    let mut i = end;
+
+    loop {
+        let name = tokens.get(i.checked_sub(1)?)?;
+
+        if name.token_type != TokenType::Identifier || name.value.ends_with('(') {
+            return None;
+        }
+
+        path.insert(0, name.value.as_str());
+        i -= 1;
+
+        match i.checked_sub(1).map(|dot| &tokens[dot]) {
+            Some(dot) if is_operator(dot, ".") => i -= 1,
+            _ => return Some(path),
+        }
+    }
+}
+
+// A name or chain of fields from `start` to the end of the value, e.g. `p.x`. Nothing if it is
+// only part of the value, as in `x == x + 1`.
+fn path_after(tokens: &[Token], start: usize) -> Option<Vec<&str>> {
+    let mut path = vec![];
+    let mut i = start;
+
+    loop {
+        let name = tokens.get(i)?;
+
+        if name.token_type != TokenType::Identifier || name.value.ends_with('(') {
+            return None;
+        }
+
+        path.push(name.value.as_str());
+        i += 1;
+
+        match tokens.get(i) {
+            Some(dot) if is_operator(dot, ".") => i += 1,
+            Some(next)
+                if is_binary_operator(next)
+                    || is_opening(next)
+                    || is_operator(next, "[]")
+                    || is_operator(next, "=") =>
+            {
+                return None
+            }
+            _ => return Some(path),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::{cmp::Ordering, fmt};
+
+use crate::lexer::{Token, TokenType};
+
+// The value of a number token. Whole numbers stay exact, anything with a decimal point is a float.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Number {
+    Whole(i64),
+    Decimal(f64),
+}
+
+impl Number {
+    pub fn parse(token: &Token) -> Option<Number> {
+        if token.token_type != TokenType::Number {
+            return None;
+        }
+
+        if let Ok(whole) = token.value.parse::<i64>() {
+            return Some(Number::Whole(whole));
+        }
+
+        token.value.parse::<f64>().ok().map(Number::Decimal)
+    }
+
+    pub fn as_f64(self) -> f64 {
+        match self {
+            Number::Whole(whole) => whole as f64,
+            Number::Decimal(decimal) => decimal,
+        }
+    }
+
+    pub fn to_token(self) -> Token {
+        Token {
+            token_type: TokenType::Number,
+            value: self.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn compare(self, other: Number) -> Option<Ordering> {
+        match (self, other) {
+            (Number::Whole(a), Number::Whole(b)) => Some(a.cmp(&b)),
+            (a, b) => a.as_f64().partial_cmp(&b.as_f64()),
+        }
+    }
+
+    // Applies `+`, `-`, `*` or `/`. Dividing two whole numbers rounds towards zero.
+    pub fn apply(self, operator: &str, other: Number) -> Result<Number, String> {
+        if let (Number::Whole(a), Number::Whole(b)) = (self, other) {
+            let result = match operator {
+                "+" => a.checked_add(b),
+                "-" => a.checked_sub(b),
+                "*" => a.checked_mul(b),
+                _ if b == 0 => return Err("Division by zero".to_string()),
+                _ => a.checked_div(b),
+            };
+
+            return result
+                .map(Number::Whole)
+                .ok_or_else(|| format!("`{}` overflowed", operator));
+        }
+
+        let (a, b) = (self.as_f64(), other.as_f64());
+
+        let result = match operator {
+            "+" => a + b,
+            "-" => a - b,
+            "*" => a * b,
+            _ if b == 0.0 => return Err("Division by zero".to_string()),
+            _ => a / b,
+        };
+
+        Number::decimal(result).ok_or_else(|| format!("`{}` overflowed", operator))
+    }
+
+    // A float, or nothing if it is infinite or not a number.
+    pub fn decimal(value: f64) -> Option<Number> {
+        value.is_finite().then_some(Number::Decimal(value))
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::Whole(whole) => write!(f, "{}", whole),
+            // Floats always have a decimal point, so they stay floats, e.g. `2.0`.
+            Number::Decimal(decimal) if decimal.fract() == 0.0 && decimal.abs() < 1e16 => {
+                write!(f, "{:.1}", decimal)
+            }
+            Number::Decimal(decimal) => write!(f, "{}", decimal),
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use std::ops::Range;
+
+use crate::{
+    lexer::{Token, TokenType},
+    patterns::Pattern,
+};
+
+use super::{
+    checker::{
+        after_opening, is_keyword, is_operator, is_test, loop_bounds, statement_end, top_level,
+    },
+    destructuring_targets, target_end,
+};
+
+// The kind of block a line is in, which decides what the line means.
+pub(super) enum Inside {
+    Declaration,
+    Impl,
+    Match,
+    Code,
+}
+
+// What the checker, the linter and the analysis do with the parts of a statement. `offset` is
+// where `tokens` starts on its line, for the analysis to find the columns of the tokens.
+pub(super) trait Visitor {
+    fn inside(&self) -> Inside;
+
+    // A `}` that closes the innermost block.
+    fn close(&mut self);
+
+    // A value that is read, e.g. the right side of an assignment or what is returned.
+    fn value(&mut self, tokens: &[Token], offset: usize);
+
+    // `struct Name {` or `enum Name {`, whose fields or variants are walked next.
+    fn declaration(&mut self, tokens: &[Token], offset: usize);
+
+    // `impl Name {`
+    fn implementation(&mut self, tokens: &[Token], offset: usize);
+
+    // `fn name(self, parameter, ...) {` in an `impl`.
+    fn method(&mut self, tokens: &[Token], offset: usize);
+
+    // `test "name" {`
+    fn test(&mut self, tokens: &[Token], offset: usize);
+
+    // A `for`, `try`, `catch` or `finally` block, which binds the names at `names`.
+    fn block(
+        &mut self,
+        keyword: &'static str,
+        tokens: &[Token],
+        offset: usize,
+        names: Range<usize>,
+    );
+
+    // `match value {`, once the value is read.
+    fn start_match(&mut self, value: &[Token], offset: usize);
+
+    // The pattern of a match arm, which starts a block of its own. Nothing if it can't be parsed.
+    fn arm(&mut self, tokens: &[Token], offset: usize, pattern: Option<&Pattern>);
+
+    // The condition after the `if` of a match arm.
+    fn guard(&mut self, tokens: &[Token], offset: usize) {
+        self.value(tokens, offset);
+    }
+
+    // import name, import "path.milo" or either with `as name`.
+    fn import(&mut self, tokens: &[Token], offset: usize);
+
+    // The target of an assignment at `range` in `tokens`, e.g. `a` or `p.x`, and the value
+    // assigned to it unless it is split between several targets.
+    fn target(
+        &mut self,
+        tokens: &[Token],
+        offset: usize,
+        range: Range<usize>,
+        value: Option<&[Token]>,
+    );
+
+    // Targets that can't be assigned to, e.g. `[a, 1] = xs`.
+    fn invalid_targets(&mut self, _targets: &[Token], _offset: usize, _message: String) {}
+
+    // The fields of a struct or variants of an enum, up to its `}`.
+    fn members(&mut self, _tokens: &[Token], _offset: usize) {}
+
+    // A keyword or operator at `i` that is part of the statement rather than of a value, e.g. the
+    // `in` of a `for` loop or the `=>` of a match arm.
+    fn syntax(&mut self, _tokens: &[Token], _offset: usize, _i: usize) {}
+
+    // A statement that runs after the one before it, unlike a `catch` or `finally` block.
+    fn reached(&mut self) {}
+
+    // The block is left by `keyword`, e.g. `return` or `exit()`.
+    fn ends(&mut self, _keyword: &'static str) {}
+}
+
+pub(super) fn statement(visitor: &mut impl Visitor, tokens: &[Token], offset: usize) {
+    let Some(first) = tokens.first() else {
+        return;
+    };
+
+    match visitor.inside() {
+        Inside::Declaration => {
+            let closing = tokens.iter().position(|token| is_operator(token, "}"));
+            visitor.members(&tokens[..closing.unwrap_or(tokens.len())], offset);
+
+            if let Some(closing) = closing {
+                visitor.close();
+                statement(visitor, &tokens[closing + 1..], offset + closing + 1);
+            }
+            return;
+        }
+        Inside::Impl if !is_operator(first, "}") => {
+            if is_keyword(first, "fn") {
+                visitor.syntax(tokens, offset, 0);
+                visitor.method(tokens, offset);
+                rest_of_block(visitor, tokens, offset);
+            }
+            return;
+        }
+        Inside::Match if !is_operator(first, "}") => {
+            arm(visitor, tokens, offset);
+            return;
+        }
+        _ => {}
+    }
+
+    if is_operator(first, "}") {
+        visitor.close();
+        statement(visitor, &tokens[1..], offset + 1);
+        return;
+    }
+
+    // Blocks that follow the one that just closed aren't statements of their own.
+    if !is_keyword(first, "catch") && !is_keyword(first, "finally") {
+        visitor.reached();
+    }
+
+    if first.token_type == TokenType::Identifier {
+        match first.value.as_str() {
+            "struct" | "enum" => {
+                visitor.syntax(tokens, offset, 0);
+                visitor.declaration(tokens, offset);
+                rest_of_block(visitor, tokens, offset);
+                return;
+            }
+            "impl" => {
+                visitor.syntax(tokens, offset, 0);
+                visitor.implementation(tokens, offset);
+                return;
+            }
+            "match" => {
+                visitor.syntax(tokens, offset, 0);
+                start_match(visitor, &tokens[1..], offset + 1);
+                return;
+            }
+            "return"
+                if tokens
+                    .get(1)
+                    .is_some_and(|token| is_keyword(token, "match")) =>
+            {
+                visitor.syntax(tokens, offset, 0);
+                visitor.syntax(tokens, offset, 1);
+                start_match(visitor, &tokens[2..], offset + 2);
+                visitor.ends("return");
+                return;
+            }
+            "return" | "throw" => {
+                let end = statement_end(tokens);
+                visitor.syntax(tokens, offset, 0);
+                visitor.value(&tokens[1..end], offset + 1);
+                visitor.ends(if first.value == "return" {
+                    "return"
+                } else {
+                    "throw"
+                });
+                statement(visitor, &tokens[end..], offset + end);
+                return;
+            }
+            "import" => {
+                visitor.syntax(tokens, offset, 0);
+                visitor.import(tokens, offset);
+                return;
+            }
+            "export" => {
+                visitor.syntax(tokens, offset, 0);
+                statement(visitor, &tokens[1..], offset + 1);
+                return;
+            }
+            "test" if is_test(tokens) => {
+                visitor.syntax(tokens, offset, 0);
+                visitor.test(tokens, offset);
+                rest_of_block(visitor, tokens, offset);
+                return;
+            }
+            "for" => {
+                let Some((in_index, opening)) = loop_bounds(tokens) else {
+                    return;
+                };
+
+                visitor.syntax(tokens, offset, 0);
+                visitor.syntax(tokens, offset, in_index);
+                visitor.value(&tokens[in_index + 1..opening], offset + in_index + 1);
+                visitor.block("for", tokens, offset, 1..in_index);
+                statement(visitor, &tokens[opening + 1..], offset + opening + 1);
+                return;
+            }
+            "try" | "finally" | "catch" => {
+                let keyword = match first.value.as_str() {
+                    "try" => "try",
+                    "finally" => "finally",
+                    _ => "catch",
+                };
+
+                // The name the error is caught as, if it has one.
+                let names = match tokens.get(1) {
+                    Some(name)
+                        if keyword == "catch" && name.token_type == TokenType::Identifier =>
+                    {
+                        1..2
+                    }
+                    _ => 0..0,
+                };
+
+                visitor.syntax(tokens, offset, 0);
+                visitor.block(keyword, tokens, offset, names);
+                rest_of_block(visitor, tokens, offset);
+                return;
+            }
+            _ => {}
+        }
+    }
+
+    // A `}` that isn't part of the expression closes a block, e.g. in `try { x } catch { y }`.
+    let end = statement_end(tokens);
+    assignment(visitor, &tokens[..end], offset);
+
+    if is_keyword(first, "exit(") {
+        visitor.ends("exit()");
+    }
+
+    statement(visitor, &tokens[end..], offset + end);
+}
+
+// Goes on with the statement after the first `{`.
+fn rest_of_block(visitor: &mut impl Visitor, tokens: &[Token], offset: usize) {
+    let rest = after_opening(tokens);
+    statement(visitor, rest, offset + tokens.len() - rest.len());
+}
+
+// match value {
+fn start_match(visitor: &mut impl Visitor, tokens: &[Token], offset: usize) {
+    let value = match tokens.split_last() {
+        Some((opening, value)) if is_operator(opening, "{") => value,
+        _ => tokens,
+    };
+
+    visitor.value(value, offset);
+    visitor.start_match(value, offset);
+}
+
+// pattern => value, pattern if guard => value, or pattern => { on its own.
+fn arm(visitor: &mut impl Visitor, tokens: &[Token], offset: usize) {
+    let Some(arrow) = tokens.iter().position(|token| is_operator(token, "=>")) else {
+        return;
+    };
+
+    let guard = tokens[..arrow]
+        .iter()
+        .position(|token| is_keyword(token, "if"));
+    let pattern = &tokens[..guard.unwrap_or(arrow)];
+
+    visitor.arm(pattern, offset, Pattern::parse(pattern).ok().as_ref());
+    visitor.syntax(tokens, offset, arrow);
+
+    if let Some(guard) = guard {
+        visitor.syntax(tokens, offset, guard);
+        visitor.guard(&tokens[guard + 1..arrow], offset + guard + 1);
+    }
+
+    let body = &tokens[arrow + 1..];
+
+    // A block stays open until its `}`.
+    if body.first().is_some_and(|token| is_operator(token, "{")) {
+        statement(visitor, &body[1..], offset + arrow + 2);
+        return;
+    }
+
+    statement(visitor, body, offset + arrow + 1);
+    visitor.close();
+}
+
+// name = value, a, b = value, p.x = value, xs[0] = value, or just a value.
+fn assignment(visitor: &mut impl Visitor, tokens: &[Token], offset: usize) {
+    let Some(equals) = top_level(tokens).find(|&i| is_operator(&tokens[i], "=")) else {
+        visitor.value(tokens, offset);
+        return;
+    };
+
+    visitor.syntax(tokens, offset, equals);
+
+    let (targets, value) = (&tokens[..equals], &tokens[equals + 1..]);
+
+    if value
+        .first()
+        .is_some_and(|token| is_keyword(token, "match"))
+    {
+        visitor.syntax(tokens, offset, equals + 1);
+        start_match(visitor, &value[1..], offset + equals + 2);
+    } else {
+        visitor.value(value, offset + equals + 1);
+    }
+
+    match destructuring_targets(targets) {
+        Some(Ok(starts)) => {
+            for start in starts {
+                let end = target_end(targets, start).unwrap_or(targets.len());
+                visitor.target(tokens, offset, start..end, None);
+            }
+        }
+        Some(Err(message)) => visitor.invalid_targets(targets, offset, message),
+        None => visitor.target(tokens, offset, 0..targets.len(), Some(value)),
+    }
+}
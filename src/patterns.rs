@@ -0,0 +1,336 @@
+use std::cmp::Ordering;
+
+use crate::{
+    lexer::{Token, TokenType},
+    parser::{compare_numbers, is_equal},
+};
+
+// The left side of a `match` arm, e.g. `Shape.Rect(w, h)` or `1..=9`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    // `_`, matches anything without binding it.
+    Wildcard,
+    // A name, matches anything and binds it to that name.
+    Binding(String),
+    Literal(Token),
+    // `1..5` or `1..=5`, where both ends are numbers.
+    Range {
+        start: Token,
+        end: Token,
+        inclusive: bool,
+    },
+    Tuple(Vec<Pattern>),
+    // `[a, b]`, or `[a, rest...]` where `rest` collects the remaining items.
+    List {
+        items: Vec<Pattern>,
+        rest: Option<String>,
+    },
+    // `Point { x: 0, y }`, where `y` is short for `y: y`.
+    Struct {
+        name: String,
+        fields: Vec<(String, Pattern)>,
+    },
+    // `Shape.Circle(r)` or `Shape.Empty`.
+    Variant {
+        enum_name: String,
+        variant: String,
+        payload: Vec<Pattern>,
+    },
+}
+
+impl Pattern {
+    pub fn parse(tokens: &[Token]) -> Result<Pattern, String> {
+        if tokens.is_empty() {
+            return Err("Expected a pattern".to_string());
+        }
+
+        let mut position = 0;
+        let pattern = parse_pattern(tokens, &mut position)?;
+
+        if let Some(token) = tokens.get(position) {
+            return Err(format!("Unexpected `{}` in pattern", token.value));
+        }
+
+        Ok(pattern)
+    }
+
+    // Whether `value` fits this pattern. The names bound along the way are added to `bindings`.
+    pub fn matches(&self, value: &Token, bindings: &mut Vec<(String, Token)>) -> bool {
+        match self {
+            Pattern::Wildcard => true,
+            Pattern::Binding(name) => {
+                bindings.push((name.clone(), value.clone()));
+                true
+            }
+            // Numbers are compared by value, like `==` and `<` do, so `1.0` matches `1`.
+            Pattern::Literal(literal) => is_equal(literal, value),
+            Pattern::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let (Some(from_start), Some(to_end)) =
+                    (compare_numbers(value, start), compare_numbers(value, end))
+                else {
+                    return false;
+                };
+
+                from_start != Ordering::Less
+                    && (to_end == Ordering::Less || *inclusive && to_end == Ordering::Equal)
+            }
+            Pattern::Tuple(items) => {
+                value.token_type == TokenType::Tuple && matches_all(items, &value.items, bindings)
+            }
+            Pattern::List { items, rest } => {
+                if value.token_type != TokenType::List
+                    || value.items.len() < items.len()
+                    || rest.is_none() && value.items.len() != items.len()
+                {
+                    return false;
+                }
+
+                if !matches_all(items, &value.items[..items.len()], bindings) {
+                    return false;
+                }
+
+                if let Some(rest) = rest {
+                    bindings.push((
+                        rest.clone(),
+                        Token {
+                            token_type: TokenType::List,
+                            items: value.items[items.len()..].to_vec(),
+                            ..Default::default()
+                        },
+                    ));
+                }
+
+                true
+            }
+            Pattern::Struct { name, fields } => {
+                if value.token_type != TokenType::Struct || value.value != *name {
+                    return false;
+                }
+
+                fields.iter().all(|(field, pattern)| {
+                    value
+                        .keys
+                        .iter()
+                        .position(|key| key.value == *field)
+                        .is_some_and(|position| pattern.matches(&value.items[position], bindings))
+                })
+            }
+            Pattern::Variant {
+                enum_name,
+                variant,
+                payload,
+            } => {
+                value.token_type == TokenType::Enum
+                    && value.value == format!("{}.{}", enum_name, variant)
+                    && matches_all(payload, &value.items, bindings)
+            }
+        }
+    }
+
+    // Whether this pattern matches every value.
+    pub fn is_irrefutable(&self) -> bool {
+        matches!(self, Pattern::Wildcard | Pattern::Binding(_))
+    }
+
+    // Calls `f` on this pattern and every pattern nested inside of it.
+    pub fn walk(&self, f: &mut impl FnMut(&Pattern)) {
+        f(self);
+
+        match self {
+            Pattern::Tuple(items) | Pattern::List { items, .. } => {
+                items.iter().for_each(|item| item.walk(f))
+            }
+            Pattern::Struct { fields, .. } => fields.iter().for_each(|(_, field)| field.walk(f)),
+            Pattern::Variant { payload, .. } => payload.iter().for_each(|item| item.walk(f)),
+            _ => {}
+        }
+    }
+
+    // The names this pattern binds, e.g. `w` and `h` in `Shape.Rect(w, h)`.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = vec![];
+
+        self.walk(&mut |pattern| match pattern {
+            Pattern::Binding(name) => names.push(name.clone()),
+            Pattern::List {
+                rest: Some(rest), ..
+            } => names.push(rest.clone()),
+            _ => {}
+        });
+
+        names
+    }
+}
+
+fn matches_all(
+    patterns: &[Pattern],
+    values: &[Token],
+    bindings: &mut Vec<(String, Token)>,
+) -> bool {
+    patterns.len() == values.len()
+        && patterns
+            .iter()
+            .zip(values)
+            .all(|(pattern, value)| pattern.matches(value, bindings))
+}
+
+fn is_operator(token: Option<&Token>, operator: &str) -> bool {
+    token.is_some_and(|token| token.token_type == TokenType::Operator && token.value == operator)
+}
+
+fn parse_pattern(tokens: &[Token], position: &mut usize) -> Result<Pattern, String> {
+    let Some(token) = tokens.get(*position) else {
+        return Err("Expected a pattern, got nothing".to_string());
+    };
+
+    *position += 1;
+
+    match token.token_type {
+        TokenType::Operator if token.value == "(" => {
+            let mut items = parse_until(tokens, position, ")")?;
+
+            // A single pattern in parentheses is just that pattern, like with values.
+            if items.len() == 1 {
+                Ok(items.remove(0))
+            } else {
+                Ok(Pattern::Tuple(items))
+            }
+        }
+        TokenType::Operator if token.value == "[" => {
+            let mut items = parse_until(tokens, position, "]")?;
+
+            let rest = match items.last() {
+                Some(Pattern::Binding(name)) if name.ends_with("...") => {
+                    let name = name.trim_end_matches("...").to_string();
+                    items.pop();
+                    Some(name)
+                }
+                _ => None,
+            };
+
+            if items
+                .iter()
+                .any(|item| matches!(item, Pattern::Binding(name) if name.ends_with("...")))
+            {
+                return Err("Only the last name can collect the rest of the values".to_string());
+            }
+
+            Ok(Pattern::List { items, rest })
+        }
+        TokenType::Number
+            if is_operator(tokens.get(*position), "..")
+                || is_operator(tokens.get(*position), "..=") =>
+        {
+            let inclusive = tokens[*position].value == "..=";
+            *position += 1;
+
+            let end = match tokens.get(*position) {
+                Some(end) if end.token_type == TokenType::Number => end,
+                _ => return Err("A range pattern expected a number after `..`".to_string()),
+            };
+
+            *position += 1;
+
+            Ok(Pattern::Range {
+                start: token.clone(),
+                end: end.clone(),
+                inclusive,
+            })
+        }
+        TokenType::Number | TokenType::String | TokenType::Boolean | TokenType::Nil => {
+            Ok(Pattern::Literal(token.clone()))
+        }
+        TokenType::Identifier if token.value.ends_with('(') => {
+            Err(format!("Unexpected `{}` in pattern", token.value))
+        }
+        TokenType::Identifier if is_operator(tokens.get(*position), ".") => {
+            *position += 1;
+
+            let Some(variant) = tokens.get(*position) else {
+                return Err(format!("Expected a variant after `{}.`", token.value));
+            };
+
+            *position += 1;
+
+            // `Shape.Circle(r)` is lexed like a method call, `Shape.Empty` like a field.
+            let (variant, payload) = match variant.value.strip_suffix('(') {
+                Some(name) => (name.to_string(), parse_until(tokens, position, ")")?),
+                None => (variant.value.clone(), vec![]),
+            };
+
+            Ok(Pattern::Variant {
+                enum_name: token.value.clone(),
+                variant,
+                payload,
+            })
+        }
+        TokenType::Identifier if is_operator(tokens.get(*position), "{") => {
+            *position += 1;
+
+            let mut fields = vec![];
+
+            loop {
+                let Some(field) = tokens.get(*position) else {
+                    return Err(format!(
+                        "`{}` pattern is missing a closing `}}`",
+                        token.value
+                    ));
+                };
+
+                *position += 1;
+
+                if field.token_type == TokenType::Operator && field.value == "}" {
+                    break;
+                }
+
+                if field.token_type != TokenType::Identifier {
+                    return Err(format!("Expected a field name, got: `{}`", field.value));
+                }
+
+                // `y` is short for `y: y`.
+                let pattern = if is_operator(tokens.get(*position), ":") {
+                    *position += 1;
+                    parse_pattern(tokens, position)?
+                } else {
+                    Pattern::Binding(field.value.clone())
+                };
+
+                fields.push((field.value.clone(), pattern));
+            }
+
+            Ok(Pattern::Struct {
+                name: token.value.clone(),
+                fields,
+            })
+        }
+        TokenType::Identifier if token.value == "_" => Ok(Pattern::Wildcard),
+        TokenType::Identifier => Ok(Pattern::Binding(token.value.clone())),
+        _ => Err(format!("Unexpected `{}` in pattern", token.value)),
+    }
+}
+
+// Parses patterns up to and including `closing`.
+fn parse_until(
+    tokens: &[Token],
+    position: &mut usize,
+    closing: &str,
+) -> Result<Vec<Pattern>, String> {
+    let mut items = vec![];
+
+    loop {
+        if is_operator(tokens.get(*position), closing) {
+            *position += 1;
+            return Ok(items);
+        }
+
+        if *position >= tokens.len() {
+            return Err(format!("Pattern is missing a closing `{}`", closing));
+        }
+
+        items.push(parse_pattern(tokens, position)?);
+    }
+}
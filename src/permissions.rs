@@ -0,0 +1,155 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+// How many symlinks in a row are followed, like the limit of most systems.
+const MAX_LINKS: usize = 40;
+
+// Something a script can only do when it is allowed to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Permission {
+    Read,
+    Write,
+    Env,
+    Run,
+    Clock,
+}
+
+impl Permission {
+    // The command line flag that grants this permission.
+    pub fn flag(self) -> &'static str {
+        match self {
+            Permission::Read => "--allow-read",
+            Permission::Write => "--allow-write",
+            Permission::Env => "--allow-env",
+            Permission::Run => "--allow-run",
+            Permission::Clock => "--allow-clock",
+        }
+    }
+
+    // The error a script gets when it does something it isn't allowed to.
+    pub fn denied(self) -> String {
+        format!("permission denied: requires {}", self.flag())
+    }
+}
+
+// What a script is allowed to do outside of the interpreter. Everything is denied by default, and
+// the `--allow-*` flags grant the rest.
+#[derive(Debug, Clone, Default)]
+pub struct Permissions {
+    // The directories files can be read from.
+    read: Vec<PathBuf>,
+    // The directories files can be written to.
+    write: Vec<PathBuf>,
+    env: bool,
+    run: bool,
+    clock: bool,
+}
+
+impl Permissions {
+    // Allows everything, like `--allow-all`.
+    pub fn all() -> Permissions {
+        let mut permissions = Permissions::default();
+
+        for permission in [
+            Permission::Read,
+            Permission::Write,
+            Permission::Env,
+            Permission::Run,
+            Permission::Clock,
+        ] {
+            permissions.allow(permission);
+        }
+
+        permissions
+    }
+
+    // Allows a permission everywhere. `allow_read` and `allow_write` allow only some directories.
+    pub fn allow(&mut self, permission: Permission) -> &mut Permissions {
+        match permission {
+            Permission::Read => self.allow_read("/"),
+            Permission::Write => self.allow_write("/"),
+            Permission::Env => {
+                self.env = true;
+                self
+            }
+            Permission::Run => {
+                self.run = true;
+                self
+            }
+            Permission::Clock => {
+                self.clock = true;
+                self
+            }
+        }
+    }
+
+    pub fn allow_read(&mut self, directory: impl Into<PathBuf>) -> &mut Permissions {
+        self.read.push(directory.into());
+        self
+    }
+
+    pub fn allow_write(&mut self, directory: impl Into<PathBuf>) -> &mut Permissions {
+        self.write.push(directory.into());
+        self
+    }
+
+    // Whether a permission was granted. For files, whether any directory was allowed.
+    pub fn allows(&self, permission: Permission) -> bool {
+        match permission {
+            Permission::Read => !self.read.is_empty(),
+            Permission::Write => !self.write.is_empty(),
+            Permission::Env => self.env,
+            Permission::Run => self.run,
+            Permission::Clock => self.clock,
+        }
+    }
+
+    pub fn can_read(&self, path: &Path) -> bool {
+        is_inside(&self.read, path)
+    }
+
+    pub fn can_write(&self, path: &Path) -> bool {
+        is_inside(&self.write, path)
+    }
+}
+
+// Whether `path` is inside one of `directories`. Paths that don't exist yet are checked through their parent.
+fn is_inside(directories: &[PathBuf], path: &Path) -> bool {
+    let Some(path) = resolve(path) else {
+        return false;
+    };
+
+    directories
+        .iter()
+        .filter_map(|directory| directory.canonicalize().ok())
+        .any(|directory| path.starts_with(directory))
+}
+
+// The absolute path `path` points to, with every `..` and symlink resolved. A symlink to something
+// that doesn't exist yet resolves to where its target would be, since writing to it creates that.
+fn resolve(path: &Path) -> Option<PathBuf> {
+    resolve_links(path, 0)
+}
+
+fn resolve_links(path: &Path, links: usize) -> Option<PathBuf> {
+    if let Ok(path) = path.canonicalize() {
+        return Some(path);
+    }
+
+    let path = env::current_dir().ok()?.join(path);
+    let parent = path.parent()?.canonicalize().ok()?;
+    let path = parent.join(path.file_name()?);
+
+    if !path.is_symlink() {
+        return Some(path);
+    }
+
+    // Links that point at each other never resolve.
+    if links >= MAX_LINKS {
+        return None;
+    }
+
+    resolve_links(&parent.join(fs::read_link(&path).ok()?), links + 1)
+}
@@ -0,0 +1,154 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+// Runs `milo` with `command` on `code` as a file of its own.
+fn milo(command: &str, name: &str, code: &str) -> Output {
+    let directory = env::temp_dir().join(format!(
+        "milo_check_{}_{}_{}",
+        command,
+        name,
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+
+    let file: PathBuf = directory.join("script.milo");
+    fs::write(&file, code).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_milo"))
+        .args(command.split_whitespace())
+        .arg(&file)
+        .output()
+        .expect("milo should run");
+
+    fs::remove_dir_all(directory).unwrap();
+    output
+}
+
+// The errors, one per line, without the colors and the file name.
+fn errors(output: &Output) -> Vec<String> {
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .map(|line| {
+            let line = line
+                .trim_start_matches("\x1b[31m")
+                .trim_end_matches("\x1b[0m");
+            line.split(" in ").next().unwrap_or(line).to_string()
+        })
+        .collect()
+}
+
+// Checks the code, and makes sure running it agrees about whether it works.
+fn check(name: &str, code: &str) -> Vec<String> {
+    let checked = milo("check", name, code);
+    let ran = milo("run", name, code);
+
+    assert_eq!(
+        checked.status.success(),
+        ran.status.success(),
+        "`check` and `run` disagree about:\n{}",
+        code
+    );
+
+    errors(&checked)
+}
+
+#[test]
+fn unclosed_brackets() {
+    assert_eq!(
+        check("list", "x = [1, 2\nprint(x)\n"),
+        ["ERROR: `[` is missing a closing `]`. Error occurred on line 1"]
+    );
+    assert_eq!(
+        check("call", "print([1 2)]\n"),
+        ["ERROR: `print` is missing a closing `)`. Error occurred on line 1"]
+    );
+}
+
+#[test]
+fn unmatched_closing_brackets() {
+    assert_eq!(
+        check("parentheses", "print(1)))]\n"),
+        ["ERROR: `)` has no opening `(`. Error occurred on line 1"]
+    );
+    assert_eq!(
+        check("brace", "x = 1 }\n"),
+        ["ERROR: `}` has no opening `{`. Error occurred on line 1"]
+    );
+    assert_eq!(
+        check("lone", "print(1)\n}\n"),
+        ["ERROR: `}` has no opening `{`. Error occurred on line 2"]
+    );
+}
+
+#[test]
+fn unclosed_blocks() {
+    assert_eq!(
+        check("try", "try {\n    print(1)\n"),
+        ["ERROR: `try` is missing a closing `}`. Error occurred on line 1"]
+    );
+    assert_eq!(
+        check("struct", "struct P { x\n"),
+        ["ERROR: `struct` is missing a closing `}`. Error occurred on line 1"]
+    );
+    assert_eq!(
+        check(
+            "method",
+            "struct P { x }\n\nimpl P {\n    fn f(self) {\n        return self.x\n"
+        ),
+        ["ERROR: `fn` is missing a closing `}`. Error occurred on line 4"]
+    );
+}
+
+#[test]
+fn lists_with_operators() {
+    assert_eq!(
+        check("add", "x = [1] + [2]\nprint(x)\n"),
+        ["ERROR: `+` expected a string or number, got: `[1]`. Error occurred on line 1"]
+    );
+    assert_eq!(
+        check("subtract", "print([1, 2] - 1)\n"),
+        ["ERROR: `-` expected a number, got: `[1, 2]`. Error occurred on line 1"]
+    );
+    assert!(check("index", "print([1, 2][0] + 1)\n").is_empty());
+}
+
+#[test]
+fn literals_with_operators() {
+    assert_eq!(
+        check("string", "print(1 - \"a\")\n"),
+        ["ERROR: `-` expected a number, got: `\"a\"`. Error occurred on line 1"]
+    );
+    assert_eq!(
+        check("boolean", "print(true && 1)\n"),
+        ["ERROR: `&&` expected a boolean, got: `1`. Error occurred on line 1"]
+    );
+}
+
+#[test]
+fn code_that_runs_is_clean() {
+    let code = "\
+struct P { x }
+
+impl P {
+    fn f(self) { return self.x }
+}
+
+print(1 + 1 == 2)
+print(P { x: 1 }.f())
+";
+
+    assert!(check("clean", code).is_empty());
+}
+
+#[test]
+fn destructuring_into_fields_and_indices() {
+    assert!(check("places", "x = [1, 2]\na, x[0] = 1, 2\nprint(a)\n").is_empty());
+    assert_eq!(
+        check("call", "a, len(a) = 1, 2\n"),
+        ["ERROR: `=` can only destructure into names, fields and indices. Error occurred on line 1"]
+    );
+}
@@ -0,0 +1,28 @@
+#![cfg(unix)]
+
+use std::{env, ffi::OsStr, fs, os::unix::ffi::OsStrExt, process::Command};
+
+#[test]
+fn variables_that_arent_utf8_are_read_lossily() {
+    let file = env::temp_dir().join(format!("milo_env_{}.milo", std::process::id()));
+    fs::write(
+        &file,
+        "print(env(\"BAD\"))\nvariables = env_vars()\nprint(variables[\"BAD\"])\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_milo"))
+        .arg("--allow-env")
+        .arg(&file)
+        .env("BAD", OsStr::from_bytes(b"a\xffb"))
+        .output()
+        .expect("milo should run");
+
+    fs::remove_file(file).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "a\u{fffd}b\na\u{fffd}b\n"
+    );
+}
@@ -0,0 +1,151 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+// Formats `code` as a file of its own, and returns what `milo fmt` printed and left in the file.
+fn fmt(name: &str, code: &str, arguments: &[&str]) -> (Output, String) {
+    let directory = env::temp_dir().join(format!("milo_fmt_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+
+    let file: PathBuf = directory.join("script.milo");
+    fs::write(&file, code).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_milo"))
+        .arg("fmt")
+        .arg(&file)
+        .args(arguments)
+        .output()
+        .expect("milo should run");
+
+    let formatted = fs::read_to_string(&file).unwrap();
+    fs::remove_dir_all(directory).unwrap();
+    (output, formatted)
+}
+
+#[test]
+fn formats_spacing_and_indentation() {
+    let code = "\
+x=[1,2 ,3]
+match x[0]==1 {
+true => print( x )
+false=>print(\"no\")
+}
+point = {\"x\":1, \"y\" : 2}
+";
+    let (output, formatted) = fmt("spacing", code, &[]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        formatted,
+        "\
+x = [1, 2, 3]
+match x[0] == 1 {
+    true => print(x)
+    false => print(\"no\")
+}
+point = {\"x\": 1, \"y\": 2}
+"
+    );
+}
+
+#[test]
+fn formatting_twice_changes_nothing() {
+    let code = "\
+a=1;b=2 // both
+struct Adder{total}
+impl Adder{
+  fn add(self,x,y){
+  return self.total+x+y
+  }
+}
+
+
+print(Adder{total:0}.add(a,b))
+";
+    let (_, once) = fmt("once", code, &[]);
+    let (_, twice) = fmt("twice", &once, &[]);
+
+    assert_eq!(
+        once,
+        "\
+a = 1
+b = 2 // both
+struct Adder { total }
+impl Adder {
+    fn add(self, x, y) {
+        return self.total + x + y
+    }
+}
+
+print(Adder { total: 0 }.add(a, b))
+"
+    );
+    assert_eq!(once, twice);
+}
+
+#[test]
+fn comments_are_kept_as_written() {
+    let code = "\
+hello = \"Hello\" //, world!\";
+# A hash comment
+// Spacing   inside   comments stays
+print(hello)   // after code
+x = 1 // one; two
+";
+    let (_, formatted) = fmt("comments", code, &[]);
+
+    assert_eq!(
+        formatted,
+        "\
+hello = \"Hello\" //, world!\";
+// A hash comment
+// Spacing   inside   comments stays
+print(hello)   // after code
+x = 1 // one; two
+"
+    );
+}
+
+#[test]
+fn statements_after_semicolons_get_their_own_line() {
+    let (_, formatted) = fmt("semicolons", "a = 1; b = 2; // both\nprint(a + b)\n", &[]);
+
+    assert_eq!(formatted, "a = 1\nb = 2  // both\nprint(a + b)\n");
+}
+
+#[test]
+fn semicolons_in_strings_and_comments_stay_where_they_are() {
+    let (output, formatted) = fmt(
+        "quoted",
+        "parts = split(\"a;b\", \";\"); print(parts) // one; two\n",
+        &[],
+    );
+
+    assert!(output.status.success());
+    assert_eq!(
+        formatted,
+        "parts = split(\"a;b\", \";\")\nprint(parts) // one; two\n"
+    );
+}
+
+#[test]
+fn check_reports_unformatted_code_without_changing_it() {
+    let (output, formatted) = fmt("check", "x=1\n", &["--check"]);
+
+    assert!(!output.status.success());
+    assert_eq!(formatted, "x=1\n");
+
+    let (output, _) = fmt("check_formatted", "x = 1\n", &["--check"]);
+
+    assert!(output.status.success());
+}
+
+#[test]
+fn minus_after_a_value_is_spaced_as_subtraction() {
+    let (_, formatted) = fmt("minus", "x=5\nprint(x-1)\nprint([1,-2])\n", &[]);
+
+    assert_eq!(formatted, "x = 5\nprint(x - 1)\nprint([1, -2])\n");
+}
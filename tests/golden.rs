@@ -0,0 +1,152 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+// Runs `milo` from the root of the repository, where the paths in the expected output start.
+fn milo(arguments: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_milo"))
+        .args(arguments)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env_remove("MILO_DEBUG")
+        .output()
+        .expect("milo should run")
+}
+
+// A directory of its own for a test, removed first in case an earlier run left it behind.
+fn directory(name: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("milo_golden_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn examples_print_what_they_should() {
+    let output = milo(&["test", "--golden", "examples"]);
+
+    assert!(output.status.success(), "{}", stdout(&output));
+}
+
+#[test]
+fn example_tests_pass() {
+    let output = milo(&["test", "examples/testing.milo"]);
+
+    assert!(output.status.success(), "{}", stdout(&output));
+}
+
+#[test]
+fn wrong_annotation_fails() {
+    let directory = directory("annotation");
+    let script = directory.join("wrong.milo");
+    fs::write(&script, "print(1 + 2) // Should print \"4\"\n").unwrap();
+
+    let output = milo(&["test", "--golden", script.to_str().unwrap()]);
+    let stdout = stdout(&output);
+
+    assert!(!output.status.success());
+    assert!(stdout.contains("- 4\n+ 3"), "{}", stdout);
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn update_writes_expected_output() {
+    let directory = directory("update");
+    let script = directory.join("script.milo");
+    fs::write(&script, "print(\"Hello\")\n").unwrap();
+    let script = script.to_str().unwrap();
+
+    assert!(!milo(&["test", "--golden", script]).status.success());
+    assert!(milo(&["test", "--golden", "--update", script])
+        .status
+        .success());
+
+    assert_eq!(
+        fs::read_to_string(directory.join("script.out")).unwrap(),
+        "Hello\n"
+    );
+    assert!(!directory.join("script.err").exists());
+    assert!(milo(&["test", "--golden", script]).status.success());
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn run_with_comment_gives_flags_arguments_and_stdin() {
+    let directory = directory("run_with");
+    let script = directory.join("script.milo");
+    fs::write(
+        &script,
+        "\
+// Run with `printf \"Ada\\nLovelace\\n\" | GREETING=Hi milo --allow-env script.milo one two`.
+print(args()) // Should print \"[\"one\", \"two\"]\"
+print(env(\"GREETING\")) // Should print \"Hi\"
+print(read_lines()) // Should print \"[\"Ada\", \"Lovelace\"]\"
+",
+    )
+    .unwrap();
+
+    let output = milo(&["test", "--golden", script.to_str().unwrap()]);
+
+    assert!(output.status.success(), "{}", stdout(&output));
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn comment_starts_outside_of_strings() {
+    let directory = directory("strings");
+    let script = directory.join("script.milo");
+    fs::write(
+        &script,
+        "print(\"http://example.com\") // Should print \"http://example.com\"\n",
+    )
+    .unwrap();
+
+    let output = milo(&["test", "--golden", script.to_str().unwrap()]);
+
+    assert!(output.status.success(), "{}", stdout(&output));
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn failures_are_never_snapshotted() {
+    let directory = directory("failure");
+    let script = directory.join("script.milo");
+    fs::write(&script, "print(\"before\")\nprint(oops)\n").unwrap();
+    let script = script.to_str().unwrap();
+
+    let output = milo(&["test", "--golden", "--update", script]);
+
+    assert!(!output.status.success());
+    assert!(
+        stdout(&output).contains("exited with code 1"),
+        "{}",
+        stdout(&output)
+    );
+    assert!(!directory.join("script.out").exists());
+    assert!(!directory.join("script.err").exists());
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn skip_comment_skips_a_script() {
+    let directory = directory("skip");
+    let script = directory.join("script.milo");
+    fs::write(&script, "// Skip: it can't run yet\nprint(oops)\n").unwrap();
+
+    let output = milo(&["test", "--golden", script.to_str().unwrap()]);
+
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("1 skipped"), "{}", stdout(&output));
+
+    fs::remove_dir_all(directory).unwrap();
+}
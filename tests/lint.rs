@@ -0,0 +1,200 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+// Lints `code` as a file of its own, with any extra arguments after the file.
+fn lint(name: &str, code: &str, arguments: &[&str]) -> Output {
+    let directory = env::temp_dir().join(format!("milo_lint_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+
+    let file: PathBuf = directory.join("script.milo");
+    fs::write(&file, code).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_milo"))
+        .arg("lint")
+        .arg(&file)
+        .args(arguments)
+        .output()
+        .expect("milo should run");
+
+    fs::remove_dir_all(directory).unwrap();
+    output
+}
+
+// The warnings, one per line, without the colors and the file name.
+fn warnings(output: &Output) -> Vec<String> {
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .map(|line| {
+            let line = line
+                .trim_start_matches("\x1b[33m")
+                .trim_start_matches("\x1b[31m");
+            let line = line.trim_end_matches("\x1b[0m");
+            line.split(" in ").next().unwrap_or(line).to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn variable_that_is_never_read_is_unused() {
+    let output = lint("unused_variable", "a = 1\n", &[]);
+
+    assert_eq!(
+        warnings(&output),
+        ["WARNING: `a` is assigned but never read (unused-variable). Warning occurred on line 1"]
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn value_replaced_before_it_is_read_is_an_unused_assignment() {
+    let output = lint("unused_assignment", "a = 1\na = 2\nprint(a)\n", &[]);
+
+    assert_eq!(
+        warnings(&output),
+        ["WARNING: The value assigned to `a` is never read (unused-assignment). Warning occurred on line 1"]
+    );
+}
+
+#[test]
+fn unused_parameter_and_binding() {
+    let code = "\
+struct P { x }
+
+impl P {
+    fn f(self, unused) {
+        return self.x
+    }
+}
+
+p = P { x: 1 }
+print(p.f(2))
+
+for i, item in [1] {
+    print(item)
+}
+";
+    let output = lint("unused_parameter", code, &[]);
+
+    assert_eq!(
+        warnings(&output),
+        [
+            "WARNING: `unused` is never used (unused-variable). Warning occurred on line 4",
+            "WARNING: `i` is never used (unused-variable). Warning occurred on line 12",
+        ]
+    );
+}
+
+#[test]
+fn code_after_throw_is_unreachable() {
+    let code = "\
+try {
+    throw \"oops\"
+    print(1)
+} catch error {
+    print(error)
+}
+";
+    let output = lint("unreachable", code, &[]);
+
+    assert_eq!(
+        warnings(&output),
+        ["WARNING: Unreachable code after `throw` on line 2 (unreachable-code). Warning occurred on line 3"]
+    );
+}
+
+#[test]
+fn self_comparison_and_constant_guard() {
+    let code = "\
+x = 1
+print(x == x)
+
+group = match x {
+    n if 1 < 2 => n
+    _ => 0
+}
+print(group)
+";
+    let output = lint("comparison", code, &[]);
+
+    assert_eq!(
+        warnings(&output),
+        [
+            "WARNING: `x` is compared with itself using `==` (self-comparison). Warning occurred on line 2",
+            "WARNING: This guard only uses literals, so it never changes (constant-condition). Warning occurred on line 5",
+        ]
+    );
+}
+
+#[test]
+fn constant_match_value() {
+    let code = "\
+x = 1
+match 1 == 1 {
+    true => print(x)
+    _ => print(0)
+}
+match x > 0 {
+    true => print(x)
+    _ => print(0)
+}
+";
+    let output = lint("constant_match", code, &[]);
+
+    assert_eq!(
+        warnings(&output),
+        ["WARNING: This match value only uses literals, so it never changes (constant-condition). Warning occurred on line 2"]
+    );
+}
+
+#[test]
+fn binding_that_hides_a_variable_shadows_it() {
+    let code = "\
+value = 1
+result = match value + 1 {
+    value => value
+}
+print(result + value)
+";
+    let output = lint("shadowing", code, &[]);
+
+    assert_eq!(
+        warnings(&output),
+        ["WARNING: `value` shadows the variable defined on line 1 (shadowing). Warning occurred on line 3"]
+    );
+}
+
+#[test]
+fn comment_allows_a_rule() {
+    let code = "\
+a = 1 // milo: allow(unused-variable)
+// milo: allow(unused-variable)
+b = 2
+c = 3
+";
+    let output = lint("allow", code, &[]);
+
+    assert_eq!(
+        warnings(&output),
+        ["WARNING: `c` is assigned but never read (unused-variable). Warning occurred on line 4"]
+    );
+}
+
+#[test]
+fn denied_rule_fails() {
+    let output = lint("deny", "a = 1\n", &["--deny", "unused-variable"]);
+
+    assert_eq!(
+        warnings(&output),
+        ["ERROR: `a` is assigned but never read (unused-variable). Error occurred on line 1"]
+    );
+    assert!(!output.status.success());
+
+    let output = lint("allow_flag", "a = 1\n", &["--allow", "unused-variable"]);
+
+    assert!(warnings(&output).is_empty());
+    assert!(output.status.success());
+}
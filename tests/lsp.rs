@@ -0,0 +1,221 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use serde_json::{json, Value};
+
+const URI: &str = "file:///tmp/script.milo";
+
+const CODE: &str = "\
+struct Point { x, y }
+
+origin = Point { x: 0, y: 0 }
+print(origin.x)
+";
+
+// Sends the messages to `milo lsp` as one session, and returns the exit code and every message
+// it sent back.
+fn session(messages: &[Value]) -> (Option<i32>, Vec<Value>) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_milo"))
+        .arg("lsp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("milo should run");
+
+    let mut stdin = child.stdin.take().unwrap();
+
+    for message in messages {
+        let body = message.to_string();
+        write!(stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+    }
+
+    drop(stdin);
+
+    let output = child.wait_with_output().unwrap();
+    let mut stdout = String::from_utf8(output.stdout).unwrap();
+    let mut received = vec![];
+
+    while let Some((header, rest)) = stdout.split_once("\r\n\r\n") {
+        let length: usize = header
+            .strip_prefix("Content-Length: ")
+            .unwrap()
+            .parse()
+            .unwrap();
+
+        received.push(serde_json::from_str(&rest[..length]).unwrap());
+        stdout = rest[length..].to_string();
+    }
+
+    (output.status.code(), received)
+}
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+fn position(line: u64, character: u64) -> Value {
+    json!({ "textDocument": { "uri": URI }, "position": { "line": line, "character": character } })
+}
+
+// The response to the request with the id.
+fn response(received: &[Value], id: u64) -> &Value {
+    received
+        .iter()
+        .find(|message| message["id"] == id)
+        .unwrap_or_else(|| panic!("no response to request {}", id))
+}
+
+#[test]
+fn answers_requests_about_an_open_document() {
+    let mut rename = position(2, 0);
+    rename["newName"] = json!("start");
+
+    let (code, received) = session(&[
+        request(1, "initialize", json!({ "capabilities": {} })),
+        notification("initialized", json!({})),
+        notification(
+            "textDocument/didOpen",
+            json!({ "textDocument": { "uri": URI, "languageId": "milo", "version": 1, "text": CODE } }),
+        ),
+        request(2, "textDocument/hover", position(3, 8)),
+        request(3, "textDocument/definition", position(3, 8)),
+        request(4, "textDocument/rename", rename),
+        request(5, "textDocument/completion", position(3, 0)),
+        request(6, "shutdown", Value::Null),
+        notification("exit", Value::Null),
+    ]);
+
+    assert_eq!(code, Some(0));
+
+    let capabilities = &response(&received, 1)["result"]["capabilities"];
+    assert_eq!(capabilities["hoverProvider"], true);
+    assert_eq!(capabilities["renameProvider"], true);
+
+    let diagnostics = received
+        .iter()
+        .find(|message| message["method"] == "textDocument/publishDiagnostics")
+        .expect("diagnostics should be published when a document is opened");
+    assert_eq!(diagnostics["params"]["uri"], URI);
+    assert_eq!(diagnostics["params"]["diagnostics"], json!([]));
+
+    let hover = &response(&received, 2)["result"];
+    assert!(
+        hover["contents"]["value"]
+            .as_str()
+            .unwrap()
+            .contains("origin"),
+        "{}",
+        hover
+    );
+
+    let definition = &response(&received, 3)["result"];
+    assert_eq!(
+        definition,
+        &json!([{
+            "uri": URI,
+            "range": {
+                "start": { "line": 2, "character": 0 },
+                "end": { "line": 2, "character": 6 },
+            },
+        }])
+    );
+
+    let edits = response(&received, 4)["result"]["changes"][URI]
+        .as_array()
+        .unwrap();
+    assert_eq!(edits.len(), 2);
+    assert!(edits.iter().all(|edit| edit["newText"] == "start"));
+
+    let labels: Vec<&str> = response(&received, 5)["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect();
+    assert!(labels.contains(&"origin"));
+    assert!(labels.contains(&"Point"));
+    assert!(labels.contains(&"print"));
+    assert!(!labels.contains(&"if"));
+    assert!(!labels.contains(&"else"));
+
+    assert_eq!(response(&received, 6)["result"], Value::Null);
+}
+
+#[test]
+fn rename_rejects_keywords_but_not_if() {
+    let mut keyword = position(2, 0);
+    keyword["newName"] = json!("match");
+
+    let mut name = position(2, 0);
+    name["newName"] = json!("if");
+
+    let (_, received) = session(&[
+        request(1, "initialize", json!({ "capabilities": {} })),
+        notification(
+            "textDocument/didOpen",
+            json!({ "textDocument": { "uri": URI, "languageId": "milo", "version": 1, "text": CODE } }),
+        ),
+        request(2, "textDocument/rename", keyword),
+        request(3, "textDocument/rename", name),
+        request(4, "shutdown", Value::Null),
+        notification("exit", Value::Null),
+    ]);
+
+    assert_eq!(
+        response(&received, 2)["error"]["message"],
+        "`match` isn't a valid name"
+    );
+    assert!(response(&received, 3)["result"]["changes"][URI].is_array());
+}
+
+#[test]
+fn exits_with_an_error_without_shutdown() {
+    let (code, _) = session(&[
+        request(1, "initialize", json!({ "capabilities": {} })),
+        notification("exit", Value::Null),
+    ]);
+
+    assert_eq!(code, Some(1));
+}
+
+#[test]
+fn finds_definitions_after_a_method_on_one_line() {
+    let code = "\
+struct Point { x, y }
+
+impl Point {
+    fn sum(self) { return self.x + self.y }
+}
+
+origin = Point { x: 0, y: 0 }
+print(origin.sum())
+";
+
+    let (_, received) = session(&[
+        request(1, "initialize", json!({ "capabilities": {} })),
+        notification(
+            "textDocument/didOpen",
+            json!({ "textDocument": { "uri": URI, "languageId": "milo", "version": 1, "text": code } }),
+        ),
+        request(2, "textDocument/definition", position(7, 6)),
+        request(3, "shutdown", Value::Null),
+        notification("exit", Value::Null),
+    ]);
+
+    assert_eq!(
+        response(&received, 2)["result"],
+        json!([{
+            "uri": URI,
+            "range": {
+                "start": { "line": 6, "character": 0 },
+                "end": { "line": 6, "character": 6 },
+            },
+        }])
+    );
+}
@@ -0,0 +1,88 @@
+#![cfg(unix)]
+
+use std::{
+    env, fs,
+    os::unix::fs::symlink,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+// A directory of its own for each test, with a `sandbox` directory inside of it.
+fn directory(name: &str) -> PathBuf {
+    let directory =
+        env::temp_dir().join(format!("milo_permissions_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(directory.join("sandbox")).unwrap();
+    directory
+}
+
+// Runs `code` from `directory` with write access to its sandbox, and returns what it printed.
+fn run_in_sandbox(directory: &Path, code: &str) -> String {
+    fs::write(directory.join("script.milo"), code).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_milo"))
+        .current_dir(directory)
+        .args(["--allow-write=sandbox", "script.milo"])
+        .output()
+        .expect("milo should run");
+
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn dangling_symlinks_cannot_leave_the_sandbox() {
+    let directory = directory("dangling");
+    symlink(
+        directory.join("outside.txt"),
+        directory.join("sandbox/link.txt"),
+    )
+    .unwrap();
+
+    let printed = run_in_sandbox(
+        &directory,
+        "\
+import fs
+try {
+    fs.write_file(\"sandbox/link.txt\", \"escaped\")
+} catch error {
+    print(error)
+}
+",
+    );
+
+    assert_eq!(
+        printed,
+        "permission denied: requires --allow-write to write to `sandbox/link.txt`\n"
+    );
+    assert!(!directory.join("outside.txt").exists());
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn symlinks_that_loop_are_denied() {
+    let directory = directory("loop");
+    symlink("b", directory.join("sandbox/a")).unwrap();
+    symlink("a", directory.join("sandbox/b")).unwrap();
+
+    let printed = run_in_sandbox(
+        &directory,
+        "\
+import fs
+try {
+    fs.write_file(\"sandbox/a\", \"x\")
+} catch error {
+    print(error)
+}
+fs.write_file(\"sandbox/file.txt\", \"inside\")
+",
+    );
+
+    assert_eq!(
+        printed,
+        "permission denied: requires --allow-write to write to `sandbox/a`\n"
+    );
+    assert!(directory.join("sandbox/file.txt").exists());
+
+    fs::remove_dir_all(directory).unwrap();
+}